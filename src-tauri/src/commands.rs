@@ -1,6 +1,14 @@
 use crate::db::Database;
+use crate::formatters;
 use crate::models::*;
+use crate::services::analytics_service::AnalyticsService;
+use crate::services::budget_service::BudgetService;
+use crate::services::export_service::ExportService;
+use crate::services::import_service::ImportService;
+use crate::services::printer_service::{PrinterService, PrinterTarget};
+use crate::services::recurring_service::RecurringService;
 use crate::services::report_service::ReportService;
+use crate::services::report_template_service::ReportTemplateService;
 use crate::services::session_service::SessionService;
 use crate::services::transaction_service::TransactionService;
 use tauri::State;
@@ -129,14 +137,22 @@ pub fn create_transaction(
     db: State<Database>,
 ) -> Result<TransactionResponse, String> {
     match TransactionService::create_transaction(&db, request) {
-        Ok(transaction) => Ok(TransactionResponse {
-            success: true,
-            data: Some(transaction),
-            error: None,
-        }),
+        Ok(transaction) => {
+            // Budget warnings never undo a recorded transaction
+            let warnings =
+                BudgetService::get_budget_warnings(&db, &transaction).unwrap_or_default();
+
+            Ok(TransactionResponse {
+                success: true,
+                data: Some(transaction),
+                warnings,
+                error: None,
+            })
+        }
         Err(e) => Ok(TransactionResponse {
             success: false,
             data: None,
+            warnings: vec![],
             error: Some(format!("Error al crear transacción: {}", e)),
         }),
     }
@@ -151,11 +167,13 @@ pub fn get_transaction_by_id(
         Ok(transaction) => Ok(TransactionResponse {
             success: true,
             data: Some(transaction),
+            warnings: vec![],
             error: None,
         }),
         Err(e) => Ok(TransactionResponse {
             success: false,
             data: None,
+            warnings: vec![],
             error: Some(format!("Error al obtener transacción: {}", e)),
         }),
     }
@@ -166,28 +184,21 @@ pub fn get_transactions(
     request: GetTransactionsRequest,
     db: State<Database>,
 ) -> Result<TransactionsListResponse, String> {
-    let limit = request.limit.unwrap_or(50);
-    let offset = request.offset.unwrap_or(0);
-
-    match TransactionService::get_transactions(
-        &db,
-        request.session_id,
-        request.transaction_type,
-        request.start_date,
-        request.end_date,
-        limit,
-        offset,
-    ) {
-        Ok((transactions, total_count)) => Ok(TransactionsListResponse {
+    match TransactionService::get_transactions(&db, request) {
+        Ok(page) => Ok(TransactionsListResponse {
             success: true,
-            data: transactions,
-            total_count,
+            data: page.items,
+            total_count: page.total_count,
+            next_cursor: page.next_cursor,
+            prev_cursor: page.prev_cursor,
             error: None,
         }),
         Err(e) => Ok(TransactionsListResponse {
             success: false,
             data: vec![],
-            total_count: 0,
+            total_count: None,
+            next_cursor: None,
+            prev_cursor: None,
             error: Some(format!("Error al obtener transacciones: {}", e)),
         }),
     }
@@ -197,21 +208,26 @@ pub fn get_transactions(
 pub fn get_recent_transactions(
     session_id: Option<i64>,
     limit: Option<i64>,
+    cursor: Option<String>,
     db: State<Database>,
 ) -> Result<TransactionsListResponse, String> {
     let limit = limit.unwrap_or(5);
 
-    match TransactionService::get_recent_transactions(&db, session_id, limit) {
-        Ok(transactions) => Ok(TransactionsListResponse {
+    match TransactionService::get_recent_transactions(&db, session_id, limit, cursor) {
+        Ok(page) => Ok(TransactionsListResponse {
             success: true,
-            data: transactions,
-            total_count: 0, // Not relevant for recent transactions
+            data: page.items,
+            total_count: None, // Not relevant for recent transactions
+            next_cursor: page.next_cursor,
+            prev_cursor: page.prev_cursor,
             error: None,
         }),
         Err(e) => Ok(TransactionsListResponse {
             success: false,
             data: vec![],
-            total_count: 0,
+            total_count: None,
+            next_cursor: None,
+            prev_cursor: None,
             error: Some(format!("Error al obtener transacciones recientes: {}", e)),
         }),
     }
@@ -236,19 +252,34 @@ pub fn get_all_categories(db: State<Database>) -> Result<CategoriesListResponse,
 #[tauri::command]
 pub fn get_categories_by_type(
     category_type: String,
+    nested: Option<bool>,
     db: State<Database>,
-) -> Result<CategoriesListResponse, String> {
-    match TransactionService::get_categories_by_type(&db, category_type) {
-        Ok(categories) => Ok(CategoriesListResponse {
-            success: true,
-            data: categories,
-            error: None,
-        }),
-        Err(e) => Ok(CategoriesListResponse {
-            success: false,
-            data: vec![],
-            error: Some(format!("Error al obtener categorías: {}", e)),
-        }),
+) -> Result<serde_json::Value, String> {
+    let result = if nested.unwrap_or(false) {
+        TransactionService::get_category_tree(&db, category_type)
+            .map(|tree| serde_json::json!(tree))
+    } else {
+        TransactionService::get_categories_by_type(&db, category_type)
+            .map(|categories| serde_json::json!(categories))
+    };
+
+    match result {
+        Ok(categories) => {
+            let response = serde_json::json!({
+                "success": true,
+                "data": categories,
+                "error": null
+            });
+            Ok(response)
+        }
+        Err(e) => {
+            let response = serde_json::json!({
+                "success": false,
+                "data": [],
+                "error": format!("Error al obtener categorías: {}", e)
+            });
+            Ok(response)
+        }
     }
 }
 
@@ -256,9 +287,11 @@ pub fn get_categories_by_type(
 pub fn create_category(
     name: String,
     category_type: String,
+    parent_id: Option<i64>,
+    account_code: Option<String>,
     db: State<Database>,
 ) -> Result<CategoryResponse, String> {
-    match TransactionService::create_category(&db, name, category_type) {
+    match TransactionService::create_category(&db, name, category_type, parent_id, account_code) {
         Ok(category) => Ok(CategoryResponse {
             success: true,
             data: Some(category),
@@ -276,9 +309,10 @@ pub fn create_category(
 pub fn update_category(
     category_id: i64,
     name: String,
+    account_code: Option<String>,
     db: State<Database>,
 ) -> Result<CategoryResponse, String> {
-    match TransactionService::update_category(&db, category_id, name) {
+    match TransactionService::update_category(&db, category_id, name, account_code) {
         Ok(category) => Ok(CategoryResponse {
             success: true,
             data: Some(category),
@@ -292,6 +326,26 @@ pub fn update_category(
     }
 }
 
+#[tauri::command]
+pub fn move_category(
+    category_id: i64,
+    parent_id: Option<i64>,
+    db: State<Database>,
+) -> Result<CategoryResponse, String> {
+    match TransactionService::move_category(&db, category_id, parent_id) {
+        Ok(category) => Ok(CategoryResponse {
+            success: true,
+            data: Some(category),
+            error: None,
+        }),
+        Err(e) => Ok(CategoryResponse {
+            success: false,
+            data: None,
+            error: Some(format!("Error al mover categoría: {}", e)),
+        }),
+    }
+}
+
 #[tauri::command]
 pub fn delete_category(category_id: i64, db: State<Database>) -> Result<serde_json::Value, String> {
     match TransactionService::delete_category(&db, category_id) {
@@ -312,6 +366,71 @@ pub fn delete_category(category_id: i64, db: State<Database>) -> Result<serde_js
     }
 }
 
+#[tauri::command]
+pub fn get_inactive_categories(
+    category_type: Option<String>,
+    db: State<Database>,
+) -> Result<CategoriesListResponse, String> {
+    match TransactionService::get_inactive_categories(&db, category_type) {
+        Ok(categories) => Ok(CategoriesListResponse {
+            success: true,
+            data: categories,
+            error: None,
+        }),
+        Err(e) => Ok(CategoriesListResponse {
+            success: false,
+            data: vec![],
+            error: Some(format!("Error al obtener categorías inactivas: {}", e)),
+        }),
+    }
+}
+
+#[tauri::command]
+pub fn reactivate_category(
+    category_id: i64,
+    db: State<Database>,
+) -> Result<CategoryResponse, String> {
+    match TransactionService::reactivate_category(&db, category_id) {
+        Ok(category) => Ok(CategoryResponse {
+            success: true,
+            data: Some(category),
+            error: None,
+        }),
+        Err(e) => Ok(CategoryResponse {
+            success: false,
+            data: None,
+            error: Some(format!("Error al reactivar categoría: {}", e)),
+        }),
+    }
+}
+
+#[tauri::command]
+pub fn merge_categories(
+    source_id: i64,
+    target_id: i64,
+    performed_by: Option<String>,
+    db: State<Database>,
+) -> Result<serde_json::Value, String> {
+    match TransactionService::merge_categories(&db, source_id, target_id, performed_by) {
+        Ok(moved) => {
+            let response = serde_json::json!({
+                "success": true,
+                "transactions_moved": moved,
+                "error": null
+            });
+            Ok(response)
+        }
+        Err(e) => {
+            let response = serde_json::json!({
+                "success": false,
+                "transactions_moved": 0,
+                "error": format!("Error al fusionar categorías: {}", e)
+            });
+            Ok(response)
+        }
+    }
+}
+
 #[tauri::command]
 pub fn get_today_transactions_summary(db: State<Database>) -> Result<serde_json::Value, String> {
     match TransactionService::get_today_transactions_summary(&db) {
@@ -344,25 +463,24 @@ pub fn get_today_transactions_summary(db: State<Database>) -> Result<serde_json:
 
 #[tauri::command]
 pub fn search_transactions(
-    query: String,
-    limit: Option<i64>,
-    offset: Option<i64>,
+    request: GetTransactionsRequest,
     db: State<Database>,
-) -> Result<TransactionsListResponse, String> {
-    let limit = limit.unwrap_or(50);
-    let offset = offset.unwrap_or(0);
-
-    match TransactionService::search_transactions(&db, &query, limit, offset) {
-        Ok((transactions, total_count)) => Ok(TransactionsListResponse {
+) -> Result<SearchResultsResponse, String> {
+    match TransactionService::search_transactions(&db, request) {
+        Ok(page) => Ok(SearchResultsResponse {
             success: true,
-            data: transactions,
-            total_count,
+            data: page.items,
+            total_count: page.total_count,
+            next_cursor: page.next_cursor,
+            prev_cursor: page.prev_cursor,
             error: None,
         }),
-        Err(e) => Ok(TransactionsListResponse {
+        Err(e) => Ok(SearchResultsResponse {
             success: false,
             data: vec![],
-            total_count: 0,
+            total_count: None,
+            next_cursor: None,
+            prev_cursor: None,
             error: Some(format!("Error al buscar transacciones: {}", e)),
         }),
     }
@@ -378,23 +496,54 @@ pub fn update_transaction(
     amount: f64,
     concept: String,
     category_id: Option<i64>,
+    lines: Option<Vec<TransactionLineRequest>>,
     db: State<Database>,
 ) -> Result<TransactionResponse, String> {
-    match TransactionService::update_transaction(&db, transaction_id, amount, &concept, category_id)
-    {
+    match TransactionService::update_transaction(
+        &db,
+        transaction_id,
+        amount,
+        &concept,
+        category_id,
+        lines,
+    ) {
         Ok(transaction) => Ok(TransactionResponse {
             success: true,
             data: Some(transaction),
+            warnings: vec![],
             error: None,
         }),
         Err(e) => Ok(TransactionResponse {
             success: false,
             data: None,
+            warnings: vec![],
             error: Some(format!("Error al actualizar transacción: {}", e)),
         }),
     }
 }
 
+#[tauri::command]
+pub fn void_transaction(
+    transaction_id: i64,
+    performed_by: Option<String>,
+    db: State<Database>,
+) -> Result<TransactionResponse, String> {
+    match TransactionService::void_transaction(&db, transaction_id, performed_by) {
+        Ok(transaction) => Ok(TransactionResponse {
+            success: true,
+            data: Some(transaction),
+            warnings: vec![],
+            error: None,
+        }),
+        Err(e) => Ok(TransactionResponse {
+            success: false,
+            data: None,
+            warnings: vec![],
+            error: Some(format!("Error al anular transacción: {}", e)),
+        }),
+    }
+}
+
 #[tauri::command]
 pub fn delete_transaction(
     transaction_id: i64,
@@ -418,31 +567,454 @@ pub fn delete_transaction(
     }
 }
 
+// ============================================
+// Tag Commands
+// ============================================
+
+#[tauri::command]
+pub fn get_tags(db: State<Database>) -> Result<TagsListResponse, String> {
+    match TransactionService::get_tags(&db) {
+        Ok(tags) => Ok(TagsListResponse {
+            success: true,
+            data: tags,
+            error: None,
+        }),
+        Err(e) => Ok(TagsListResponse {
+            success: false,
+            data: vec![],
+            error: Some(format!("Error al obtener etiquetas: {}", e)),
+        }),
+    }
+}
+
+#[tauri::command]
+pub fn add_transaction_tags(
+    transaction_id: i64,
+    tags: Vec<String>,
+    db: State<Database>,
+) -> Result<TransactionResponse, String> {
+    match TransactionService::add_transaction_tags(&db, transaction_id, tags) {
+        Ok(transaction) => Ok(TransactionResponse {
+            success: true,
+            data: Some(transaction),
+            warnings: vec![],
+            error: None,
+        }),
+        Err(e) => Ok(TransactionResponse {
+            success: false,
+            data: None,
+            warnings: vec![],
+            error: Some(format!("Error al etiquetar transacción: {}", e)),
+        }),
+    }
+}
+
+#[tauri::command]
+pub fn remove_transaction_tag(
+    transaction_id: i64,
+    tag: String,
+    db: State<Database>,
+) -> Result<TransactionResponse, String> {
+    match TransactionService::remove_transaction_tag(&db, transaction_id, &tag) {
+        Ok(transaction) => Ok(TransactionResponse {
+            success: true,
+            data: Some(transaction),
+            warnings: vec![],
+            error: None,
+        }),
+        Err(e) => Ok(TransactionResponse {
+            success: false,
+            data: None,
+            warnings: vec![],
+            error: Some(format!("Error al quitar etiqueta: {}", e)),
+        }),
+    }
+}
+
+// ============================================
+// Budget Commands
+// ============================================
+
+#[tauri::command]
+pub fn create_budget(
+    request: CreateBudgetRequest,
+    db: State<Database>,
+) -> Result<BudgetResponse, String> {
+    match BudgetService::create_budget(&db, request) {
+        Ok(budget) => Ok(BudgetResponse {
+            success: true,
+            data: Some(budget),
+            error: None,
+        }),
+        Err(e) => Ok(BudgetResponse {
+            success: false,
+            data: None,
+            error: Some(format!("Error al crear presupuesto: {}", e)),
+        }),
+    }
+}
+
+#[tauri::command]
+pub fn update_budget(
+    budget_id: i64,
+    amount: f64,
+    block_on_exceed: Option<bool>,
+    db: State<Database>,
+) -> Result<BudgetResponse, String> {
+    match BudgetService::update_budget(&db, budget_id, amount, block_on_exceed) {
+        Ok(budget) => Ok(BudgetResponse {
+            success: true,
+            data: Some(budget),
+            error: None,
+        }),
+        Err(e) => Ok(BudgetResponse {
+            success: false,
+            data: None,
+            error: Some(format!("Error al actualizar presupuesto: {}", e)),
+        }),
+    }
+}
+
+#[tauri::command]
+pub fn delete_budget(budget_id: i64, db: State<Database>) -> Result<serde_json::Value, String> {
+    match BudgetService::delete_budget(&db, budget_id) {
+        Ok(_) => {
+            let response = serde_json::json!({
+                "success": true,
+                "error": null
+            });
+            Ok(response)
+        }
+        Err(e) => {
+            let response = serde_json::json!({
+                "success": false,
+                "error": format!("Error al eliminar presupuesto: {}", e)
+            });
+            Ok(response)
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_budget_status(
+    request: GetTransactionsRequest,
+    db: State<Database>,
+) -> Result<BudgetStatusListResponse, String> {
+    match BudgetService::get_budget_status(&db, request) {
+        Ok(statuses) => Ok(BudgetStatusListResponse {
+            success: true,
+            data: statuses,
+            error: None,
+        }),
+        Err(e) => Ok(BudgetStatusListResponse {
+            success: false,
+            data: vec![],
+            error: Some(format!("Error al obtener ejecución presupuestaria: {}", e)),
+        }),
+    }
+}
+
+// ============================================
+// Recurring Template Commands
+// ============================================
+
+#[tauri::command]
+pub fn create_recurring_template(
+    request: CreateRecurringTemplateRequest,
+    db: State<Database>,
+) -> Result<RecurringTemplateResponse, String> {
+    match RecurringService::create_template(&db, request) {
+        Ok(template) => Ok(RecurringTemplateResponse {
+            success: true,
+            data: Some(template),
+            error: None,
+        }),
+        Err(e) => Ok(RecurringTemplateResponse {
+            success: false,
+            data: None,
+            error: Some(format!("Error al crear plantilla: {}", e)),
+        }),
+    }
+}
+
+#[tauri::command]
+pub fn get_recurring_templates(
+    db: State<Database>,
+) -> Result<RecurringTemplatesListResponse, String> {
+    match RecurringService::get_templates(&db) {
+        Ok(templates) => Ok(RecurringTemplatesListResponse {
+            success: true,
+            data: templates,
+            error: None,
+        }),
+        Err(e) => Ok(RecurringTemplatesListResponse {
+            success: false,
+            data: vec![],
+            error: Some(format!("Error al obtener plantillas: {}", e)),
+        }),
+    }
+}
+
+#[tauri::command]
+pub fn delete_recurring_template(
+    template_id: i64,
+    db: State<Database>,
+) -> Result<serde_json::Value, String> {
+    match RecurringService::delete_template(&db, template_id) {
+        Ok(_) => {
+            let response = serde_json::json!({
+                "success": true,
+                "error": null
+            });
+            Ok(response)
+        }
+        Err(e) => {
+            let response = serde_json::json!({
+                "success": false,
+                "error": format!("Error al eliminar plantilla: {}", e)
+            });
+            Ok(response)
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_due_templates(db: State<Database>) -> Result<DueTemplatesListResponse, String> {
+    match RecurringService::get_due_templates(&db) {
+        Ok(templates) => Ok(DueTemplatesListResponse {
+            success: true,
+            data: templates,
+            error: None,
+        }),
+        Err(e) => Ok(DueTemplatesListResponse {
+            success: false,
+            data: vec![],
+            error: Some(format!("Error al obtener plantillas pendientes: {}", e)),
+        }),
+    }
+}
+
+#[tauri::command]
+pub fn post_recurring_template(
+    template_id: i64,
+    created_by: String,
+    db: State<Database>,
+) -> Result<TransactionResponse, String> {
+    match RecurringService::post_template(&db, template_id, &created_by) {
+        Ok(transaction) => {
+            // Budget warnings never undo a recorded transaction
+            let warnings =
+                BudgetService::get_budget_warnings(&db, &transaction).unwrap_or_default();
+
+            Ok(TransactionResponse {
+                success: true,
+                data: Some(transaction),
+                warnings,
+                error: None,
+            })
+        }
+        Err(e) => Ok(TransactionResponse {
+            success: false,
+            data: None,
+            warnings: vec![],
+            error: Some(format!("Error al registrar plantilla: {}", e)),
+        }),
+    }
+}
+
+// ============================================
+// Import Commands
+// ============================================
+
+#[tauri::command]
+pub fn import_transactions(
+    request: ImportTransactionsRequest,
+    db: State<Database>,
+) -> Result<ImportResponse, String> {
+    match ImportService::import_transactions(&db, request) {
+        Ok(result) => Ok(ImportResponse {
+            success: true,
+            data: Some(result),
+            error: None,
+        }),
+        Err(e) => Ok(ImportResponse {
+            success: false,
+            data: None,
+            error: Some(format!("Error al importar transacciones: {}", e)),
+        }),
+    }
+}
+
+// ============================================
+// Export Commands
+// ============================================
+
+#[tauri::command]
+pub fn export_data(request: ExportRequest, db: State<Database>) -> Result<ExportResponse, String> {
+    match ExportService::export(&db, request) {
+        Ok(result) => Ok(ExportResponse {
+            success: true,
+            data: Some(result),
+            error: None,
+        }),
+        Err(e) => Ok(ExportResponse {
+            success: false,
+            data: None,
+            error: Some(format!("Error al exportar datos: {}", e)),
+        }),
+    }
+}
+
+// ============================================
+// Analytics Commands
+// ============================================
+
+#[tauri::command]
+pub fn get_dashboard_analytics(
+    request: DashboardAnalyticsRequest,
+    db: State<Database>,
+) -> Result<DashboardAnalyticsResponse, String> {
+    match AnalyticsService::get_dashboard_analytics(&db, request) {
+        Ok(analytics) => Ok(DashboardAnalyticsResponse {
+            success: true,
+            data: Some(analytics),
+            error: None,
+        }),
+        Err(e) => Ok(DashboardAnalyticsResponse {
+            success: false,
+            data: None,
+            error: Some(format!("Error al obtener estadísticas: {}", e)),
+        }),
+    }
+}
+
 // ============================================
 // Report Commands
 // ============================================
 
 #[tauri::command]
-pub fn generate_report(
-    report_type: String,
+pub fn generate_report(spec: ReportSpec, db: State<Database>) -> Result<ReportResponse, String> {
+    match ReportService::generate_report(&db, &spec) {
+        Ok(file_path) => Ok(ReportResponse {
+            success: true,
+            file_path: Some(file_path.to_string_lossy().to_string()),
+            error: None,
+        }),
+        Err(e) => Ok(ReportResponse {
+            success: false,
+            file_path: None,
+            error: Some(e),
+        }),
+    }
+}
+
+#[tauri::command]
+pub fn preview_report(
+    spec: ReportSpec,
+    db: State<Database>,
+) -> Result<ReportPreviewResponse, String> {
+    match ReportService::build_document(&db, &spec) {
+        Ok(document) => Ok(ReportPreviewResponse {
+            success: true,
+            data: Some(document),
+            error: None,
+        }),
+        Err(e) => Ok(ReportPreviewResponse {
+            success: false,
+            data: None,
+            error: Some(e),
+        }),
+    }
+}
+
+#[tauri::command]
+pub fn get_operator_performance(
     start_date: String,
     end_date: String,
-    format: String,
-    download_path: Option<String>,
+    db: State<Database>,
+) -> Result<OperatorPerformanceResponse, String> {
+    match ReportService::get_operator_performance(&db, &start_date, &end_date) {
+        Ok(operators) => Ok(OperatorPerformanceResponse {
+            success: true,
+            data: operators,
+            error: None,
+        }),
+        Err(e) => Ok(OperatorPerformanceResponse {
+            success: false,
+            data: vec![],
+            error: Some(format!("Error al obtener desempeño por operador: {}", e)),
+        }),
+    }
+}
+
+// ============================================
+// Report Template Commands
+// ============================================
+
+#[tauri::command]
+pub fn create_report_template(
+    request: SaveReportTemplateRequest,
+    db: State<Database>,
+) -> Result<ReportTemplateResponse, String> {
+    match ReportTemplateService::create_template(&db, request) {
+        Ok(template) => Ok(ReportTemplateResponse {
+            success: true,
+            data: Some(template),
+            error: None,
+        }),
+        Err(e) => Ok(ReportTemplateResponse {
+            success: false,
+            data: None,
+            error: Some(e),
+        }),
+    }
+}
+
+#[tauri::command]
+pub fn update_report_template(
+    template_id: i64,
+    request: SaveReportTemplateRequest,
+    db: State<Database>,
+) -> Result<ReportTemplateResponse, String> {
+    match ReportTemplateService::update_template(&db, template_id, request) {
+        Ok(template) => Ok(ReportTemplateResponse {
+            success: true,
+            data: Some(template),
+            error: None,
+        }),
+        Err(e) => Ok(ReportTemplateResponse {
+            success: false,
+            data: None,
+            error: Some(e),
+        }),
+    }
+}
+
+#[tauri::command]
+pub fn get_report_templates(db: State<Database>) -> Result<ReportTemplatesListResponse, String> {
+    match ReportTemplateService::get_templates(&db) {
+        Ok(templates) => Ok(ReportTemplatesListResponse {
+            success: true,
+            data: templates,
+            error: None,
+        }),
+        Err(e) => Ok(ReportTemplatesListResponse {
+            success: false,
+            data: vec![],
+            error: Some(e),
+        }),
+    }
+}
+
+#[tauri::command]
+pub fn delete_report_template(
+    template_id: i64,
     db: State<Database>,
 ) -> Result<serde_json::Value, String> {
-    match ReportService::generate_report(
-        &db,
-        &report_type,
-        &start_date,
-        &end_date,
-        &format,
-        download_path.as_deref(),
-    ) {
-        Ok(file_path) => {
+    match ReportTemplateService::delete_template(&db, template_id) {
+        Ok(_) => {
             let response = serde_json::json!({
                 "success": true,
-                "file_path": file_path.to_string_lossy().to_string(),
                 "error": null
             });
             Ok(response)
@@ -450,7 +1022,6 @@ pub fn generate_report(
         Err(e) => {
             let response = serde_json::json!({
                 "success": false,
-                "file_path": null,
                 "error": e
             });
             Ok(response)
@@ -458,6 +1029,96 @@ pub fn generate_report(
     }
 }
 
+#[tauri::command]
+pub fn run_report_template(
+    template_id: i64,
+    format: Option<ReportFormat>,
+    download_path: Option<String>,
+    db: State<Database>,
+) -> Result<ReportResponse, String> {
+    match ReportTemplateService::run_template(&db, template_id, format, download_path) {
+        Ok(file_path) => Ok(ReportResponse {
+            success: true,
+            file_path: Some(file_path.to_string_lossy().to_string()),
+            error: None,
+        }),
+        Err(e) => Ok(ReportResponse {
+            success: false,
+            file_path: None,
+            error: Some(e),
+        }),
+    }
+}
+
+// ============================================
+// Printer Commands
+// ============================================
+
+#[tauri::command]
+pub fn print_transaction_receipt(
+    transaction_id: i64,
+    target: PrinterTargetRequest,
+    include_qr: Option<bool>,
+    db: State<Database>,
+) -> Result<serde_json::Value, String> {
+    let result = PrinterTarget::from_request(&target).and_then(|target| {
+        PrinterService::print_transaction_receipt(
+            &db,
+            transaction_id,
+            &target,
+            include_qr.unwrap_or(false),
+        )
+    });
+
+    match result {
+        Ok(bytes_written) => {
+            let response = serde_json::json!({
+                "success": true,
+                "bytes_written": bytes_written,
+                "error": null
+            });
+            Ok(response)
+        }
+        Err(e) => {
+            let response = serde_json::json!({
+                "success": false,
+                "bytes_written": 0,
+                "error": format!("Error al imprimir comprobante: {}", e)
+            });
+            Ok(response)
+        }
+    }
+}
+
+#[tauri::command]
+pub fn print_session_close(
+    session_id: i64,
+    target: PrinterTargetRequest,
+    db: State<Database>,
+) -> Result<serde_json::Value, String> {
+    let result = PrinterTarget::from_request(&target)
+        .and_then(|target| PrinterService::print_session_close(&db, session_id, &target));
+
+    match result {
+        Ok(bytes_written) => {
+            let response = serde_json::json!({
+                "success": true,
+                "bytes_written": bytes_written,
+                "error": null
+            });
+            Ok(response)
+        }
+        Err(e) => {
+            let response = serde_json::json!({
+                "success": false,
+                "bytes_written": 0,
+                "error": format!("Error al imprimir cierre de caja: {}", e)
+            });
+            Ok(response)
+        }
+    }
+}
+
 // ============================================
 // Backup Commands
 // ============================================
@@ -629,6 +1290,12 @@ pub fn delete_all_records() -> Result<serde_json::Value, String> {
         .map_err(|e| format!("Error abriendo base de datos: {}", e))?;
 
     // Delete all records from tables (in correct order to avoid foreign key constraints)
+    conn.execute("DELETE FROM transaction_lines", [])
+        .map_err(|e| format!("Error eliminando líneas de transacciones: {}", e))?;
+
+    conn.execute("DELETE FROM transaction_tags", [])
+        .map_err(|e| format!("Error eliminando etiquetas de transacciones: {}", e))?;
+
     conn.execute("DELETE FROM transactions", [])
         .map_err(|e| format!("Error eliminando transacciones: {}", e))?;
 
@@ -651,6 +1318,32 @@ pub fn delete_all_records() -> Result<serde_json::Value, String> {
     Ok(response)
 }
 
+// ============================================
+// Formatter Commands
+// ============================================
+
+#[tauri::command]
+pub fn amount_to_words(amount: f64, currency: String) -> Result<serde_json::Value, String> {
+    match formatters::amount_to_words(amount, &currency) {
+        Ok(words) => {
+            let response = serde_json::json!({
+                "success": true,
+                "data": words,
+                "error": null
+            });
+            Ok(response)
+        }
+        Err(e) => {
+            let response = serde_json::json!({
+                "success": false,
+                "data": null,
+                "error": e
+            });
+            Ok(response)
+        }
+    }
+}
+
 // ============================================
 // Test Command
 // ============================================
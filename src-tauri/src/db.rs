@@ -1,8 +1,98 @@
-use crate::models::{Category, DailySummary, Session, Transaction};
+use crate::models::{
+    Budget, Category, CategoryStat, DailySummary, GetTransactionsRequest, HeatmapCell,
+    ImportedSession, Page, RecurringTemplate, RelativeDateRange, ReportSpec, ReportTemplate,
+    SeriesPoint, Session, Tag, Transaction, TransactionLine, TransactionLineRequest,
+    TransactionSearchResult,
+};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, Value, ValueRef};
 use rusqlite::{Connection, Result, Row};
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
+/// Opaque position in a keyset-paginated listing: the sort key and id of the
+/// row at the edge of the current page, and whether the requested page lies
+/// before it
+struct PageCursor {
+    key: String,
+    id: i64,
+    backward: bool,
+}
+
+impl PageCursor {
+    fn encode(&self) -> String {
+        let direction = if self.backward { "b" } else { "a" };
+        format!("{}:{}:{}", direction, self.id, self.key)
+            .bytes()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    fn decode(cursor: &str) -> Result<Self> {
+        if !cursor.len().is_multiple_of(2) || !cursor.is_ascii() {
+            return Err(Self::invalid());
+        }
+
+        let bytes = (0..cursor.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&cursor[i..i + 2], 16))
+            .collect::<std::result::Result<Vec<u8>, _>>()
+            .map_err(|_| Self::invalid())?;
+        let text = String::from_utf8(bytes).map_err(|_| Self::invalid())?;
+
+        let mut parts = text.splitn(3, ':');
+        let backward = match parts.next() {
+            Some("a") => false,
+            Some("b") => true,
+            _ => return Err(Self::invalid()),
+        };
+        let id = parts
+            .next()
+            .and_then(|id| id.parse().ok())
+            .ok_or_else(Self::invalid)?;
+        let key = parts.next().ok_or_else(Self::invalid)?.to_string();
+
+        Ok(Self { key, id, backward })
+    }
+
+    fn invalid() -> rusqlite::Error {
+        rusqlite::Error::InvalidParameterName("Cursor de paginación inválido".to_string())
+    }
+
+    /// Next and previous cursors for a page read from `cursor` (or from the
+    /// start, skipping rows when `skipped`), where `has_more` means a row
+    /// was found past the page in the direction it was read
+    fn around<T>(
+        items: &[T],
+        cursor: Option<&PageCursor>,
+        has_more: bool,
+        skipped: bool,
+        key: impl Fn(&T) -> (String, i64),
+    ) -> (Option<String>, Option<String>) {
+        let (more_after, more_before) = match cursor {
+            Some(cursor) if cursor.backward => (true, has_more),
+            Some(_) => (has_more, true),
+            None => (has_more, skipped),
+        };
+
+        let at = |item: &T, backward: bool| {
+            let (key, id) = key(item);
+            Self { key, id, backward }.encode()
+        };
+
+        (
+            items
+                .last()
+                .filter(|_| more_after)
+                .map(|item| at(item, false)),
+            items
+                .first()
+                .filter(|_| more_before)
+                .map(|item| at(item, true)),
+        )
+    }
+}
+
 pub struct Database {
     conn: Arc<Mutex<Connection>>,
 }
@@ -68,6 +158,53 @@ impl Database {
             [],
         )?;
 
+        // Transaction lines - Distribución de una transacción entre categorías
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS transaction_lines (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                transaction_id INTEGER NOT NULL,
+                category_id INTEGER NOT NULL,
+                amount REAL NOT NULL,
+                FOREIGN KEY (transaction_id) REFERENCES transactions(id) ON DELETE CASCADE,
+                FOREIGN KEY (category_id) REFERENCES categories(id)
+            )",
+            [],
+        )?;
+
+        // Amount per category of every transaction: its lines when split,
+        // otherwise the whole amount under its single category
+        conn.execute(
+            "CREATE VIEW IF NOT EXISTS transaction_allocations AS
+             SELECT t.id AS transaction_id, t.category_id, t.amount
+             FROM transactions t
+             WHERE NOT EXISTS (SELECT 1 FROM transaction_lines l WHERE l.transaction_id = t.id)
+             UNION ALL
+             SELECT l.transaction_id, l.category_id, l.amount
+             FROM transaction_lines l",
+            [],
+        )?;
+
+        // Tags - Etiquetas libres (eventos, proyectos)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS tags (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+                created_at TEXT NOT NULL DEFAULT (datetime('now'))
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS transaction_tags (
+                transaction_id INTEGER NOT NULL,
+                tag_id INTEGER NOT NULL,
+                PRIMARY KEY (transaction_id, tag_id),
+                FOREIGN KEY (transaction_id) REFERENCES transactions(id) ON DELETE CASCADE,
+                FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+
         // Categories table - Categorías predefinidas
         conn.execute(
             "CREATE TABLE IF NOT EXISTS categories (
@@ -92,6 +229,72 @@ impl Database {
             [],
         )?;
 
+        // Budgets table - Presupuestos por categoría y período
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS budgets (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                category_id INTEGER NOT NULL,
+                period_type TEXT NOT NULL CHECK(period_type IN ('month', 'term')),
+                period_start TEXT NOT NULL,
+                period_end TEXT NOT NULL,
+                amount REAL NOT NULL,
+                block_on_exceed INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                FOREIGN KEY (category_id) REFERENCES categories(id)
+            )",
+            [],
+        )?;
+
+        // Recurring templates - Plantillas de transacciones periódicas
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS recurring_templates (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                type TEXT NOT NULL CHECK(type IN ('income', 'expense')),
+                amount REAL NOT NULL,
+                concept TEXT NOT NULL,
+                category_id INTEGER,
+                counterparty TEXT,
+                recurrence TEXT NOT NULL CHECK(recurrence IN ('monthly', 'weekly', 'fixed_day')),
+                recurrence_day INTEGER,
+                start_date TEXT NOT NULL,
+                last_posted_on TEXT,
+                is_active INTEGER NOT NULL DEFAULT 1,
+                created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                FOREIGN KEY (category_id) REFERENCES categories(id)
+            )",
+            [],
+        )?;
+
+        // Report templates - Reportes guardados para volver a generarse
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS report_templates (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                spec TEXT NOT NULL,
+                date_range TEXT CHECK(date_range IN ('last_month', 'current_term', 'year_to_date')),
+                output_folder TEXT,
+                last_run_at TEXT,
+                is_active INTEGER NOT NULL DEFAULT 1,
+                created_at TEXT NOT NULL DEFAULT (datetime('now'))
+            )",
+            [],
+        )?;
+
+        // Audit log - Registro de operaciones administrativas
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS audit_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                action TEXT NOT NULL,
+                entity_type TEXT NOT NULL,
+                entity_id INTEGER,
+                details TEXT,
+                performed_by TEXT,
+                created_at TEXT NOT NULL DEFAULT (datetime('now'))
+            )",
+            [],
+        )?;
+
         // Schema version tracking
         conn.execute(
             "CREATE TABLE IF NOT EXISTS schema_version (
@@ -118,6 +321,29 @@ impl Database {
             "CREATE INDEX IF NOT EXISTS idx_sessions_active ON sessions(is_active)",
             [],
         )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_transaction_lines_transaction
+             ON transaction_lines(transaction_id)",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_transaction_lines_category
+             ON transaction_lines(category_id)",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_transaction_tags_tag ON transaction_tags(tag_id)",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_budgets_category ON budgets(category_id, period_start)",
+            [],
+        )?;
+        conn.execute(
+            "CREATE UNIQUE INDEX IF NOT EXISTS idx_report_templates_active_name
+             ON report_templates(name COLLATE NOCASE) WHERE is_active = 1",
+            [],
+        )?;
 
         Ok(())
     }
@@ -168,6 +394,160 @@ impl Database {
             conn.execute("INSERT INTO schema_version (version) VALUES (1)", [])?;
         }
 
+        if current_version < 2 {
+            // Migration 2: Hierarchical categories with optional accounting codes
+            conn.execute(
+                "ALTER TABLE categories ADD COLUMN parent_id INTEGER REFERENCES categories(id)",
+                [],
+            )?;
+            conn.execute("ALTER TABLE categories ADD COLUMN account_code TEXT", [])?;
+            conn.execute(
+                "CREATE INDEX IF NOT EXISTS idx_categories_parent ON categories(parent_id)",
+                [],
+            )?;
+
+            conn.execute("INSERT INTO schema_version (version) VALUES (2)", [])?;
+        }
+
+        if current_version < 3 {
+            // Migration 3: Unique (name, type) among active categories.
+            // Fold existing duplicates into the oldest category first.
+            conn.execute_batch(
+                "CREATE TEMP TABLE category_duplicates AS
+                 SELECT c.id AS duplicate_id,
+                        (SELECT MIN(k.id) FROM categories k
+                         WHERE k.is_active = 1 AND k.type = c.type
+                         AND k.name = c.name COLLATE NOCASE) AS keep_id
+                 FROM categories c
+                 WHERE c.is_active = 1;
+                 DELETE FROM category_duplicates WHERE duplicate_id = keep_id;
+
+                 UPDATE transactions
+                 SET category_id = (SELECT keep_id FROM category_duplicates
+                                    WHERE duplicate_id = transactions.category_id)
+                 WHERE category_id IN (SELECT duplicate_id FROM category_duplicates);
+                 UPDATE categories
+                 SET parent_id = (SELECT keep_id FROM category_duplicates
+                                  WHERE duplicate_id = categories.parent_id)
+                 WHERE parent_id IN (SELECT duplicate_id FROM category_duplicates);
+                 UPDATE categories SET is_active = 0
+                 WHERE id IN (SELECT duplicate_id FROM category_duplicates);
+
+                 DROP TABLE category_duplicates;
+
+                 CREATE UNIQUE INDEX IF NOT EXISTS idx_categories_active_name
+                 ON categories(name COLLATE NOCASE, type) WHERE is_active = 1;
+
+                 INSERT INTO schema_version (version) VALUES (3);",
+            )?;
+        }
+
+        if current_version < 4 {
+            // Migration 4: Counterparty (proveedor o pagador) on transactions
+            conn.execute("ALTER TABLE transactions ADD COLUMN counterparty TEXT", [])?;
+
+            conn.execute("INSERT INTO schema_version (version) VALUES (4)", [])?;
+        }
+
+        if current_version < 5 {
+            // Migration 5: Full-text index over transactions, accent-insensitive,
+            // kept in sync by triggers
+            conn.execute_batch(
+                "CREATE VIRTUAL TABLE IF NOT EXISTS transactions_fts USING fts5(
+                     transaction_number, concept, counterparty,
+                     content = 'transactions',
+                     content_rowid = 'id',
+                     tokenize = 'unicode61 remove_diacritics 2',
+                     prefix = '2 3'
+                 );
+
+                 CREATE TRIGGER IF NOT EXISTS transactions_fts_insert
+                 AFTER INSERT ON transactions BEGIN
+                     INSERT INTO transactions_fts (rowid, transaction_number, concept, counterparty)
+                     VALUES (new.id, new.transaction_number, new.concept, new.counterparty);
+                 END;
+
+                 CREATE TRIGGER IF NOT EXISTS transactions_fts_delete
+                 AFTER DELETE ON transactions BEGIN
+                     INSERT INTO transactions_fts
+                         (transactions_fts, rowid, transaction_number, concept, counterparty)
+                     VALUES ('delete', old.id, old.transaction_number, old.concept, old.counterparty);
+                 END;
+
+                 CREATE TRIGGER IF NOT EXISTS transactions_fts_update
+                 AFTER UPDATE OF transaction_number, concept, counterparty ON transactions BEGIN
+                     INSERT INTO transactions_fts
+                         (transactions_fts, rowid, transaction_number, concept, counterparty)
+                     VALUES ('delete', old.id, old.transaction_number, old.concept, old.counterparty);
+                     INSERT INTO transactions_fts (rowid, transaction_number, concept, counterparty)
+                     VALUES (new.id, new.transaction_number, new.concept, new.counterparty);
+                 END;
+
+                 INSERT INTO transactions_fts (transactions_fts) VALUES ('rebuild');
+
+                 INSERT INTO schema_version (version) VALUES (5);",
+            )?;
+        }
+
+        if current_version < 6 {
+            // Migration 6: Covering index for the dashboard analytics, which
+            // aggregate amounts by type over a date range
+            conn.execute_batch(
+                "CREATE INDEX IF NOT EXISTS idx_transactions_date_type_amount
+                 ON transactions(created_at, type, amount);
+
+                 INSERT INTO schema_version (version) VALUES (6);",
+            )?;
+        }
+
+        if current_version < 7 {
+            // Migration 7: Lines left behind by deleted transactions; foreign
+            // keys are not enforced, so they were never deleted by cascade
+            conn.execute_batch(
+                "DELETE FROM transaction_lines
+                 WHERE transaction_id NOT IN (SELECT id FROM transactions);
+
+                 INSERT INTO schema_version (version) VALUES (7);",
+            )?;
+        }
+
+        if current_version < 8 {
+            // Migration 8: Tag links left behind by deleted transactions, for
+            // the same reason
+            conn.execute_batch(
+                "DELETE FROM transaction_tags
+                 WHERE transaction_id NOT IN (SELECT id FROM transactions);
+
+                 INSERT INTO schema_version (version) VALUES (8);",
+            )?;
+        }
+
+        if current_version < 9 {
+            // Migration 9: Payment method and voiding. Voided transactions are
+            // kept for the record but no longer count in any total.
+            conn.execute_batch(
+                "ALTER TABLE transactions ADD COLUMN payment_method TEXT NOT NULL DEFAULT 'cash';
+                 ALTER TABLE transactions ADD COLUMN voided_at TEXT;
+
+                 INSERT INTO schema_version (version) VALUES (9);",
+            )?;
+        }
+
+        if current_version < 10 {
+            // Migration 10: Report filters take a list of categories; saved
+            // templates with a single one are moved to the list
+            conn.execute_batch(
+                "UPDATE report_templates
+                 SET spec = json_remove(
+                     json_set(spec, '$.filters.category_ids',
+                              json_array(json_extract(spec, '$.filters.category_id'))),
+                     '$.filters.category_id')
+                 WHERE json_extract(spec, '$.filters.category_id') IS NOT NULL;
+
+                 INSERT INTO schema_version (version) VALUES (10);",
+            )?;
+        }
+
         Ok(())
     }
 
@@ -237,6 +617,7 @@ impl Database {
     }
 
     // Transaction operations
+    #[allow(clippy::too_many_arguments)]
     pub fn create_transaction(
         &self,
         session_id: i64,
@@ -244,31 +625,217 @@ impl Database {
         amount: f64,
         concept: &str,
         category_id: Option<i64>,
+        counterparty: Option<&str>,
+        payment_method: &str,
         created_by: &str,
+        lines: &[TransactionLineRequest],
     ) -> Result<Transaction> {
-        let conn = self.conn.lock().unwrap();
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
 
         // Generate transaction number (TR-XXXX)
-        let transaction_number = format!("TR-{}", self.generate_transaction_number(&conn)?);
+        let transaction_number = format!("TR-{}", self.generate_transaction_number(&tx)?);
 
-        conn.execute(
+        tx.execute(
             "INSERT INTO transactions 
-             (session_id, transaction_number, type, amount, concept, category_id, created_by) 
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            [
-                &session_id.to_string(),
-                &transaction_number,
+             (session_id, transaction_number, type, amount, concept, category_id, counterparty,
+              payment_method, created_by) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            rusqlite::params![
+                session_id,
+                transaction_number,
                 transaction_type,
-                &amount.to_string(),
+                amount,
                 concept,
-                &category_id.map(|id| id.to_string()).unwrap_or_default(),
+                category_id,
+                counterparty,
+                payment_method,
                 created_by,
             ],
         )?;
 
-        let id = conn.last_insert_rowid();
+        let id = tx.last_insert_rowid();
+        Self::insert_transaction_lines(&tx, id, lines)?;
 
-        self.get_transaction_by_id_internal(&conn, id)
+        let transaction = self.get_transaction_by_id_internal(&tx, id)?;
+        tx.commit()?;
+
+        Ok(transaction)
+    }
+
+    /// Replace the category lines of a transaction (an empty slice removes the split)
+    pub fn replace_transaction_lines(
+        conn: &Connection,
+        transaction_id: i64,
+        lines: &[TransactionLineRequest],
+    ) -> Result<()> {
+        conn.execute(
+            "DELETE FROM transaction_lines WHERE transaction_id = ?1",
+            [transaction_id],
+        )?;
+        Self::insert_transaction_lines(conn, transaction_id, lines)
+    }
+
+    fn insert_transaction_lines(
+        conn: &Connection,
+        transaction_id: i64,
+        lines: &[TransactionLineRequest],
+    ) -> Result<()> {
+        let mut stmt = conn.prepare(
+            "INSERT INTO transaction_lines (transaction_id, category_id, amount)
+             VALUES (?1, ?2, ?3)",
+        )?;
+
+        for line in lines {
+            stmt.execute(rusqlite::params![
+                transaction_id,
+                line.category_id,
+                line.amount
+            ])?;
+        }
+
+        Ok(())
+    }
+
+    /// Fill in the category lines and tags of already loaded transactions
+    pub fn load_transaction_details(
+        conn: &Connection,
+        transactions: &mut [Transaction],
+    ) -> Result<()> {
+        if transactions.is_empty() {
+            return Ok(());
+        }
+
+        let ids = serde_json::to_string(&transactions.iter().map(|t| t.id).collect::<Vec<_>>())
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+
+        let mut stmt = conn.prepare(
+            "SELECT l.id, l.transaction_id, l.category_id, c.name, l.amount
+             FROM transaction_lines l
+             LEFT JOIN categories c ON l.category_id = c.id
+             WHERE l.transaction_id IN (SELECT value FROM json_each(?1))
+             ORDER BY l.id",
+        )?;
+        let lines = stmt
+            .query_map([&ids], |row| {
+                Ok(TransactionLine {
+                    id: row.get(0)?,
+                    transaction_id: row.get(1)?,
+                    category_id: row.get(2)?,
+                    category_name: row.get(3)?,
+                    amount: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        for line in lines {
+            if let Some(transaction) = transactions
+                .iter_mut()
+                .find(|t| t.id == line.transaction_id)
+            {
+                transaction.lines.push(line);
+            }
+        }
+
+        let mut stmt = conn.prepare(
+            "SELECT tt.transaction_id, g.name
+             FROM transaction_tags tt
+             JOIN tags g ON tt.tag_id = g.id
+             WHERE tt.transaction_id IN (SELECT value FROM json_each(?1))
+             ORDER BY g.name",
+        )?;
+        let tags = stmt
+            .query_map([&ids], |row| Ok((row.get::<_, i64>(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<(i64, String)>>>()?;
+
+        for (transaction_id, tag) in tags {
+            if let Some(transaction) = transactions.iter_mut().find(|t| t.id == transaction_id) {
+                transaction.tags.push(tag);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write imported days as closed sessions with their transactions, creating
+    /// the new categories they reference. Either everything is written or nothing.
+    pub fn import_sessions(&self, sessions: &[ImportedSession], details: &str) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let mut new_categories: HashMap<(String, String), i64> = HashMap::new();
+        let mut imported = 0;
+
+        for session in sessions {
+            tx.execute(
+                "INSERT INTO sessions
+                 (operator_name, opening_amount, closing_amount, opened_at, closed_at, is_active)
+                 VALUES (?1, ?2, ?3, ?4, ?5, 0)",
+                rusqlite::params![
+                    session.operator_name,
+                    session.opening_amount,
+                    session.closing_amount,
+                    session.opened_at,
+                    session.closed_at,
+                ],
+            )?;
+            let session_id = tx.last_insert_rowid();
+
+            for transaction in &session.transactions {
+                let category_id = match &transaction.new_category {
+                    Some(name) => {
+                        let key = (name.to_lowercase(), transaction.transaction_type.clone());
+                        match new_categories.get(&key) {
+                            Some(id) => Some(*id),
+                            None => {
+                                tx.execute(
+                                    "INSERT INTO categories (name, type, is_active) VALUES (?1, ?2, 1)",
+                                    [name, &transaction.transaction_type],
+                                )?;
+                                let id = tx.last_insert_rowid();
+                                new_categories.insert(key, id);
+                                Some(id)
+                            }
+                        }
+                    }
+                    None => transaction.category_id,
+                };
+
+                let transaction_number = format!("TR-{}", self.generate_transaction_number(&tx)?);
+                tx.execute(
+                    "INSERT INTO transactions
+                     (session_id, transaction_number, type, amount, concept, category_id,
+                      created_at, created_by)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    rusqlite::params![
+                        session_id,
+                        transaction_number,
+                        transaction.transaction_type,
+                        transaction.amount,
+                        transaction.concept,
+                        category_id,
+                        transaction.created_at,
+                        transaction.created_by,
+                    ],
+                )?;
+                imported += 1;
+            }
+        }
+
+        Self::record_audit(
+            &tx,
+            "import",
+            "transaction",
+            None,
+            &format!(
+                "{} ({} transacciones en {} sesiones)",
+                details,
+                imported,
+                sessions.len()
+            ),
+            None,
+        )?;
+
+        tx.commit()
     }
 
     fn generate_transaction_number(&self, conn: &Connection) -> Result<String> {
@@ -284,138 +851,686 @@ impl Database {
     }
 
     fn get_transaction_by_id_internal(&self, conn: &Connection, id: i64) -> Result<Transaction> {
-        conn.query_row(
+        let transaction = conn.query_row(
             "SELECT 
                 t.id, t.session_id, t.transaction_number, t.type, t.amount, 
-                t.concept, t.category_id, c.name as category_name, t.created_at, t.created_by
+                t.concept, t.category_id, c.name as category_name, t.created_at, t.created_by,
+                t.counterparty, t.payment_method, t.voided_at
              FROM transactions t
              LEFT JOIN categories c ON t.category_id = c.id
              WHERE t.id = ?1",
             [id],
             |row| self.row_to_transaction(row),
-        )
+        )?;
+
+        let mut transactions = [transaction];
+        Self::load_transaction_details(conn, &mut transactions)?;
+        let [transaction] = transactions;
+
+        Ok(transaction)
     }
 
-    pub fn get_transactions(
-        &self,
-        session_id: Option<i64>,
-        transaction_type: Option<&str>,
-        start_date: Option<&str>,
-        end_date: Option<&str>,
-        limit: i64,
-        offset: i64,
-    ) -> Result<Vec<Transaction>> {
+    /// Transactions matching a structured query. With a cursor the page is
+    /// read by keyset on (created_at, id), which requires sorting by date;
+    /// otherwise LIMIT/OFFSET is used. The total count of the filter is
+    /// computed on the first page only, unless asked for.
+    pub fn query_transactions(&self, query: &GetTransactionsRequest) -> Result<Page<Transaction>> {
         let conn = self.conn.lock().unwrap();
 
-        let mut query = String::from(
+        let (mut filter, mut params) = Self::transaction_filter(query);
+        let mut order_by = Self::transaction_order(query)?;
+
+        let total_count = if query.include_total.unwrap_or(query.cursor.is_none()) {
+            Some(conn.query_row(
+                &format!(
+                    "SELECT COUNT(*)
+                     FROM transactions t
+                     LEFT JOIN categories c ON t.category_id = c.id
+                     WHERE 1=1{}",
+                    filter
+                ),
+                rusqlite::params_from_iter(params.iter()),
+                |row| row.get(0),
+            )?)
+        } else {
+            None
+        };
+
+        let keyset = query.sort_by.as_deref().unwrap_or("date") == "date";
+        let cursor = query
+            .cursor
+            .as_deref()
+            .map(PageCursor::decode)
+            .transpose()?;
+
+        if let Some(cursor) = &cursor {
+            if !keyset {
+                return Err(rusqlite::Error::InvalidParameterName(
+                    "La paginación por cursor requiere ordenar por fecha".to_string(),
+                ));
+            }
+
+            // Scan away from the cursor row; a previous page is read in reverse
+            let descending = query.sort_direction.as_deref() != Some("asc");
+            let scan_desc = descending != cursor.backward;
+            let (op, dir) = if scan_desc {
+                ("<", "DESC")
+            } else {
+                (">", "ASC")
+            };
+
+            filter.push_str(&format!(" AND (t.created_at, t.id) {} (?, ?)", op));
+            params.push(Value::Text(cursor.key.clone()));
+            params.push(Value::Integer(cursor.id));
+            order_by = format!("t.created_at {}, t.id {}", dir, dir);
+        }
+
+        // One extra row tells whether there is another page
+        let limit = query.limit.unwrap_or(50);
+        let offset = if cursor.is_some() {
+            0
+        } else {
+            query.offset.unwrap_or(0)
+        };
+        params.push(Value::Integer(limit + 1));
+        params.push(Value::Integer(offset));
+
+        let mut stmt = conn.prepare(&format!(
             "SELECT 
                 t.id, t.session_id, t.transaction_number, t.type, t.amount, 
-                t.concept, t.category_id, c.name as category_name, t.created_at, t.created_by
+                t.concept, t.category_id, c.name as category_name, t.created_at, t.created_by,
+                t.counterparty, t.payment_method, t.voided_at
              FROM transactions t
              LEFT JOIN categories c ON t.category_id = c.id
-             WHERE 1=1",
-        );
+             WHERE 1=1{}
+             ORDER BY {}
+             LIMIT ? OFFSET ?",
+            filter, order_by
+        ))?;
+
+        let mut transactions = stmt
+            .query_map(rusqlite::params_from_iter(params.iter()), |row| {
+                self.row_to_transaction(row)
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        let has_more = transactions.len() as i64 > limit;
+        transactions.truncate(limit as usize);
+        if cursor.as_ref().is_some_and(|c| c.backward) {
+            transactions.reverse();
+        }
+        Self::load_transaction_details(&conn, &mut transactions)?;
 
-        let mut params: Vec<String> = Vec::new();
+        let (next_cursor, prev_cursor) = if keyset {
+            PageCursor::around(&transactions, cursor.as_ref(), has_more, offset > 0, |t| {
+                (t.created_at.clone(), t.id)
+            })
+        } else {
+            (None, None)
+        };
+
+        Ok(Page {
+            items: transactions,
+            total_count,
+            next_cursor,
+            prev_cursor,
+        })
+    }
 
-        if let Some(id) = session_id {
-            query.push_str(" AND t.session_id = ?");
-            params.push(id.to_string());
+    /// WHERE clause (each condition prefixed with AND) and bound values for a
+    /// transaction query over `transactions t LEFT JOIN categories c`
+    pub fn transaction_filter(query: &GetTransactionsRequest) -> (String, Vec<Value>) {
+        let mut filter = String::new();
+        let mut params: Vec<Value> = Vec::new();
+
+        if let Some(id) = query.session_id {
+            filter.push_str(" AND t.session_id = ?");
+            params.push(Value::Integer(id));
         }
 
-        if let Some(t) = transaction_type {
-            query.push_str(" AND t.type = ?");
-            params.push(t.to_string());
+        if let Some(t) = &query.transaction_type {
+            filter.push_str(" AND t.type = ?");
+            params.push(Value::Text(t.clone()));
         }
 
-        if let Some(start) = start_date {
-            query.push_str(" AND date(t.created_at) >= date(?)");
-            params.push(start.to_string());
+        if let Some(start) = &query.start_date {
+            filter.push_str(&format!(
+                " AND {}",
+                Self::local_day_from("t.created_at", "?")
+            ));
+            params.push(Value::Text(start.clone()));
         }
 
-        if let Some(end) = end_date {
-            query.push_str(" AND date(t.created_at) <= date(?)");
-            params.push(end.to_string());
+        if let Some(end) = &query.end_date {
+            filter.push_str(&format!(
+                " AND {}",
+                Self::local_day_until("t.created_at", "?")
+            ));
+            params.push(Value::Text(end.clone()));
         }
 
-        query.push_str(" ORDER BY t.created_at DESC LIMIT ? OFFSET ?");
-        params.push(limit.to_string());
-        params.push(offset.to_string());
+        // Categories include their subcategories and the lines of split transactions
+        if let Some(category_ids) = query.category_ids.as_ref().filter(|ids| !ids.is_empty()) {
+            filter.push_str(
+                " AND t.id IN (
+                     WITH RECURSIVE subtree(id) AS (
+                         SELECT value FROM json_each(?)
+                         UNION
+                         SELECT sc.id FROM categories sc JOIN subtree ON sc.parent_id = subtree.id
+                     )
+                     SELECT transaction_id FROM transaction_allocations
+                     WHERE category_id IN (SELECT id FROM subtree)
+                 )",
+            );
+            params.push(Value::Text(
+                serde_json::to_string(category_ids).unwrap_or_else(|_| "[]".to_string()),
+            ));
+        }
 
-        let mut stmt = conn.prepare(&query)?;
-        let param_refs: Vec<&dyn rusqlite::ToSql> =
-            params.iter().map(|p| p as &dyn rusqlite::ToSql).collect();
+        if let Some(min) = query.min_amount {
+            filter.push_str(" AND t.amount >= ?");
+            params.push(Value::Real(min));
+        }
 
-        let transactions =
-            stmt.query_map(param_refs.as_slice(), |row| self.row_to_transaction(row))?;
+        if let Some(max) = query.max_amount {
+            filter.push_str(" AND t.amount <= ?");
+            params.push(Value::Real(max));
+        }
 
-        transactions.collect::<Result<Vec<_>>>()
-    }
+        if let Some(created_by) = query.created_by.as_deref().map(str::trim)
+            && !created_by.is_empty()
+        {
+            filter.push_str(" AND t.created_by = ? COLLATE NOCASE");
+            params.push(Value::Text(created_by.to_string()));
+        }
 
-    pub fn get_daily_summary(&self, date: &str) -> Result<DailySummary> {
-        let conn = self.conn.lock().unwrap();
+        if let Some(counterparty) = query.counterparty.as_deref().map(str::trim)
+            && !counterparty.is_empty()
+        {
+            filter.push_str(" AND t.counterparty LIKE ? ESCAPE '\\'");
+            params.push(Value::Text(format!(
+                "%{}%",
+                Self::escape_like(counterparty)
+            )));
+        }
 
-        let (total_income, income_count): (f64, i64) = conn.query_row(
-            "SELECT COALESCE(SUM(amount), 0), COUNT(*) 
-             FROM transactions 
-             WHERE type = 'income' AND date(created_at) = date(?)",
-            [date],
-            |row| Ok((row.get(0)?, row.get(1)?)),
-        )?;
+        if let Some(text) = query.text.as_deref()
+            && let Some(match_query) = Self::fts_match_query(text)
+        {
+            filter.push_str(
+                " AND t.id IN (SELECT rowid FROM transactions_fts WHERE transactions_fts MATCH ?)",
+            );
+            params.push(Value::Text(match_query));
+        }
 
-        let (total_expense, expense_count): (f64, i64) = conn.query_row(
-            "SELECT COALESCE(SUM(amount), 0), COUNT(*) 
-             FROM transactions 
-             WHERE type = 'expense' AND date(created_at) = date(?)",
-            [date],
-            |row| Ok((row.get(0)?, row.get(1)?)),
-        )?;
+        if let Some(tags) = query.tags.as_ref().filter(|tags| !tags.is_empty()) {
+            filter.push_str(
+                " AND t.id IN (
+                     SELECT tt.transaction_id FROM transaction_tags tt
+                     JOIN tags g ON tt.tag_id = g.id
+                     WHERE g.name IN (SELECT value FROM json_each(?))
+                     GROUP BY tt.transaction_id
+                     HAVING COUNT(DISTINCT g.id) = ?
+                 )",
+            );
+            params.push(Value::Text(
+                serde_json::to_string(tags).unwrap_or_else(|_| "[]".to_string()),
+            ));
+            params.push(Value::Integer(tags.len() as i64));
+        }
 
-        // Get current balance from active session
-        let current_balance: f64 = conn
-            .query_row(
-                "SELECT COALESCE(
-                opening_amount + (
-                    SELECT COALESCE(SUM(CASE WHEN type = 'income' THEN amount ELSE -amount END), 0)
-                    FROM transactions WHERE session_id = sessions.id
-                ), 0)
-             FROM sessions 
-             WHERE is_active = 1",
-                [],
-                |row| row.get(0),
-            )
-            .unwrap_or(0.0);
+        if let Some(payment_method) = &query.payment_method {
+            filter.push_str(" AND t.payment_method = ?");
+            params.push(Value::Text(payment_method.clone()));
+        }
 
-        Ok(DailySummary {
-            date: date.to_string(),
-            total_income,
-            total_expense,
-            balance: total_income - total_expense,
-            income_count,
-            expense_count,
-            current_balance,
-        })
+        match query.voided {
+            Some(true) => filter.push_str(" AND t.voided_at IS NOT NULL"),
+            Some(false) => filter.push_str(" AND t.voided_at IS NULL"),
+            None => {}
+        }
+
+        (filter, params)
     }
 
-    // Category operations
-    pub fn get_categories(&self, category_type: Option<&str>) -> Result<Vec<Category>> {
-        let conn = self.conn.lock().unwrap();
+    /// ORDER BY clause from the whitelisted sort field and direction.
+    /// The id breaks ties so pages are stable.
+    pub fn transaction_order(query: &GetTransactionsRequest) -> Result<String> {
+        let column = match query.sort_by.as_deref().unwrap_or("date") {
+            "date" => "t.created_at",
+            "amount" => "t.amount",
+            "number" => "t.id",
+            "concept" => "t.concept COLLATE NOCASE",
+            "category" => "c.name COLLATE NOCASE",
+            "created_by" => "t.created_by COLLATE NOCASE",
+            other => {
+                return Err(rusqlite::Error::InvalidParameterName(format!(
+                    "Campo de orden no soportado: {}",
+                    other
+                )));
+            }
+        };
+
+        let direction = match query.sort_direction.as_deref().unwrap_or("desc") {
+            "asc" => "ASC",
+            "desc" => "DESC",
+            _ => {
+                return Err(rusqlite::Error::InvalidParameterName(
+                    "La dirección de orden debe ser 'asc' o 'desc'".to_string(),
+                ));
+            }
+        };
+
+        Ok(format!("{} {}, t.id {}", column, direction, direction))
+    }
+
+    /// Full-text search ranked by relevance (bm25). `query.text` holds the
+    /// search terms; the remaining filters narrow the matches as in
+    /// `query_transactions`. Snippets mark matches between \u{2} and \u{3}.
+    /// Cursors are keyed on (score, id) since results are not in date order.
+    pub fn search_transactions(
+        &self,
+        query: &GetTransactionsRequest,
+    ) -> Result<Page<TransactionSearchResult>> {
+        let Some(match_query) = query.text.as_deref().and_then(Self::fts_match_query) else {
+            return Ok(Page {
+                items: Vec::new(),
+                total_count: query
+                    .include_total
+                    .unwrap_or(query.cursor.is_none())
+                    .then_some(0),
+                next_cursor: None,
+                prev_cursor: None,
+            });
+        };
+
+        let conn = self.conn.lock().unwrap();
+
+        let filters = GetTransactionsRequest {
+            text: None,
+            ..query.clone()
+        };
+        let (filter, filter_params) = Self::transaction_filter(&filters);
+        let mut params = vec![Value::Text(match_query)];
+        params.extend(filter_params);
+
+        let total_count = if query.include_total.unwrap_or(query.cursor.is_none()) {
+            Some(conn.query_row(
+                &format!(
+                    "SELECT COUNT(*)
+                     FROM transactions_fts f
+                     JOIN transactions t ON t.id = f.rowid
+                     LEFT JOIN categories c ON t.category_id = c.id
+                     WHERE transactions_fts MATCH ?{}",
+                    filter
+                ),
+                rusqlite::params_from_iter(params.iter()),
+                |row| row.get(0),
+            )?)
+        } else {
+            None
+        };
+
+        let cursor = query
+            .cursor
+            .as_deref()
+            .map(PageCursor::decode)
+            .transpose()?;
+        let (keyset, order_by) = match &cursor {
+            Some(cursor) => {
+                let score: f64 = cursor.key.parse().map_err(|_| PageCursor::invalid())?;
+                params.push(Value::Real(score));
+                params.push(Value::Real(score));
+                params.push(Value::Integer(cursor.id));
+
+                // Scan away from the cursor row; a previous page is read in reverse
+                if cursor.backward {
+                    (
+                        "WHERE score < ? OR (score = ? AND id > ?)",
+                        "score DESC, id ASC",
+                    )
+                } else {
+                    (
+                        "WHERE score > ? OR (score = ? AND id < ?)",
+                        "score ASC, id DESC",
+                    )
+                }
+            }
+            None => ("", "score ASC, id DESC"),
+        };
 
-        let query = if let Some(t) = category_type {
-            format!("SELECT id, name, type, is_active FROM categories WHERE type = '{}' AND is_active = 1", t)
+        // One extra row tells whether there is another page
+        let limit = query.limit.unwrap_or(50);
+        let offset = if cursor.is_some() {
+            0
         } else {
-            "SELECT id, name, type, is_active FROM categories WHERE is_active = 1".to_string()
+            query.offset.unwrap_or(0)
         };
+        params.push(Value::Integer(limit + 1));
+        params.push(Value::Integer(offset));
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT * FROM (
+                 SELECT 
+                    t.id, t.session_id, t.transaction_number, t.type, t.amount, 
+                    t.concept, t.category_id, c.name as category_name, t.created_at, t.created_by,
+                    t.counterparty, t.payment_method, t.voided_at,
+                    snippet(transactions_fts, -1, char(2), char(3), '…', 12) AS snippet,
+                    bm25(transactions_fts) AS score
+                 FROM transactions_fts f
+                 JOIN transactions t ON t.id = f.rowid
+                 LEFT JOIN categories c ON t.category_id = c.id
+                 WHERE transactions_fts MATCH ?{}
+             )
+             {}
+             ORDER BY {}
+             LIMIT ? OFFSET ?",
+            filter, keyset, order_by
+        ))?;
+
+        let mut rows = stmt
+            .query_map(rusqlite::params_from_iter(params.iter()), |row| {
+                Ok((
+                    self.row_to_transaction(row)?,
+                    row.get::<_, String>(13)?,
+                    row.get::<_, f64>(14)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        let has_more = rows.len() as i64 > limit;
+        rows.truncate(limit as usize);
+        if cursor.as_ref().is_some_and(|c| c.backward) {
+            rows.reverse();
+        }
 
-        let mut stmt = conn.prepare(&query)?;
-        let categories = stmt.query_map([], |row| {
-            Ok(Category {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                category_type: row.get(2)?,
-                is_active: row.get(3)?,
+        let (mut transactions, extras): (Vec<Transaction>, Vec<(String, f64)>) = rows
+            .into_iter()
+            .map(|(transaction, snippet, rank)| (transaction, (snippet, rank)))
+            .unzip();
+        Self::load_transaction_details(&conn, &mut transactions)?;
+
+        let results: Vec<TransactionSearchResult> = transactions
+            .into_iter()
+            .zip(extras)
+            .map(|(transaction, (snippet, rank))| TransactionSearchResult {
+                transaction,
+                snippet,
+                rank,
             })
-        })?;
+            .collect();
+
+        let (next_cursor, prev_cursor) =
+            PageCursor::around(&results, cursor.as_ref(), has_more, offset > 0, |r| {
+                (r.rank.to_string(), r.transaction.id)
+            });
+
+        Ok(Page {
+            items: results,
+            total_count,
+            next_cursor,
+            prev_cursor,
+        })
+    }
+
+    /// FTS5 query for free text: every word must match, each as a prefix.
+    /// Returns None when the text has no searchable words.
+    fn fts_match_query(text: &str) -> Option<String> {
+        let terms: Vec<String> = text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(|word| format!("\"{}\"*", word))
+            .collect();
+
+        if terms.is_empty() {
+            None
+        } else {
+            Some(terms.join(" "))
+        }
+    }
+
+    /// Escape LIKE wildcards so user text matches literally
+    fn escape_like(text: &str) -> String {
+        text.replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_")
+    }
+
+    // Timestamps are stored in UTC, like datetime('now'), while dates picked
+    // by users and the days that totals are grouped by are local. Every query
+    // on days goes through these helpers. Ranges convert the local bounds to
+    // UTC instead of converting the column, so that SQLite can use its indexes.
+
+    /// Local day of a UTC timestamp expression, as YYYY-MM-DD
+    pub fn local_date(timestamp: &str) -> String {
+        format!("date({}, 'localtime')", timestamp)
+    }
+
+    /// Local time of a UTC timestamp expression, as YYYY-MM-DD HH:MM:SS
+    pub fn local_datetime(timestamp: &str) -> String {
+        format!("datetime({}, 'localtime')", timestamp)
+    }
+
+    /// `column` falls on the local day `date` (an SQL expression) or later
+    pub fn local_day_from(column: &str, date: &str) -> String {
+        format!("{} >= datetime(date({}), 'utc')", column, date)
+    }
+
+    /// `column` falls on the local day `date` (an SQL expression) or earlier
+    pub fn local_day_until(column: &str, date: &str) -> String {
+        format!("{} < datetime(date({}), '+1 day', 'utc')", column, date)
+    }
+
+    pub fn get_daily_summary(&self, date: &str) -> Result<DailySummary> {
+        let conn = self.conn.lock().unwrap();
+
+        let (total_income, income_count): (f64, i64) = conn.query_row(
+            &format!(
+                "SELECT COALESCE(SUM(amount), 0), COUNT(*)
+                 FROM transactions
+                 WHERE type = 'income' AND {} AND {} AND voided_at IS NULL",
+                Self::local_day_from("created_at", "?1"),
+                Self::local_day_until("created_at", "?1")
+            ),
+            [date],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+
+        let (total_expense, expense_count): (f64, i64) = conn.query_row(
+            &format!(
+                "SELECT COALESCE(SUM(amount), 0), COUNT(*)
+                 FROM transactions
+                 WHERE type = 'expense' AND {} AND {} AND voided_at IS NULL",
+                Self::local_day_from("created_at", "?1"),
+                Self::local_day_until("created_at", "?1")
+            ),
+            [date],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+
+        // Get current balance from active session
+        let current_balance: f64 = conn
+            .query_row(
+                "SELECT COALESCE(
+                opening_amount + (
+                    SELECT COALESCE(SUM(CASE WHEN type = 'income' THEN amount ELSE -amount END), 0)
+                    FROM transactions WHERE session_id = sessions.id AND voided_at IS NULL
+                ), 0)
+             FROM sessions 
+             WHERE is_active = 1",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap_or(0.0);
+
+        Ok(DailySummary {
+            date: date.to_string(),
+            total_income,
+            total_expense,
+            balance: total_income - total_expense,
+            income_count,
+            expense_count,
+            current_balance,
+        })
+    }
+
+    // Analytics, bucketed on local days like every other total. The ranges
+    // let SQLite use idx_transactions_date_type_amount.
+
+    /// Income and expense per day or month of the range, including those
+    /// without movements. `period` is a strftime pattern: '%Y-%m-%d' or '%Y-%m'.
+    pub fn get_income_expense_series(
+        &self,
+        start_date: &str,
+        end_date: &str,
+        period: &str,
+    ) -> Result<Vec<SeriesPoint>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn.prepare(&format!(
+            "WITH RECURSIVE days(day) AS (
+                 SELECT date(?1)
+                 UNION ALL
+                 SELECT date(day, '+1 day') FROM days WHERE day < date(?2)
+             ),
+             totals AS (
+                 SELECT {} AS day,
+                     SUM(CASE WHEN type = 'income' THEN amount ELSE 0 END) AS income,
+                     SUM(CASE WHEN type = 'expense' THEN amount ELSE 0 END) AS expense,
+                     SUM(type = 'income') AS income_count,
+                     SUM(type = 'expense') AS expense_count
+                 FROM transactions
+                 WHERE {} AND {}
+                 AND voided_at IS NULL
+                 GROUP BY day
+             )
+             SELECT strftime(?3, days.day) AS period,
+                 COALESCE(SUM(totals.income), 0), COALESCE(SUM(totals.expense), 0),
+                 COALESCE(SUM(totals.income_count), 0), COALESCE(SUM(totals.expense_count), 0)
+             FROM days
+             LEFT JOIN totals ON totals.day = days.day
+             GROUP BY period
+             ORDER BY period",
+            Self::local_date("created_at"),
+            Self::local_day_from("created_at", "?1"),
+            Self::local_day_until("created_at", "?2")
+        ))?;
+
+        stmt.query_map([start_date, end_date, period], |row| {
+            Ok(SeriesPoint {
+                period: row.get(0)?,
+                total_income: row.get(1)?,
+                total_expense: row.get(2)?,
+                income_count: row.get(3)?,
+                expense_count: row.get(4)?,
+            })
+        })?
+        .collect()
+    }
+
+    /// Categories with the largest totals of each type, with their average
+    /// ticket. Split transactions count toward every category of their lines.
+    pub fn get_top_categories(
+        &self,
+        start_date: &str,
+        end_date: &str,
+        top_n: i64,
+    ) -> Result<Vec<CategoryStat>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT category_id, category_name, type, total, transaction_count
+             FROM (
+                 SELECT a.category_id, c.name AS category_name, t.type,
+                     SUM(a.amount) AS total,
+                     COUNT(DISTINCT a.transaction_id) AS transaction_count,
+                     ROW_NUMBER() OVER (
+                         PARTITION BY t.type ORDER BY SUM(a.amount) DESC
+                     ) AS position
+                 FROM transactions t
+                 JOIN transaction_allocations a ON a.transaction_id = t.id
+                 LEFT JOIN categories c ON a.category_id = c.id
+                 WHERE {} AND {}
+                 AND t.voided_at IS NULL
+                 GROUP BY a.category_id, t.type
+             )
+             WHERE position <= ?3
+             ORDER BY type = 'expense', position",
+            Self::local_day_from("t.created_at", "?1"),
+            Self::local_day_until("t.created_at", "?2")
+        ))?;
+
+        stmt.query_map(rusqlite::params![start_date, end_date, top_n], |row| {
+            let total: f64 = row.get(3)?;
+            let transaction_count: i64 = row.get(4)?;
+
+            Ok(CategoryStat {
+                category_id: row.get(0)?,
+                category_name: row.get(1)?,
+                transaction_type: row.get(2)?,
+                total,
+                transaction_count,
+                average_ticket: total / transaction_count as f64,
+            })
+        })?
+        .collect()
+    }
+
+    /// Transaction count per weekday and hour of the range
+    pub fn get_hourly_heatmap(&self, start_date: &str, end_date: &str) -> Result<Vec<HeatmapCell>> {
+        let conn = self.conn.lock().unwrap();
+
+        // strftime('%w') starts the week on Sunday; shift it to Monday
+        let local_time = Self::local_datetime("created_at");
+        let mut stmt = conn.prepare(&format!(
+            "SELECT (CAST(strftime('%w', {0}) AS INTEGER) + 6) % 7 AS weekday,
+                 CAST(strftime('%H', {0}) AS INTEGER) AS hour,
+                 COUNT(*)
+             FROM transactions
+             WHERE {1} AND {2}
+             AND voided_at IS NULL
+             GROUP BY weekday, hour
+             ORDER BY weekday, hour",
+            local_time,
+            Self::local_day_from("created_at", "?1"),
+            Self::local_day_until("created_at", "?2")
+        ))?;
+
+        stmt.query_map([start_date, end_date], |row| {
+            Ok(HeatmapCell {
+                weekday: row.get(0)?,
+                hour: row.get(1)?,
+                transaction_count: row.get(2)?,
+            })
+        })?
+        .collect()
+    }
+
+    // Category operations
+    pub fn get_categories(&self, category_type: Option<&str>) -> Result<Vec<Category>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn.prepare(
+            "SELECT id, name, type, is_active, parent_id, account_code
+             FROM categories
+             WHERE is_active = 1 AND (?1 IS NULL OR type = ?1)
+             ORDER BY name",
+        )?;
+        let categories = stmt.query_map([category_type], |row| self.row_to_category(row))?;
+
+        categories.collect::<Result<Vec<_>>>()
+    }
+
+    /// All categories, including deactivated ones
+    pub fn get_all_categories(&self) -> Result<Vec<Category>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn.prepare(
+            "SELECT id, name, type, is_active, parent_id, account_code
+             FROM categories
+             ORDER BY name",
+        )?;
+        let categories = stmt.query_map([], |row| self.row_to_category(row))?;
 
         categories.collect::<Result<Vec<_>>>()
     }
@@ -424,49 +1539,205 @@ impl Database {
         let conn = self.conn.lock().unwrap();
 
         conn.query_row(
-            "SELECT id, name, type, is_active FROM categories WHERE id = ?1",
+            "SELECT id, name, type, is_active, parent_id, account_code FROM categories WHERE id = ?1",
             [id],
-            |row| {
-                Ok(Category {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    category_type: row.get(2)?,
-                    is_active: row.get(3)?,
-                })
-            },
+            |row| self.row_to_category(row),
         )
     }
 
-    pub fn create_category(&self, name: &str, category_type: &str) -> Result<Category> {
+    pub fn get_inactive_categories(&self, category_type: Option<&str>) -> Result<Vec<Category>> {
         let conn = self.conn.lock().unwrap();
 
-        conn.execute(
-            "INSERT INTO categories (name, type, is_active) VALUES (?1, ?2, 1)",
-            [name, category_type],
+        let mut stmt = conn.prepare(
+            "SELECT id, name, type, is_active, parent_id, account_code
+             FROM categories
+             WHERE is_active = 0 AND (?1 IS NULL OR type = ?1)
+             ORDER BY name",
         )?;
+        let categories = stmt.query_map([category_type], |row| self.row_to_category(row))?;
 
-        let id = conn.last_insert_rowid();
+        categories.collect::<Result<Vec<_>>>()
+    }
 
-        Ok(Category {
-            id,
-            name: name.to_string(),
-            category_type: category_type.to_string(),
-            is_active: true,
-        })
+    /// Find an active category with the same name (case-insensitive) and type
+    pub fn find_active_category_by_name(
+        &self,
+        name: &str,
+        category_type: &str,
+    ) -> Result<Option<Category>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn.prepare(
+            "SELECT id, name, type, is_active, parent_id, account_code
+             FROM categories
+             WHERE is_active = 1 AND type = ?1 AND name = ?2 COLLATE NOCASE",
+        )?;
+        let mut rows = stmt.query_map([category_type, name.trim()], |row| {
+            self.row_to_category(row)
+        })?;
+
+        rows.next().transpose()
+    }
+
+    /// Whether the category has active subcategories (i.e. it is not a leaf)
+    pub fn category_has_children(&self, id: i64) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM categories WHERE parent_id = ?1 AND is_active = 1)",
+            [id],
+            |row| row.get(0),
+        )
+    }
+
+    pub fn category_has_transactions(&self, id: i64) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM transactions WHERE category_id = ?1)
+                 OR EXISTS(SELECT 1 FROM transaction_lines WHERE category_id = ?1)",
+            [id],
+            |row| row.get(0),
+        )
+    }
+
+    pub fn create_category(
+        &self,
+        name: &str,
+        category_type: &str,
+        parent_id: Option<i64>,
+        account_code: Option<&str>,
+    ) -> Result<Category> {
+        let id = {
+            let conn = self.conn.lock().unwrap();
+            conn.execute(
+                "INSERT INTO categories (name, type, is_active, parent_id, account_code)
+                 VALUES (?1, ?2, 1, ?3, ?4)",
+                rusqlite::params![name, category_type, parent_id, account_code],
+            )?;
+            conn.last_insert_rowid()
+        }; // Release lock here
+
+        self.get_category_by_id(id)
     }
 
-    pub fn update_category(&self, id: i64, name: &str) -> Result<Category> {
+    pub fn update_category(
+        &self,
+        id: i64,
+        name: &str,
+        account_code: Option<&str>,
+    ) -> Result<Category> {
         {
             let conn = self.conn.lock().unwrap();
             conn.execute(
-                "UPDATE categories SET name = ?1 WHERE id = ?2",
-                [name, &id.to_string()],
+                "UPDATE categories SET name = ?1, account_code = ?2 WHERE id = ?3",
+                rusqlite::params![name, account_code, id],
             )?;
         } // Release lock here
 
         self.get_category_by_id(id)
     }
 
+    pub fn set_category_parent(&self, id: i64, parent_id: Option<i64>) -> Result<Category> {
+        {
+            let conn = self.conn.lock().unwrap();
+            conn.execute(
+                "UPDATE categories SET parent_id = ?1 WHERE id = ?2",
+                rusqlite::params![parent_id, id],
+            )?;
+        } // Release lock here
+
+        self.get_category_by_id(id)
+    }
+
+    pub fn reactivate_category(&self, id: i64) -> Result<Category> {
+        {
+            let conn = self.conn.lock().unwrap();
+            conn.execute("UPDATE categories SET is_active = 1 WHERE id = ?1", [id])?;
+        } // Release lock here
+
+        self.get_category_by_id(id)
+    }
+
+    /// Mark a transaction as voided and record it in the audit log, in one
+    /// database transaction
+    pub fn void_transaction(
+        &self,
+        transaction_id: i64,
+        details: &str,
+        performed_by: Option<&str>,
+    ) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        tx.execute(
+            "UPDATE transactions SET voided_at = datetime('now') WHERE id = ?1",
+            [transaction_id],
+        )?;
+        Self::record_audit(
+            &tx,
+            "void",
+            "transaction",
+            Some(transaction_id),
+            details,
+            performed_by,
+        )?;
+
+        tx.commit()
+    }
+
+    /// Reassign every transaction of `source_id` to `target_id` and deactivate the
+    /// source, all in one database transaction. Returns the number of transactions moved.
+    pub fn merge_categories(
+        &self,
+        source_id: i64,
+        target_id: i64,
+        details: &str,
+        performed_by: Option<&str>,
+    ) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        // Split transactions count once, however many of their lines move
+        let split_moved: usize = tx.query_row(
+            "SELECT COUNT(DISTINCT transaction_id) FROM transaction_lines WHERE category_id = ?1",
+            [source_id],
+            |row| row.get(0),
+        )?;
+        let moved = tx.execute(
+            "UPDATE transactions SET category_id = ?1 WHERE category_id = ?2",
+            [target_id, source_id],
+        )? + split_moved;
+        tx.execute(
+            "UPDATE transaction_lines SET category_id = ?1 WHERE category_id = ?2",
+            [target_id, source_id],
+        )?;
+        tx.execute(
+            "UPDATE recurring_templates SET category_id = ?1 WHERE category_id = ?2",
+            [target_id, source_id],
+        )?;
+        tx.execute(
+            "UPDATE budgets SET category_id = ?1 WHERE category_id = ?2",
+            [target_id, source_id],
+        )?;
+        tx.execute(
+            "UPDATE categories SET is_active = 0 WHERE id = ?1",
+            [source_id],
+        )?;
+        Self::record_audit(
+            &tx,
+            "merge",
+            "category",
+            Some(source_id),
+            &format!("{} ({} transacciones)", details, moved),
+            performed_by,
+        )?;
+
+        tx.commit()?;
+
+        Ok(moved)
+    }
+
     pub fn delete_category(&self, id: i64) -> Result<()> {
         let conn = self.conn.lock().unwrap();
 
@@ -479,6 +1750,459 @@ impl Database {
         Ok(())
     }
 
+    // Budget operations
+    pub fn create_budget(
+        &self,
+        category_id: i64,
+        period_type: &str,
+        period_start: &str,
+        period_end: &str,
+        amount: f64,
+        block_on_exceed: bool,
+    ) -> Result<Budget> {
+        let id = {
+            let conn = self.conn.lock().unwrap();
+            conn.execute(
+                "INSERT INTO budgets
+                 (category_id, period_type, period_start, period_end, amount, block_on_exceed)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                rusqlite::params![
+                    category_id,
+                    period_type,
+                    period_start,
+                    period_end,
+                    amount,
+                    block_on_exceed
+                ],
+            )?;
+            conn.last_insert_rowid()
+        }; // Release lock here
+
+        self.get_budget_by_id(id)
+    }
+
+    pub fn update_budget(&self, id: i64, amount: f64, block_on_exceed: bool) -> Result<Budget> {
+        {
+            let conn = self.conn.lock().unwrap();
+            conn.execute(
+                "UPDATE budgets SET amount = ?1, block_on_exceed = ?2 WHERE id = ?3",
+                rusqlite::params![amount, block_on_exceed, id],
+            )?;
+        } // Release lock here
+
+        self.get_budget_by_id(id)
+    }
+
+    pub fn delete_budget(&self, id: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM budgets WHERE id = ?1", [id])?;
+        Ok(())
+    }
+
+    pub fn get_budget_by_id(&self, id: i64) -> Result<Budget> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.query_row(
+            "SELECT b.id, b.category_id, c.name, b.period_type, b.period_start, b.period_end,
+                    b.amount, b.block_on_exceed
+             FROM budgets b
+             LEFT JOIN categories c ON b.category_id = c.id
+             WHERE b.id = ?1",
+            [id],
+            |row| self.row_to_budget(row),
+        )
+    }
+
+    /// Budgets whose period overlaps the given range (open ends match everything)
+    pub fn get_budgets(
+        &self,
+        start_date: Option<&str>,
+        end_date: Option<&str>,
+    ) -> Result<Vec<Budget>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn.prepare(
+            "SELECT b.id, b.category_id, c.name, b.period_type, b.period_start, b.period_end,
+                    b.amount, b.block_on_exceed
+             FROM budgets b
+             LEFT JOIN categories c ON b.category_id = c.id
+             WHERE (?1 IS NULL OR b.period_end >= date(?1))
+             AND (?2 IS NULL OR b.period_start <= date(?2))
+             ORDER BY b.period_start, c.name",
+        )?;
+        let budgets = stmt.query_map([start_date, end_date], |row| self.row_to_budget(row))?;
+
+        budgets.collect::<Result<Vec<_>>>()
+    }
+
+    /// Budgets of a category that overlap the given period
+    pub fn get_overlapping_budgets(
+        &self,
+        category_id: i64,
+        period_start: &str,
+        period_end: &str,
+    ) -> Result<Vec<Budget>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn.prepare(
+            "SELECT b.id, b.category_id, c.name, b.period_type, b.period_start, b.period_end,
+                    b.amount, b.block_on_exceed
+             FROM budgets b
+             LEFT JOIN categories c ON b.category_id = c.id
+             WHERE b.category_id = ?1 AND b.period_end >= ?2 AND b.period_start <= ?3",
+        )?;
+        let budgets = stmt.query_map(
+            rusqlite::params![category_id, period_start, period_end],
+            |row| self.row_to_budget(row),
+        )?;
+
+        budgets.collect::<Result<Vec<_>>>()
+    }
+
+    /// Budgets that apply to a category on the local day of a UTC timestamp
+    /// (or 'now'): those of the category itself and those of any of its ancestors
+    pub fn get_budgets_covering(&self, category_id: i64, timestamp: &str) -> Result<Vec<Budget>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn.prepare(&format!(
+            "WITH RECURSIVE ancestors(id) AS (
+                 SELECT ?1
+                 UNION ALL
+                 SELECT c.parent_id FROM categories c JOIN ancestors ON c.id = ancestors.id
+                 WHERE c.parent_id IS NOT NULL
+             )
+             SELECT b.id, b.category_id, c.name, b.period_type, b.period_start, b.period_end,
+                    b.amount, b.block_on_exceed
+             FROM budgets b
+             LEFT JOIN categories c ON b.category_id = c.id
+             WHERE b.category_id IN (SELECT id FROM ancestors)
+             AND {} BETWEEN b.period_start AND b.period_end",
+            Self::local_date("?2")
+        ))?;
+        let budgets = stmt.query_map(rusqlite::params![category_id, timestamp], |row| {
+            self.row_to_budget(row)
+        })?;
+
+        budgets.collect::<Result<Vec<_>>>()
+    }
+
+    /// Expenses recorded on a category and its subcategories among the
+    /// transactions matching a query
+    pub fn get_category_spent(
+        &self,
+        category_id: i64,
+        query: &GetTransactionsRequest,
+    ) -> Result<f64> {
+        let conn = self.conn.lock().unwrap();
+
+        let (filter, filter_params) = Self::transaction_filter(query);
+        let mut params = vec![Value::Integer(category_id)];
+        params.extend(filter_params);
+
+        conn.query_row(
+            &format!(
+                "WITH RECURSIVE subtree(id) AS (
+                     SELECT ?
+                     UNION ALL
+                     SELECT c.id FROM categories c JOIN subtree ON c.parent_id = subtree.id
+                 )
+                 SELECT COALESCE(SUM(a.amount), 0)
+                 FROM transaction_allocations a
+                 JOIN transactions t ON a.transaction_id = t.id
+                 WHERE t.type = 'expense'
+                 AND a.category_id IN (SELECT id FROM subtree){}",
+                filter
+            ),
+            rusqlite::params_from_iter(params.iter()),
+            |row| row.get(0),
+        )
+    }
+
+    // Tag operations
+    pub fn get_tags(&self) -> Result<Vec<Tag>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn.prepare(
+            "SELECT g.id, g.name, COUNT(tt.transaction_id)
+             FROM tags g
+             LEFT JOIN transaction_tags tt ON tt.tag_id = g.id
+             GROUP BY g.id
+             ORDER BY g.name",
+        )?;
+        let tags = stmt.query_map([], |row| self.row_to_tag(row))?;
+
+        tags.collect::<Result<Vec<_>>>()
+    }
+
+    pub fn get_tag_by_id(&self, id: i64) -> Result<Tag> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.query_row(
+            "SELECT g.id, g.name,
+                    (SELECT COUNT(*) FROM transaction_tags tt WHERE tt.tag_id = g.id)
+             FROM tags g
+             WHERE g.id = ?1",
+            [id],
+            |row| self.row_to_tag(row),
+        )
+    }
+
+    /// Attach tags to a transaction, creating the ones that do not exist yet
+    pub fn add_transaction_tags(&self, transaction_id: i64, tags: &[String]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        for tag in tags {
+            tx.execute("INSERT OR IGNORE INTO tags (name) VALUES (?1)", [tag])?;
+            tx.execute(
+                "INSERT OR IGNORE INTO transaction_tags (transaction_id, tag_id)
+                 SELECT ?1, id FROM tags WHERE name = ?2",
+                rusqlite::params![transaction_id, tag],
+            )?;
+        }
+
+        tx.commit()
+    }
+
+    pub fn remove_transaction_tag(&self, transaction_id: i64, tag: &str) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.execute(
+            "DELETE FROM transaction_tags
+             WHERE transaction_id = ?1
+             AND tag_id IN (SELECT id FROM tags WHERE name = ?2)",
+            rusqlite::params![transaction_id, tag],
+        )
+    }
+
+    // Recurring template operations
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_recurring_template(
+        &self,
+        name: &str,
+        transaction_type: &str,
+        amount: f64,
+        concept: &str,
+        category_id: Option<i64>,
+        counterparty: Option<&str>,
+        recurrence: &str,
+        recurrence_day: Option<i64>,
+        start_date: &str,
+    ) -> Result<RecurringTemplate> {
+        let id = {
+            let conn = self.conn.lock().unwrap();
+            conn.execute(
+                "INSERT INTO recurring_templates
+                 (name, type, amount, concept, category_id, counterparty, recurrence,
+                  recurrence_day, start_date)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                rusqlite::params![
+                    name,
+                    transaction_type,
+                    amount,
+                    concept,
+                    category_id,
+                    counterparty,
+                    recurrence,
+                    recurrence_day,
+                    start_date
+                ],
+            )?;
+            conn.last_insert_rowid()
+        }; // Release lock here
+
+        self.get_recurring_template_by_id(id)
+    }
+
+    pub fn get_recurring_template_by_id(&self, id: i64) -> Result<RecurringTemplate> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.query_row(
+            "SELECT r.id, r.name, r.type, r.amount, r.concept, r.category_id, c.name,
+                    r.counterparty, r.recurrence, r.recurrence_day, r.start_date,
+                    r.last_posted_on, r.is_active
+             FROM recurring_templates r
+             LEFT JOIN categories c ON r.category_id = c.id
+             WHERE r.id = ?1",
+            [id],
+            |row| self.row_to_recurring_template(row),
+        )
+    }
+
+    pub fn get_recurring_templates(&self) -> Result<Vec<RecurringTemplate>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn.prepare(
+            "SELECT r.id, r.name, r.type, r.amount, r.concept, r.category_id, c.name,
+                    r.counterparty, r.recurrence, r.recurrence_day, r.start_date,
+                    r.last_posted_on, r.is_active
+             FROM recurring_templates r
+             LEFT JOIN categories c ON r.category_id = c.id
+             WHERE r.is_active = 1
+             ORDER BY r.name",
+        )?;
+        let templates = stmt.query_map([], |row| self.row_to_recurring_template(row))?;
+
+        templates.collect::<Result<Vec<_>>>()
+    }
+
+    pub fn mark_recurring_template_posted(&self, id: i64, posted_on: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE recurring_templates SET last_posted_on = ?1 WHERE id = ?2",
+            rusqlite::params![posted_on, id],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_recurring_template(&self, id: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+
+        // Soft delete - mark as inactive
+        conn.execute(
+            "UPDATE recurring_templates SET is_active = 0 WHERE id = ?1",
+            [id],
+        )?;
+
+        Ok(())
+    }
+
+    // Report template operations
+    pub fn create_report_template(
+        &self,
+        name: &str,
+        spec: &ReportSpec,
+        date_range: Option<RelativeDateRange>,
+        output_folder: Option<&str>,
+    ) -> Result<ReportTemplate> {
+        let id = {
+            let conn = self.conn.lock().unwrap();
+            conn.execute(
+                "INSERT INTO report_templates (name, spec, date_range, output_folder)
+                 VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![name, spec, date_range, output_folder],
+            )?;
+            conn.last_insert_rowid()
+        }; // Release lock here
+
+        self.get_report_template_by_id(id)
+    }
+
+    pub fn update_report_template(
+        &self,
+        id: i64,
+        name: &str,
+        spec: &ReportSpec,
+        date_range: Option<RelativeDateRange>,
+        output_folder: Option<&str>,
+    ) -> Result<ReportTemplate> {
+        {
+            let conn = self.conn.lock().unwrap();
+            conn.execute(
+                "UPDATE report_templates
+                 SET name = ?1, spec = ?2, date_range = ?3, output_folder = ?4
+                 WHERE id = ?5",
+                rusqlite::params![name, spec, date_range, output_folder, id],
+            )?;
+        } // Release lock here
+
+        self.get_report_template_by_id(id)
+    }
+
+    pub fn get_report_template_by_id(&self, id: i64) -> Result<ReportTemplate> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.query_row(
+            "SELECT id, name, spec, date_range, output_folder, last_run_at, is_active
+             FROM report_templates WHERE id = ?1",
+            [id],
+            |row| self.row_to_report_template(row),
+        )
+    }
+
+    pub fn get_report_templates(&self) -> Result<Vec<ReportTemplate>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn.prepare(
+            "SELECT id, name, spec, date_range, output_folder, last_run_at, is_active
+             FROM report_templates
+             WHERE is_active = 1
+             ORDER BY name",
+        )?;
+        let templates = stmt.query_map([], |row| self.row_to_report_template(row))?;
+
+        templates.collect::<Result<Vec<_>>>()
+    }
+
+    /// Find an active template with the same name (case-insensitive)
+    pub fn find_report_template_by_name(&self, name: &str) -> Result<Option<ReportTemplate>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn.prepare(
+            "SELECT id, name, spec, date_range, output_folder, last_run_at, is_active
+             FROM report_templates
+             WHERE is_active = 1 AND name = ?1 COLLATE NOCASE",
+        )?;
+        let mut rows = stmt.query_map([name.trim()], |row| self.row_to_report_template(row))?;
+
+        rows.next().transpose()
+    }
+
+    pub fn mark_report_template_run(&self, id: i64, ran_at: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE report_templates SET last_run_at = ?1 WHERE id = ?2",
+            rusqlite::params![ran_at, id],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_report_template(&self, id: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+
+        // Soft delete - mark as inactive
+        conn.execute(
+            "UPDATE report_templates SET is_active = 0 WHERE id = ?1",
+            [id],
+        )?;
+
+        Ok(())
+    }
+
+    /// First and last day of the term budget period that includes a date.
+    /// Terms are the periods of the budgets of type 'term'.
+    pub fn get_term_covering(&self, date: &str) -> Result<Option<(String, String)>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn.prepare(
+            "SELECT period_start, period_end FROM budgets
+             WHERE period_type = 'term' AND date(?1) BETWEEN period_start AND period_end
+             ORDER BY period_start DESC, period_end
+             LIMIT 1",
+        )?;
+        let mut rows = stmt.query_map([date], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+        rows.next().transpose()
+    }
+
+    // Audit log
+    pub fn record_audit(
+        conn: &Connection,
+        action: &str,
+        entity_type: &str,
+        entity_id: Option<i64>,
+        details: &str,
+        performed_by: Option<&str>,
+    ) -> Result<()> {
+        conn.execute(
+            "INSERT INTO audit_log (action, entity_type, entity_id, details, performed_by)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            rusqlite::params![action, entity_type, entity_id, details, performed_by],
+        )?;
+
+        Ok(())
+    }
+
     // Helper functions
     fn row_to_session(&self, row: &Row) -> Result<Session> {
         Ok(Session {
@@ -492,6 +2216,68 @@ impl Database {
         })
     }
 
+    fn row_to_category(&self, row: &Row) -> Result<Category> {
+        Ok(Category {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            category_type: row.get(2)?,
+            is_active: row.get(3)?,
+            parent_id: row.get(4)?,
+            account_code: row.get(5)?,
+        })
+    }
+
+    fn row_to_budget(&self, row: &Row) -> Result<Budget> {
+        Ok(Budget {
+            id: row.get(0)?,
+            category_id: row.get(1)?,
+            category_name: row.get(2)?,
+            period_type: row.get(3)?,
+            period_start: row.get(4)?,
+            period_end: row.get(5)?,
+            amount: row.get(6)?,
+            block_on_exceed: row.get::<_, i64>(7)? != 0,
+        })
+    }
+
+    fn row_to_recurring_template(&self, row: &Row) -> Result<RecurringTemplate> {
+        Ok(RecurringTemplate {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            transaction_type: row.get(2)?,
+            amount: row.get(3)?,
+            concept: row.get(4)?,
+            category_id: row.get(5)?,
+            category_name: row.get(6)?,
+            counterparty: row.get(7)?,
+            recurrence: row.get(8)?,
+            recurrence_day: row.get(9)?,
+            start_date: row.get(10)?,
+            last_posted_on: row.get(11)?,
+            is_active: row.get::<_, i64>(12)? != 0,
+        })
+    }
+
+    fn row_to_report_template(&self, row: &Row) -> Result<ReportTemplate> {
+        Ok(ReportTemplate {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            spec: row.get(2)?,
+            date_range: row.get(3)?,
+            output_folder: row.get(4)?,
+            last_run_at: row.get(5)?,
+            is_active: row.get::<_, i64>(6)? != 0,
+        })
+    }
+
+    fn row_to_tag(&self, row: &Row) -> Result<Tag> {
+        Ok(Tag {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            transaction_count: row.get(2)?,
+        })
+    }
+
     fn row_to_transaction(&self, row: &Row) -> Result<Transaction> {
         Ok(Transaction {
             id: row.get(0)?,
@@ -504,6 +2290,11 @@ impl Database {
             category_name: row.get(7)?,
             created_at: row.get(8)?,
             created_by: row.get(9)?,
+            counterparty: row.get(10)?,
+            payment_method: row.get(11)?,
+            voided_at: row.get(12)?,
+            lines: Vec::new(),
+            tags: Vec::new(),
         })
     }
 
@@ -554,3 +2345,39 @@ impl Database {
         backups.collect::<Result<Vec<_>>>()
     }
 }
+
+// Report templates keep their spec as JSON
+impl ToSql for ReportSpec {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        serde_json::to_string(self)
+            .map(ToSqlOutput::from)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+    }
+}
+
+impl FromSql for ReportSpec {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        serde_json::from_str(value.as_str()?).map_err(|e| FromSqlError::Other(Box::new(e)))
+    }
+}
+
+impl ToSql for RelativeDateRange {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(match self {
+            RelativeDateRange::LastMonth => "last_month",
+            RelativeDateRange::CurrentTerm => "current_term",
+            RelativeDateRange::YearToDate => "year_to_date",
+        }))
+    }
+}
+
+impl FromSql for RelativeDateRange {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value.as_str()? {
+            "last_month" => Ok(RelativeDateRange::LastMonth),
+            "current_term" => Ok(RelativeDateRange::CurrentTerm),
+            "year_to_date" => Ok(RelativeDateRange::YearToDate),
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+}
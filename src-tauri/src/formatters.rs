@@ -0,0 +1,245 @@
+// Formatters shared by reports, receipts and the frontend
+
+/// Grammatical gender of the noun that follows a number ("un boliviano", "una cuota")
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Gender {
+    Masculine,
+    Feminine,
+}
+
+/// Currency names used when spelling out amounts
+#[derive(Debug, Clone, Copy)]
+pub struct CurrencyName {
+    pub singular: &'static str,
+    pub plural: &'static str,
+    pub gender: Gender,
+}
+
+/// Largest integer part supported (just under one billón)
+const MAX_AMOUNT: u64 = 999_999_999_999;
+
+const UNITS: [&str; 10] = [
+    "", "uno", "dos", "tres", "cuatro", "cinco", "seis", "siete", "ocho", "nueve",
+];
+
+const TEENS: [&str; 10] = [
+    "diez",
+    "once",
+    "doce",
+    "trece",
+    "catorce",
+    "quince",
+    "dieciséis",
+    "diecisiete",
+    "dieciocho",
+    "diecinueve",
+];
+
+const TWENTIES: [&str; 10] = [
+    "veinte",
+    "veintiuno",
+    "veintidós",
+    "veintitrés",
+    "veinticuatro",
+    "veinticinco",
+    "veintiséis",
+    "veintisiete",
+    "veintiocho",
+    "veintinueve",
+];
+
+const TENS: [&str; 10] = [
+    "",
+    "",
+    "",
+    "treinta",
+    "cuarenta",
+    "cincuenta",
+    "sesenta",
+    "setenta",
+    "ochenta",
+    "noventa",
+];
+
+const HUNDREDS: [&str; 10] = [
+    "",
+    "ciento",
+    "doscientos",
+    "trescientos",
+    "cuatrocientos",
+    "quinientos",
+    "seiscientos",
+    "setecientos",
+    "ochocientos",
+    "novecientos",
+];
+
+/// Look up the currency name for a currency code ('BOB', 'USD' or 'EUR')
+pub fn currency_name(currency: &str) -> Result<CurrencyName, String> {
+    match currency.to_uppercase().as_str() {
+        "BOB" => Ok(CurrencyName {
+            singular: "boliviano",
+            plural: "bolivianos",
+            gender: Gender::Masculine,
+        }),
+        "USD" => Ok(CurrencyName {
+            singular: "dólar estadounidense",
+            plural: "dólares estadounidenses",
+            gender: Gender::Masculine,
+        }),
+        "EUR" => Ok(CurrencyName {
+            singular: "euro",
+            plural: "euros",
+            gender: Gender::Masculine,
+        }),
+        _ => Err(format!(
+            "Moneda no soportada: {}. Use 'BOB', 'USD' o 'EUR'",
+            currency
+        )),
+    }
+}
+
+/// Spell out an amount in Spanish with its currency and the centavos fraction,
+/// e.g. 1234.5 BOB -> "Mil doscientos treinta y cuatro bolivianos con 50/100"
+pub fn amount_to_words(amount: f64, currency: &str) -> Result<String, String> {
+    if !amount.is_finite() {
+        return Err("El monto no es un número válido".to_string());
+    }
+
+    let currency = currency_name(currency)?;
+    let total_cents = (amount.abs() * 100.0).round() as u64;
+    let integer = total_cents / 100;
+    let cents = total_cents % 100;
+
+    if integer > MAX_AMOUNT {
+        return Err("El monto es demasiado grande para expresarlo en letras".to_string());
+    }
+
+    let mut words = number_to_words(integer, currency.gender);
+
+    // "un millón de bolivianos", but "un millón cien bolivianos"
+    if integer >= 1_000_000 && integer.is_multiple_of(1_000_000) {
+        words.push_str(" de");
+    }
+
+    let currency_label = if integer == 1 {
+        currency.singular
+    } else {
+        currency.plural
+    };
+
+    let mut result = format!("{} {} con {:02}/100", words, currency_label, cents);
+    if amount < 0.0 && total_cents > 0 {
+        result = format!("menos {}", result);
+    }
+
+    Ok(capitalize(&result))
+}
+
+/// Spell out an integer as it reads before a noun of the given gender
+/// ("veintiún bolivianos", "doscientas una cuotas")
+pub fn number_to_words(number: u64, gender: Gender) -> String {
+    if number == 0 {
+        return "cero".to_string();
+    }
+
+    let millions = number / 1_000_000;
+    let thousands = (number / 1_000) % 1_000;
+    let rest = number % 1_000;
+
+    let mut parts: Vec<String> = Vec::new();
+
+    if millions > 0 {
+        if millions == 1 {
+            parts.push("un millón".to_string());
+        } else {
+            // "millón" is masculine regardless of the counted noun
+            parts.push(format!(
+                "{} millones",
+                thousands_to_words(millions, Gender::Masculine)
+            ));
+        }
+    }
+
+    if thousands > 0 {
+        parts.push(thousands_to_words(thousands * 1_000, gender));
+    }
+
+    if rest > 0 {
+        parts.push(below_thousand(rest, gender));
+    }
+
+    parts.join(" ")
+}
+
+/// Words for numbers below one million
+fn thousands_to_words(number: u64, gender: Gender) -> String {
+    let thousands = number / 1_000;
+    let rest = number % 1_000;
+
+    let mut parts: Vec<String> = Vec::new();
+
+    if thousands == 1 {
+        parts.push("mil".to_string());
+    } else if thousands > 1 {
+        parts.push(format!("{} mil", below_thousand(thousands, gender)));
+    }
+
+    if rest > 0 {
+        parts.push(below_thousand(rest, gender));
+    }
+
+    parts.join(" ")
+}
+
+fn below_thousand(number: u64, gender: Gender) -> String {
+    let hundreds = (number / 100) as usize;
+    let rest = (number % 100) as usize;
+
+    let mut parts: Vec<String> = Vec::new();
+
+    if hundreds > 0 {
+        if hundreds == 1 && rest == 0 {
+            parts.push("cien".to_string());
+        } else if hundreds > 1 && gender == Gender::Feminine {
+            parts.push(HUNDREDS[hundreds].replace("ientos", "ientas"));
+        } else {
+            parts.push(HUNDREDS[hundreds].to_string());
+        }
+    }
+
+    if rest > 0 {
+        parts.push(below_hundred(rest, gender));
+    }
+
+    parts.join(" ")
+}
+
+fn below_hundred(number: usize, gender: Gender) -> String {
+    let words = match number {
+        1..=9 => UNITS[number].to_string(),
+        10..=19 => TEENS[number - 10].to_string(),
+        20..=29 => TWENTIES[number - 20].to_string(),
+        _ if number.is_multiple_of(10) => TENS[number / 10].to_string(),
+        _ => format!("{} y {}", TENS[number / 10], UNITS[number % 10]),
+    };
+
+    // Numbers ending in "uno" agree with the noun that follows
+    if number % 10 == 1 && number != 11 {
+        match gender {
+            Gender::Masculine if number == 21 => words.replace("veintiuno", "veintiún"),
+            Gender::Masculine => words.replace("uno", "un"),
+            Gender::Feminine => words.replace("uno", "una"),
+        }
+    } else {
+        words
+    }
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
+        None => String::new(),
+    }
+}
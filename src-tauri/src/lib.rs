@@ -3,6 +3,7 @@
 
 pub mod commands;
 pub mod db;
+pub mod formatters;
 pub mod models;
 pub mod services;
 
@@ -34,13 +35,50 @@ pub fn run() {
             commands::get_categories_by_type,
             commands::create_category,
             commands::update_category,
+            commands::move_category,
             commands::delete_category,
+            commands::get_inactive_categories,
+            commands::reactivate_category,
+            commands::merge_categories,
             commands::get_today_transactions_summary,
             commands::search_transactions,
             commands::update_transaction,
+            commands::void_transaction,
             commands::delete_transaction,
+            // Tag commands
+            commands::get_tags,
+            commands::add_transaction_tags,
+            commands::remove_transaction_tag,
+            // Budget commands
+            commands::create_budget,
+            commands::update_budget,
+            commands::delete_budget,
+            commands::get_budget_status,
+            // Recurring template commands
+            commands::create_recurring_template,
+            commands::get_recurring_templates,
+            commands::delete_recurring_template,
+            commands::get_due_templates,
+            commands::post_recurring_template,
+            // Import commands
+            commands::import_transactions,
+            // Export commands
+            commands::export_data,
+            // Analytics commands
+            commands::get_dashboard_analytics,
             // Report commands
             commands::generate_report,
+            commands::preview_report,
+            commands::get_operator_performance,
+            // Report template commands
+            commands::create_report_template,
+            commands::update_report_template,
+            commands::get_report_templates,
+            commands::delete_report_template,
+            commands::run_report_template,
+            // Printer commands
+            commands::print_transaction_receipt,
+            commands::print_session_close,
             // Backup commands
             commands::create_backup,
             commands::restore_backup,
@@ -49,6 +87,8 @@ pub fn run() {
             commands::get_database_info,
             // Database management commands
             commands::delete_all_records,
+            // Formatter commands
+            commands::amount_to_words,
             // Test command
             commands::greet,
         ])
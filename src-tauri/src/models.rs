@@ -27,6 +27,40 @@ pub struct Transaction {
     pub category_name: Option<String>,
     pub created_at: String,
     pub created_by: String,
+    pub counterparty: Option<String>,
+    pub payment_method: String,    // 'cash', 'card', 'transfer' or 'other'
+    pub voided_at: Option<String>, // Voided transactions count in no total
+    #[serde(default)]
+    pub lines: Vec<TransactionLine>, // Empty unless split across categories
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct TransactionSearchResult {
+    #[serde(flatten)]
+    pub transaction: Transaction,
+    pub snippet: String, // HTML-escaped, matches wrapped in <mark>
+    pub rank: f64,       // Lower is more relevant
+}
+
+/// One page of a keyset-paginated listing. Cursors are opaque and only set
+/// when there are rows in that direction.
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total_count: Option<i64>, // Only when requested
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TransactionLine {
+    pub id: i64,
+    pub transaction_id: i64,
+    pub category_id: i64,
+    pub category_name: Option<String>,
+    pub amount: f64,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -35,6 +69,67 @@ pub struct Category {
     pub name: String,
     pub category_type: String, // 'income' or 'expense'
     pub is_active: bool,
+    pub parent_id: Option<i64>,
+    pub account_code: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Tag {
+    pub id: i64,
+    pub name: String,
+    pub transaction_count: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CategoryNode {
+    #[serde(flatten)]
+    pub category: Category,
+    pub children: Vec<CategoryNode>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Budget {
+    pub id: i64,
+    pub category_id: i64,
+    pub category_name: Option<String>,
+    pub period_type: String,  // 'month' or 'term'
+    pub period_start: String, // Format: YYYY-MM-DD
+    pub period_end: String,   // Format: YYYY-MM-DD
+    pub amount: f64,
+    pub block_on_exceed: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RecurringTemplate {
+    pub id: i64,
+    pub name: String,
+    pub transaction_type: String, // 'income' or 'expense'
+    pub amount: f64,
+    pub concept: String,
+    pub category_id: Option<i64>,
+    pub category_name: Option<String>,
+    pub counterparty: Option<String>,
+    pub recurrence: String,          // 'monthly', 'weekly' or 'fixed_day'
+    pub recurrence_day: Option<i64>, // Weekday 1-7 (Mon-Sun) or day of month 1-31
+    pub start_date: String,          // Format: YYYY-MM-DD
+    pub last_posted_on: Option<String>,
+    pub is_active: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DueTemplate {
+    #[serde(flatten)]
+    pub template: RecurringTemplate,
+    pub due_date: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BudgetStatus {
+    #[serde(flatten)]
+    pub budget: Budget,
+    pub actual: f64,
+    pub remaining: f64,
+    pub percent_used: f64,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -48,6 +143,213 @@ pub struct DailySummary {
     pub current_balance: f64,
 }
 
+/// Sessions, movements and closing differences of one operator
+#[derive(Debug, Serialize, Clone)]
+pub struct OperatorPerformance {
+    pub operator_name: String,
+    pub session_count: i64,
+    pub average_session_minutes: Option<f64>, // Closed sessions only
+    pub transaction_count: i64,               // Movements recorded by the operator
+    pub total_income: f64,
+    pub total_expense: f64,
+    pub total_difference: f64,
+    pub discrepancy_count: i64, // Closings that did not match the expected amount
+    pub closings: Vec<ClosingDifference>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ClosingDifference {
+    pub session_id: i64,
+    pub opened_at: String,
+    pub closed_at: String,
+    pub expected_closing: f64,
+    pub closing_amount: f64,
+    pub difference: f64, // Counted minus expected
+}
+
+/// Income and expense of one day ('YYYY-MM-DD') or month ('YYYY-MM')
+#[derive(Debug, Serialize, Clone)]
+pub struct SeriesPoint {
+    pub period: String,
+    pub total_income: f64,
+    pub total_expense: f64,
+    pub income_count: i64,
+    pub expense_count: i64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CategoryStat {
+    pub category_id: Option<i64>,
+    pub category_name: Option<String>,
+    pub transaction_type: String,
+    pub total: f64,
+    pub transaction_count: i64,
+    pub average_ticket: f64,
+}
+
+/// Number of transactions recorded in one hour of one weekday
+#[derive(Debug, Serialize, Clone)]
+pub struct HeatmapCell {
+    pub weekday: i64, // 0 = Monday ... 6 = Sunday
+    pub hour: i64,    // 0-23
+    pub transaction_count: i64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DashboardAnalytics {
+    pub start_date: String,
+    pub end_date: String,
+    pub daily: Vec<SeriesPoint>,
+    pub monthly: Vec<SeriesPoint>,
+    pub top_categories: Vec<CategoryStat>, // Top N of each type, income first
+    pub heatmap: Vec<HeatmapCell>,         // Only hours with movements
+}
+
+/// Content of a report independent of its output format. The frontend
+/// previews it and the renderer of each output format lays it out.
+#[derive(Debug, Serialize, Clone)]
+pub struct ReportDocument {
+    pub title: String,
+    pub subtitles: Vec<String>, // Period lines under the title
+    pub generated_at: String,
+    pub summary: Vec<ReportSummaryItem>,
+    pub summary_sheet: Option<String>, // Spreadsheet sheet of its own for the header and summary
+    pub sections: Vec<ReportSection>,
+    pub notes: Vec<String>, // Footnotes
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ReportSummaryItem {
+    pub label: String,
+    pub value: ReportCell,
+    pub kind: ReportColumnKind,
+    pub strong: bool,
+    pub formula: Option<ReportSummaryFormula>, // Spreadsheets compute the value instead of storing it
+}
+
+/// How a summary value derives from the sections or from other summary items
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ReportSummaryFormula {
+    Aggregate(ReportAggregate),
+    Difference { minuend: usize, subtrahend: usize }, // Of summary items, by index
+}
+
+/// A table of the report. Sections sharing a sheet name are stacked on the
+/// same spreadsheet sheet.
+#[derive(Debug, Serialize, Clone)]
+pub struct ReportSection {
+    pub title: String,
+    pub sheet: String,
+    pub columns: Vec<ReportColumn>,
+    pub rows: Vec<ReportRow>,
+    pub totals: Option<ReportRow>,
+    pub chart: Option<ReportChart>,
+    pub table: bool, // Spreadsheets write it as a table with autofilter and frozen headers
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ReportColumn {
+    pub label: String,
+    pub kind: ReportColumnKind,
+    pub width: u16,                     // In characters
+    pub indent: bool,                   // Indented by the depth of the row
+    pub formula: Option<ReportFormula>, // Spreadsheets compute the column instead of storing it
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportColumnKind {
+    Text,
+    Integer,
+    Money,
+    Percent, // Stored as a fraction: 0.25 is 25%
+    Date,    // Text as YYYY-MM-DD; spreadsheets store it as a date
+}
+
+/// How a column derives from other columns of the same row, by index, or
+/// from the rows of another section
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ReportFormula {
+    Difference {
+        minuend: usize,
+        subtrahend: usize,
+    },
+    Balance {
+        base: usize,
+        plus: usize,
+        minus: usize,
+    },
+    RunningBalance {
+        plus: usize,
+        minus: usize,
+    }, // The previous row of this column plus and minus
+    Ratio {
+        numerator: usize,
+        denominator: usize,
+    }, // Empty when the denominator is zero
+    Change {
+        previous: usize,
+        current: usize,
+    }, // (current - previous) / |previous|, empty when previous is zero
+    Aggregate(ReportAggregate),
+}
+
+/// Sum or count of the rows of a section that meet every criterion, like
+/// SUMIFS and COUNTIFS
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ReportAggregate {
+    pub section: usize,
+    pub column: Option<usize>, // Summed column; rows are counted without one
+    pub criteria: Vec<ReportCriterion>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ReportCriterion {
+    pub column: usize, // Of the aggregated section
+    pub equals: ReportCriterionValue,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum ReportCriterionValue {
+    Text(String),
+    Column(usize), // The cell of this column on the row being computed
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ReportRow {
+    pub cells: Vec<ReportCell>,
+    pub depth: usize, // Category level for indented columns
+    pub style: ReportRowStyle,
+    pub literal: bool, // Written as is, without the column formulas (e.g. an opening balance)
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportRowStyle {
+    Normal,
+    Strong,
+    Alert,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum ReportCell {
+    Number(f64),
+    Text(String),
+    Empty,
+}
+
+/// Chart of the top-level rows of a section
+#[derive(Debug, Serialize, Clone)]
+pub struct ReportChart {
+    pub title: String,
+    pub label_column: usize,
+    pub value_columns: Vec<usize>, // The last one is the main series
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct BackupInfo {
     pub id: i64,
@@ -81,17 +383,112 @@ pub struct CreateTransactionRequest {
     pub amount: f64,
     pub concept: String,
     pub category_id: Option<i64>,
+    pub counterparty: Option<String>,
+    pub payment_method: Option<String>, // Defaults to 'cash'
     pub created_by: String,
+    #[serde(default)]
+    pub lines: Vec<TransactionLineRequest>, // Category lines summing to `amount`
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct TransactionLineRequest {
+    pub category_id: i64,
+    pub amount: f64,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct CreateRecurringTemplateRequest {
+    pub name: String,
+    pub transaction_type: String, // 'income' or 'expense'
+    pub amount: f64,
+    pub concept: String,
+    pub category_id: Option<i64>,
+    pub counterparty: Option<String>,
+    pub recurrence: String, // 'monthly', 'weekly' or 'fixed_day'
+    pub recurrence_day: Option<i64>,
+    pub start_date: Option<String>, // Defaults to today
+}
+
+/// Header names of the source columns for each transaction field
+#[derive(Debug, Deserialize, Clone)]
+pub struct ImportColumnMapping {
+    pub date: String,
+    pub transaction_type: Option<String>, // Without it, negative amounts are expenses
+    pub amount: String,
+    pub concept: String,
+    pub category: Option<String>, // Category name
+    pub operator: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ImportTransactionsRequest {
+    pub file_path: String, // .csv or .xlsx
+    pub mapping: ImportColumnMapping,
+    pub sheet: Option<String>,     // XLSX sheet, defaults to the first one
+    pub delimiter: Option<String>, // CSV, defaults to ','
+    pub date_format: Option<String>, // chrono format, defaults to YYYY-MM-DD or DD/MM/YYYY
+    pub decimal_comma: Option<bool>, // Amounts as 1.234,56 instead of 1,234.56
+    pub opening_amount: Option<f64>, // Cash before the first imported day, defaults to 0
+    pub default_operator: Option<String>,
+    pub create_categories: Option<bool>, // Create unknown categories, defaults to true
+    pub dry_run: bool,
 }
 
 #[derive(Debug, Deserialize, Clone)]
+pub struct CreateBudgetRequest {
+    pub category_id: i64,
+    pub period_type: String,      // 'month' or 'term'
+    pub start_date: String,       // Format: YYYY-MM-DD (any day of the month for 'month')
+    pub end_date: Option<String>, // Required for 'term'
+    pub amount: f64,
+    pub block_on_exceed: Option<bool>,
+}
+
+/// Structured transaction query shared by listing, search and counting
+#[derive(Debug, Deserialize, Clone, Default)]
 pub struct GetTransactionsRequest {
     pub session_id: Option<i64>,
     pub transaction_type: Option<String>,
-    pub start_date: Option<String>, // Format: YYYY-MM-DD
-    pub end_date: Option<String>,   // Format: YYYY-MM-DD
+    pub start_date: Option<String>,     // Format: YYYY-MM-DD
+    pub end_date: Option<String>,       // Format: YYYY-MM-DD
+    pub category_ids: Option<Vec<i64>>, // Includes subcategories and split lines
+    pub min_amount: Option<f64>,
+    pub max_amount: Option<f64>,
+    pub created_by: Option<String>,
+    pub counterparty: Option<String>, // Partial match
+    pub text: Option<String>,         // Concept, number or counterparty
+    pub tags: Option<Vec<String>>,    // Transactions carrying all of these tags
+    pub payment_method: Option<String>,
+    pub voided: Option<bool>,    // Both voided and valid ones when not set
+    pub sort_by: Option<String>, // 'date', 'amount', 'number', 'concept', 'category', 'created_by'
+    pub sort_direction: Option<String>, // 'asc' or 'desc'
     pub limit: Option<i64>,
     pub offset: Option<i64>,
+    pub cursor: Option<String>, // From a previous page; takes precedence over offset
+    pub include_total: Option<bool>, // Defaults to true on the first page only
+}
+
+/// A validated import row, ready to be written
+#[derive(Debug, Clone)]
+pub struct ImportedTransaction {
+    pub transaction_type: String,
+    pub amount: f64,
+    pub concept: String,
+    pub category_id: Option<i64>,
+    pub new_category: Option<String>, // Created with the transaction type on commit
+    pub created_at: String,           // Format: YYYY-MM-DD HH:MM:SS
+    pub created_by: String,
+}
+
+/// One imported day, written as a closed session
+#[derive(Debug, Clone)]
+pub struct ImportedSession {
+    pub operator_name: String,
+    pub opening_amount: f64,
+    pub closing_amount: f64,
+    pub opened_at: String,
+    pub closed_at: String,
+    pub transactions: Vec<ImportedTransaction>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -104,12 +501,123 @@ pub struct GetCategoriesRequest {
     pub category_type: Option<String>, // 'income' or 'expense'
 }
 
+/// What a report shows. New kinds are added here rather than encoded in the
+/// report name.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportKind {
+    Transactions, // Movements with category subtotals, narrowed by the filters
+    Balance,      // Opening and closing of every session
+    Ledger,       // Cash book with running balance
+    Budget,       // Budget execution
+    Breakdown,    // Category shares against the previous period
+    Comparison,   // Two periods side by side
+    Operators,    // Per-operator sessions and closing differences
+}
+
+/// How the movements of a `Transactions` report are summarized
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportGrouping {
+    #[default]
+    Category,
+    Week, // ISO weeks
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportFormat {
+    Pdf,
+    Excel,
+    Ods,      // OpenDocument spreadsheet, same sheets as Excel
+    Html,     // Single file with inline styles and SVG charts
+    Markdown, // For e-mails and the intranet
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct ReportFilters {
+    pub transaction_type: Option<String>, // 'income' or 'expense'
+    #[serde(default)]
+    pub category_ids: Vec<i64>, // Any of them, including their subcategories
+    pub tag_id: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ReportOptions {
+    pub currency: Option<String>,
+    pub download_path: Option<String>,
+    pub compare_start: Option<String>, // Earlier period of a comparison
+    pub compare_end: Option<String>,
+    pub growth_threshold: Option<f64>, // Percent; expense growth above it is highlighted
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReportSpec {
+    pub kind: ReportKind,
+    pub start_date: String,
+    pub end_date: String,
+    #[serde(default)]
+    pub filters: ReportFilters,
+    #[serde(default)]
+    pub grouping: ReportGrouping,
+    pub format: ReportFormat,
+    #[serde(default)]
+    pub options: ReportOptions,
+}
+
+/// Period of a saved report, resolved on the day it runs
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RelativeDateRange {
+    LastMonth,
+    CurrentTerm, // The term budget period that includes the day
+    YearToDate,
+}
+
+/// A report definition saved under a name to be run again
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReportTemplate {
+    pub id: i64,
+    pub name: String,
+    pub spec: ReportSpec, // Its format is the default one of the template
+    pub date_range: Option<RelativeDateRange>, // Replaces the dates of the spec
+    pub output_folder: Option<String>, // Defaults to Documents/CajaChoca/Reportes
+    pub last_run_at: Option<String>,
+    pub is_active: bool,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct SaveReportTemplateRequest {
+    pub name: String,
+    pub spec: ReportSpec,
+    pub date_range: Option<RelativeDateRange>,
+    pub output_folder: Option<String>,
+}
+
 #[derive(Debug, Deserialize, Clone)]
-pub struct GenerateReportRequest {
-    pub report_type: String, // 'income', 'expense', 'balance', 'weekly'
+pub struct DashboardAnalyticsRequest {
     pub start_date: String,
     pub end_date: String,
-    pub format: String, // 'pdf' or 'excel'
+    pub top_n: Option<i64>, // Categories per type, default 5
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ExportRequest {
+    pub entity: String, // 'transactions', 'sessions', 'categories' or 'audit_log'
+    pub format: String, // 'csv', 'json' or 'jsonl'
+    #[serde(default)]
+    pub filter: GetTransactionsRequest, // Pagination is ignored; dates also apply to sessions and the audit log
+    pub delimiter: Option<String>, // CSV, defaults to ',' or ';' with decimal comma
+    pub decimal_comma: Option<bool>, // CSV numbers as 12,50
+    pub download_path: Option<String>, // Directory, defaults to Documents/CajaChoca/Exportaciones
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct PrinterTargetRequest {
+    pub target_type: String, // 'device', 'file' or 'tcp'
+    pub path: Option<String>,
+    pub host: Option<String>,
+    pub port: Option<u16>, // Defaults to 9100 for 'tcp'
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -137,6 +645,7 @@ pub struct SessionResponse {
 pub struct TransactionResponse {
     pub success: bool,
     pub data: Option<Transaction>,
+    pub warnings: Vec<String>,
     pub error: Option<String>,
 }
 
@@ -144,7 +653,19 @@ pub struct TransactionResponse {
 pub struct TransactionsListResponse {
     pub success: bool,
     pub data: Vec<Transaction>,
-    pub total_count: i64,
+    pub total_count: Option<i64>,
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SearchResultsResponse {
+    pub success: bool,
+    pub data: Vec<TransactionSearchResult>,
+    pub total_count: Option<i64>,
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
     pub error: Option<String>,
 }
 
@@ -155,6 +676,13 @@ pub struct CategoriesListResponse {
     pub error: Option<String>,
 }
 
+#[derive(Debug, Serialize, Clone)]
+pub struct TagsListResponse {
+    pub success: bool,
+    pub data: Vec<Tag>,
+    pub error: Option<String>,
+}
+
 #[derive(Debug, Serialize, Clone)]
 pub struct CategoryResponse {
     pub success: bool,
@@ -162,6 +690,79 @@ pub struct CategoryResponse {
     pub error: Option<String>,
 }
 
+#[derive(Debug, Serialize, Clone)]
+pub struct BudgetResponse {
+    pub success: bool,
+    pub data: Option<Budget>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RecurringTemplateResponse {
+    pub success: bool,
+    pub data: Option<RecurringTemplate>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RecurringTemplatesListResponse {
+    pub success: bool,
+    pub data: Vec<RecurringTemplate>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DueTemplatesListResponse {
+    pub success: bool,
+    pub data: Vec<DueTemplate>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ImportRowError {
+    pub row: usize, // Line in the file, counting the header
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ImportResult {
+    pub dry_run: bool,
+    pub committed: bool, // False on a dry run or when any row failed
+    pub total_rows: usize,
+    pub valid_rows: usize,
+    pub session_count: usize,
+    pub new_categories: Vec<String>,
+    pub errors: Vec<ImportRowError>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ImportResponse {
+    pub success: bool,
+    pub data: Option<ImportResult>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DashboardAnalyticsResponse {
+    pub success: bool,
+    pub data: Option<DashboardAnalytics>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct OperatorPerformanceResponse {
+    pub success: bool,
+    pub data: Vec<OperatorPerformance>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BudgetStatusListResponse {
+    pub success: bool,
+    pub data: Vec<BudgetStatus>,
+    pub error: Option<String>,
+}
+
 #[derive(Debug, Serialize, Clone)]
 pub struct DailySummaryResponse {
     pub success: bool,
@@ -169,6 +770,27 @@ pub struct DailySummaryResponse {
     pub error: Option<String>,
 }
 
+#[derive(Debug, Serialize, Clone)]
+pub struct ReportTemplateResponse {
+    pub success: bool,
+    pub data: Option<ReportTemplate>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ReportTemplatesListResponse {
+    pub success: bool,
+    pub data: Vec<ReportTemplate>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ReportPreviewResponse {
+    pub success: bool,
+    pub data: Option<ReportDocument>,
+    pub error: Option<String>,
+}
+
 #[derive(Debug, Serialize, Clone)]
 pub struct ReportResponse {
     pub success: bool,
@@ -176,6 +798,19 @@ pub struct ReportResponse {
     pub error: Option<String>,
 }
 
+#[derive(Debug, Serialize, Clone)]
+pub struct ExportResult {
+    pub file_path: String,
+    pub row_count: usize,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ExportResponse {
+    pub success: bool,
+    pub data: Option<ExportResult>,
+    pub error: Option<String>,
+}
+
 #[derive(Debug, Serialize, Clone)]
 pub struct BackupResponse {
     pub success: bool,
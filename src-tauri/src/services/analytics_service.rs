@@ -0,0 +1,60 @@
+use crate::db::Database;
+use crate::models::{DashboardAnalytics, DashboardAnalyticsRequest};
+use chrono::NaiveDate;
+use rusqlite::Result;
+
+pub struct AnalyticsService;
+
+impl AnalyticsService {
+    /// Trends, top categories and hourly activity of a date range, all
+    /// aggregated in SQL
+    pub fn get_dashboard_analytics(
+        db: &Database,
+        request: DashboardAnalyticsRequest,
+    ) -> Result<DashboardAnalytics> {
+        let start = NaiveDate::parse_from_str(&request.start_date, "%Y-%m-%d");
+        let end = NaiveDate::parse_from_str(&request.end_date, "%Y-%m-%d");
+        let (Ok(start), Ok(end)) = (start, end) else {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "Las fechas deben tener el formato AAAA-MM-DD".to_string(),
+            ));
+        };
+
+        if end < start {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "La fecha de fin es anterior a la de inicio".to_string(),
+            ));
+        }
+
+        // The daily series has one point per day of the range
+        if (end - start).num_days() > 3660 {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "El rango no puede superar los 10 años".to_string(),
+            ));
+        }
+
+        let top_n = request.top_n.unwrap_or(5);
+        if !(1..=50).contains(&top_n) {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "La cantidad de categorías debe estar entre 1 y 50".to_string(),
+            ));
+        }
+
+        Ok(DashboardAnalytics {
+            daily: db.get_income_expense_series(
+                &request.start_date,
+                &request.end_date,
+                "%Y-%m-%d",
+            )?,
+            monthly: db.get_income_expense_series(
+                &request.start_date,
+                &request.end_date,
+                "%Y-%m",
+            )?,
+            top_categories: db.get_top_categories(&request.start_date, &request.end_date, top_n)?,
+            heatmap: db.get_hourly_heatmap(&request.start_date, &request.end_date)?,
+            start_date: request.start_date,
+            end_date: request.end_date,
+        })
+    }
+}
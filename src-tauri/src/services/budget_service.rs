@@ -0,0 +1,316 @@
+use crate::db::Database;
+use crate::models::{
+    Budget, BudgetStatus, CreateBudgetRequest, GetTransactionsRequest, Transaction,
+};
+use crate::services::transaction_service::TransactionService;
+use chrono::{Datelike, NaiveDate};
+use rusqlite::Result;
+use std::collections::HashMap;
+
+pub struct BudgetService;
+
+impl BudgetService {
+    /// Create a budget for an expense category and period
+    pub fn create_budget(db: &Database, request: CreateBudgetRequest) -> Result<Budget> {
+        if request.amount <= 0.0 {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "El monto del presupuesto debe ser mayor a cero".to_string(),
+            ));
+        }
+
+        let category = db.get_category_by_id(request.category_id)?;
+        if category.category_type != "expense" {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "Solo se pueden presupuestar categorías de egreso".to_string(),
+            ));
+        }
+
+        if !category.is_active {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "La categoría está desactivada".to_string(),
+            ));
+        }
+
+        let (period_start, period_end) = Self::resolve_period(
+            &request.period_type,
+            &request.start_date,
+            request.end_date.as_deref(),
+        )?;
+
+        if !db
+            .get_overlapping_budgets(request.category_id, &period_start, &period_end)?
+            .is_empty()
+        {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "Ya existe un presupuesto para esta categoría en ese período".to_string(),
+            ));
+        }
+
+        db.create_budget(
+            request.category_id,
+            &request.period_type,
+            &period_start,
+            &period_end,
+            request.amount,
+            request.block_on_exceed.unwrap_or(false),
+        )
+    }
+
+    /// Update the amount and blocking behaviour of a budget
+    pub fn update_budget(
+        db: &Database,
+        budget_id: i64,
+        amount: f64,
+        block_on_exceed: Option<bool>,
+    ) -> Result<Budget> {
+        if amount <= 0.0 {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "El monto del presupuesto debe ser mayor a cero".to_string(),
+            ));
+        }
+
+        let current = db.get_budget_by_id(budget_id)?;
+
+        db.update_budget(
+            budget_id,
+            amount,
+            block_on_exceed.unwrap_or(current.block_on_exceed),
+        )
+    }
+
+    /// Delete a budget
+    pub fn delete_budget(db: &Database, budget_id: i64) -> Result<()> {
+        let _ = db.get_budget_by_id(budget_id)?;
+        db.delete_budget(budget_id)
+    }
+
+    /// Budget vs. actual vs. remaining for every budget overlapping the filters.
+    /// Actual spending is limited to the intersection of the budget period and
+    /// the requested date range, and to the transactions matching the other
+    /// filters. With a category filter, only budgets of those categories, of
+    /// their subcategories or of their ancestors are listed. Voided expenses
+    /// only count when asked for explicitly.
+    pub fn get_budget_status(
+        db: &Database,
+        query: GetTransactionsRequest,
+    ) -> Result<Vec<BudgetStatus>> {
+        let mut query = TransactionService::prepare_query(query)?;
+
+        // Budgets only track expenses
+        if query.transaction_type.as_deref() == Some("income") {
+            return Ok(Vec::new());
+        }
+
+        query.voided = Some(query.voided.unwrap_or(false));
+        let mut budgets = db.get_budgets(query.start_date.as_deref(), query.end_date.as_deref())?;
+        if let Some(category_ids) = query.category_ids.as_ref().filter(|ids| !ids.is_empty()) {
+            let parents: HashMap<i64, Option<i64>> = db
+                .get_all_categories()?
+                .into_iter()
+                .map(|category| (category.id, category.parent_id))
+                .collect();
+            // The category and its ancestors, closest first
+            let lineage = |id: i64| {
+                std::iter::successors(Some(id), |id| parents.get(id).copied().flatten())
+                    .collect::<Vec<_>>()
+            };
+
+            budgets.retain(|budget| {
+                lineage(budget.category_id)
+                    .iter()
+                    .any(|id| category_ids.contains(id))
+                    || category_ids
+                        .iter()
+                        .any(|&id| lineage(id).contains(&budget.category_id))
+            });
+        }
+
+        let mut statuses = Vec::with_capacity(budgets.len());
+
+        for budget in budgets {
+            let range_start = match query.start_date.as_deref() {
+                Some(start) if start > budget.period_start.as_str() => start.to_string(),
+                _ => budget.period_start.clone(),
+            };
+            let range_end = match query.end_date.as_deref() {
+                Some(end) if end < budget.period_end.as_str() => end.to_string(),
+                _ => budget.period_end.clone(),
+            };
+
+            let actual = db.get_category_spent(
+                budget.category_id,
+                &GetTransactionsRequest {
+                    start_date: Some(range_start),
+                    end_date: Some(range_end),
+                    ..query.clone()
+                },
+            )?;
+
+            statuses.push(BudgetStatus {
+                actual,
+                remaining: budget.amount - actual,
+                percent_used: actual / budget.amount * 100.0,
+                budget,
+            });
+        }
+
+        Ok(statuses)
+    }
+
+    /// Valid expenses recorded against a budget over its whole period
+    fn period_spent(db: &Database, budget: &Budget) -> Result<f64> {
+        db.get_category_spent(
+            budget.category_id,
+            &GetTransactionsRequest {
+                start_date: Some(budget.period_start.clone()),
+                end_date: Some(budget.period_end.clone()),
+                voided: Some(false),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Fail when an expense would exceed a budget configured to block.
+    /// `allocations` is the amount per category of the expense; budgets of
+    /// each category and of its ancestors are checked once, against the sum
+    /// of every allocation they cover.
+    pub fn check_expense(db: &Database, allocations: &[(i64, f64)]) -> Result<()> {
+        let mut covered: Vec<(Budget, f64)> = Vec::new();
+        for &(category_id, amount) in allocations {
+            for budget in db.get_budgets_covering(category_id, "now")? {
+                if !budget.block_on_exceed {
+                    continue;
+                }
+
+                match covered.iter_mut().find(|(b, _)| b.id == budget.id) {
+                    Some((_, total)) => *total += amount,
+                    None => covered.push((budget, amount)),
+                }
+            }
+        }
+
+        for (budget, amount) in covered {
+            let spent = Self::period_spent(db, &budget)?;
+            let remaining = budget.amount - spent;
+
+            if amount > remaining {
+                return Err(rusqlite::Error::InvalidParameterName(format!(
+                    "El egreso excede el presupuesto de '{}'. Saldo disponible: ${:.2}",
+                    budget.category_name.as_deref().unwrap_or("Sin categoría"),
+                    remaining.max(0.0)
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Warnings for budgets exceeded once the given expense has been recorded
+    pub fn get_budget_warnings(db: &Database, transaction: &Transaction) -> Result<Vec<String>> {
+        if transaction.transaction_type != "expense" {
+            return Ok(Vec::new());
+        }
+
+        // Every category the expense touches, once
+        let mut category_ids: Vec<i64> = if transaction.lines.is_empty() {
+            transaction.category_id.into_iter().collect()
+        } else {
+            transaction
+                .lines
+                .iter()
+                .map(|line| line.category_id)
+                .collect()
+        };
+        category_ids.sort_unstable();
+        category_ids.dedup();
+
+        let mut warnings = Vec::new();
+        let mut checked_budgets = Vec::new();
+
+        for category_id in category_ids {
+            for budget in db.get_budgets_covering(category_id, &transaction.created_at)? {
+                // Lines under the same parent share its budget
+                if checked_budgets.contains(&budget.id) {
+                    continue;
+                }
+                checked_budgets.push(budget.id);
+
+                let spent = Self::period_spent(db, &budget)?;
+
+                if spent > budget.amount {
+                    warnings.push(format!(
+                        "Presupuesto de '{}' ({} al {}) excedido en ${:.2}",
+                        budget.category_name.as_deref().unwrap_or("Sin categoría"),
+                        budget.period_start,
+                        budget.period_end,
+                        spent - budget.amount
+                    ));
+                }
+            }
+        }
+
+        Ok(warnings)
+    }
+
+    /// Normalize a period to its first and last day (YYYY-MM-DD)
+    fn resolve_period(
+        period_type: &str,
+        start_date: &str,
+        end_date: Option<&str>,
+    ) -> Result<(String, String)> {
+        let invalid_date =
+            || rusqlite::Error::InvalidParameterName("Fecha inválida. Use YYYY-MM-DD".to_string());
+
+        match period_type {
+            "month" => {
+                // Accept either YYYY-MM or any day of the month
+                let date = NaiveDate::parse_from_str(start_date, "%Y-%m-%d")
+                    .or_else(|_| {
+                        NaiveDate::parse_from_str(&format!("{}-01", start_date), "%Y-%m-%d")
+                    })
+                    .map_err(|_| invalid_date())?;
+
+                let first = date.with_day(1).ok_or_else(invalid_date)?;
+                let next_month = if first.month() == 12 {
+                    NaiveDate::from_ymd_opt(first.year() + 1, 1, 1)
+                } else {
+                    NaiveDate::from_ymd_opt(first.year(), first.month() + 1, 1)
+                }
+                .ok_or_else(invalid_date)?;
+                let last = next_month.pred_opt().ok_or_else(invalid_date)?;
+
+                Ok((
+                    first.format("%Y-%m-%d").to_string(),
+                    last.format("%Y-%m-%d").to_string(),
+                ))
+            }
+            "term" => {
+                let start = NaiveDate::parse_from_str(start_date, "%Y-%m-%d")
+                    .map_err(|_| invalid_date())?;
+                let end = end_date
+                    .ok_or_else(|| {
+                        rusqlite::Error::InvalidParameterName(
+                            "Debe indicar la fecha de fin del período".to_string(),
+                        )
+                    })
+                    .and_then(|end| {
+                        NaiveDate::parse_from_str(end, "%Y-%m-%d").map_err(|_| invalid_date())
+                    })?;
+
+                if end < start {
+                    return Err(rusqlite::Error::InvalidParameterName(
+                        "La fecha de fin debe ser posterior a la de inicio".to_string(),
+                    ));
+                }
+
+                Ok((
+                    start.format("%Y-%m-%d").to_string(),
+                    end.format("%Y-%m-%d").to_string(),
+                ))
+            }
+            _ => Err(rusqlite::Error::InvalidParameterName(
+                "El período debe ser 'month' o 'term'".to_string(),
+            )),
+        }
+    }
+}
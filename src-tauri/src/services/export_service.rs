@@ -0,0 +1,307 @@
+use crate::db::Database;
+use crate::models::{ExportRequest, ExportResult, GetTransactionsRequest};
+use crate::services::transaction_service::TransactionService;
+use rusqlite::types::{Value, ValueRef};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+/// Columns holding JSON arrays, embedded as such in JSON output
+const JSON_COLUMNS: &[&str] = &["lines", "tags"];
+
+pub struct ExportService;
+
+impl ExportService {
+    /// Export raw rows to CSV, JSON or JSON Lines. Rows are written as they
+    /// are read from the database, so large exports are never held in memory.
+    pub fn export(db: &Database, request: ExportRequest) -> Result<ExportResult, String> {
+        let extension = match request.format.as_str() {
+            "csv" => "csv",
+            "json" => "json",
+            "jsonl" => "jsonl",
+            _ => return Err("El formato debe ser 'csv', 'json' o 'jsonl'".to_string()),
+        };
+
+        let decimal_comma = request.decimal_comma.unwrap_or(false);
+        let delimiter = match request.delimiter.as_deref() {
+            None if decimal_comma => b';',
+            None => b',',
+            Some("\\t") | Some("tab") => b'\t',
+            Some(d) if d.len() == 1 => d.as_bytes()[0],
+            Some(_) => return Err("El separador debe ser un solo carácter".to_string()),
+        };
+        if decimal_comma && delimiter == b',' {
+            return Err("Con coma decimal use otro separador, por ejemplo ';'".to_string());
+        }
+
+        let (sql, params) = Self::export_query(&request.entity, request.filter)?;
+
+        let export_dir = match request.download_path.as_deref() {
+            Some(path) => PathBuf::from(path),
+            None => Self::get_exports_directory()?,
+        };
+        if !export_dir.exists() {
+            std::fs::create_dir_all(&export_dir)
+                .map_err(|e| format!("Error creating directory: {}", e))?;
+        }
+
+        let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+        let file_name = format!("{}_{}.{}", request.entity, timestamp, extension);
+        let file_path = export_dir.join(&file_name);
+
+        // Rows go to a temporary file that only takes the final name once
+        // complete, so a failed export leaves no truncated file behind
+        let temp_path = export_dir.join(format!(".{}.part", file_name));
+        let written = File::create(&temp_path)
+            .map_err(|e| format!("Error creating file: {}", e))
+            .and_then(|file| {
+                Self::write_rows(
+                    db,
+                    &sql,
+                    &params,
+                    BufWriter::new(file),
+                    extension,
+                    delimiter,
+                    decimal_comma,
+                )
+            })
+            .and_then(|row_count| {
+                std::fs::rename(&temp_path, &file_path)
+                    .map(|_| row_count)
+                    .map_err(|e| format!("Error saving file: {}", e))
+            });
+        let row_count = match written {
+            Ok(row_count) => row_count,
+            Err(e) => {
+                let _ = std::fs::remove_file(&temp_path);
+                return Err(e);
+            }
+        };
+
+        Ok(ExportResult {
+            file_path: file_path.to_string_lossy().to_string(),
+            row_count,
+        })
+    }
+
+    /// Stream the rows of `sql` to `out` and return how many were written
+    fn write_rows(
+        db: &Database,
+        sql: &str,
+        params: &[Value],
+        out: BufWriter<File>,
+        extension: &str,
+        delimiter: u8,
+        decimal_comma: bool,
+    ) -> Result<usize, String> {
+        let conn = db.get_connection();
+        let conn = conn.lock().unwrap();
+        let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+        let columns: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+        let mut rows = stmt
+            .query(rusqlite::params_from_iter(params.iter()))
+            .map_err(|e| e.to_string())?;
+
+        let mut row_count = 0;
+        if extension == "csv" {
+            let mut writer = csv::WriterBuilder::new()
+                .delimiter(delimiter)
+                .from_writer(out);
+            writer.write_record(&columns).map_err(|e| e.to_string())?;
+
+            while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+                let record = (0..columns.len())
+                    .map(|i| {
+                        row.get_ref(i)
+                            .map(|value| Self::csv_field(value, decimal_comma))
+                    })
+                    .collect::<rusqlite::Result<Vec<_>>>()
+                    .map_err(|e| e.to_string())?;
+                writer.write_record(&record).map_err(|e| e.to_string())?;
+                row_count += 1;
+            }
+
+            writer.flush().map_err(|e| e.to_string())?;
+        } else {
+            let mut out = out;
+            let lines = extension == "jsonl";
+            if !lines {
+                out.write_all(b"[\n").map_err(|e| e.to_string())?;
+            }
+
+            while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+                let mut object = serde_json::Map::new();
+                for (i, column) in columns.iter().enumerate() {
+                    let value = row.get_ref(i).map_err(|e| e.to_string())?;
+                    object.insert(column.clone(), Self::json_value(column, value));
+                }
+
+                if !lines && row_count > 0 {
+                    out.write_all(b",\n").map_err(|e| e.to_string())?;
+                }
+                serde_json::to_writer(&mut out, &object).map_err(|e| e.to_string())?;
+                if lines {
+                    out.write_all(b"\n").map_err(|e| e.to_string())?;
+                }
+                row_count += 1;
+            }
+
+            if !lines {
+                out.write_all(b"\n]\n").map_err(|e| e.to_string())?;
+            }
+            out.flush().map_err(|e| e.to_string())?;
+        }
+
+        Ok(row_count)
+    }
+
+    /// Query and bound values for each exportable entity
+    fn export_query(
+        entity: &str,
+        filter: GetTransactionsRequest,
+    ) -> Result<(String, Vec<Value>), String> {
+        match entity {
+            "transactions" => {
+                let query = TransactionService::prepare_query(filter).map_err(|e| e.to_string())?;
+                let (filter, params) = Database::transaction_filter(&query);
+                let order_by = Database::transaction_order(&query).map_err(|e| e.to_string())?;
+
+                Ok((
+                    format!(
+                        "SELECT
+                            t.id, t.transaction_number, t.session_id, t.created_at, t.type,
+                            t.amount, t.concept, t.category_id, c.name AS category_name,
+                            t.counterparty, t.payment_method, t.voided_at, t.created_by,
+                            (SELECT json_group_array(json_object(
+                                        'category_id', l.category_id,
+                                        'category_name', lc.name,
+                                        'amount', l.amount))
+                             FROM transaction_lines l
+                             LEFT JOIN categories lc ON l.category_id = lc.id
+                             WHERE l.transaction_id = t.id) AS lines,
+                            (SELECT json_group_array(g.name)
+                             FROM transaction_tags tt
+                             JOIN tags g ON tt.tag_id = g.id
+                             WHERE tt.transaction_id = t.id) AS tags
+                         FROM transactions t
+                         LEFT JOIN categories c ON t.category_id = c.id
+                         WHERE 1=1{}
+                         ORDER BY {}",
+                        filter, order_by
+                    ),
+                    params,
+                ))
+            }
+            "sessions" => {
+                let mut conditions = String::new();
+                let mut params = Vec::new();
+                if let Some(id) = filter.session_id {
+                    conditions.push_str(" AND s.id = ?");
+                    params.push(Value::Integer(id));
+                }
+                Self::push_date_range(&mut conditions, &mut params, "s.opened_at", &filter);
+
+                Ok((
+                    format!(
+                        "SELECT
+                            s.id, s.operator_name, s.opening_amount, s.closing_amount,
+                            s.opened_at, s.closed_at, s.is_active,
+                            COALESCE(SUM(CASE WHEN t.type = 'income' THEN t.amount END), 0)
+                                AS total_income,
+                            COALESCE(SUM(CASE WHEN t.type = 'expense' THEN t.amount END), 0)
+                                AS total_expense,
+                            COUNT(t.id) AS transaction_count
+                         FROM sessions s
+                         LEFT JOIN transactions t ON t.session_id = s.id AND t.voided_at IS NULL
+                         WHERE 1=1{}
+                         GROUP BY s.id
+                         ORDER BY s.opened_at, s.id",
+                        conditions
+                    ),
+                    params,
+                ))
+            }
+            "categories" => Ok((
+                "SELECT
+                    c.id, c.name, c.type, c.parent_id, p.name AS parent_name,
+                    c.account_code, c.is_active
+                 FROM categories c
+                 LEFT JOIN categories p ON c.parent_id = p.id
+                 ORDER BY c.type, c.name"
+                    .to_string(),
+                Vec::new(),
+            )),
+            "audit_log" => {
+                let mut conditions = String::new();
+                let mut params = Vec::new();
+                Self::push_date_range(&mut conditions, &mut params, "created_at", &filter);
+
+                Ok((
+                    format!(
+                        "SELECT id, created_at, action, entity_type, entity_id, details, performed_by
+                         FROM audit_log
+                         WHERE 1=1{}
+                         ORDER BY id",
+                        conditions
+                    ),
+                    params,
+                ))
+            }
+            _ => Err(
+                "Solo se exportan 'transactions', 'sessions', 'categories' o 'audit_log'"
+                    .to_string(),
+            ),
+        }
+    }
+
+    fn push_date_range(
+        conditions: &mut String,
+        params: &mut Vec<Value>,
+        column: &str,
+        filter: &GetTransactionsRequest,
+    ) {
+        if let Some(start) = &filter.start_date {
+            conditions.push_str(&format!(" AND {}", Database::local_day_from(column, "?")));
+            params.push(Value::Text(start.clone()));
+        }
+        if let Some(end) = &filter.end_date {
+            conditions.push_str(&format!(" AND {}", Database::local_day_until(column, "?")));
+            params.push(Value::Text(end.clone()));
+        }
+    }
+
+    fn csv_field(value: ValueRef, decimal_comma: bool) -> String {
+        match value {
+            ValueRef::Null => String::new(),
+            ValueRef::Integer(i) => i.to_string(),
+            ValueRef::Real(f) if decimal_comma => f.to_string().replace('.', ","),
+            ValueRef::Real(f) => f.to_string(),
+            ValueRef::Text(t) | ValueRef::Blob(t) => String::from_utf8_lossy(t).to_string(),
+        }
+    }
+
+    fn json_value(column: &str, value: ValueRef) -> serde_json::Value {
+        match value {
+            ValueRef::Null => serde_json::Value::Null,
+            ValueRef::Integer(i) => i.into(),
+            ValueRef::Real(f) => f.into(),
+            ValueRef::Text(t) | ValueRef::Blob(t) => {
+                let text = String::from_utf8_lossy(t);
+                if JSON_COLUMNS.contains(&column)
+                    && let Ok(json) = serde_json::from_str(&text)
+                {
+                    return json;
+                }
+                text.to_string().into()
+            }
+        }
+    }
+
+    fn get_exports_directory() -> Result<PathBuf, String> {
+        let docs_dir = dirs::document_dir()
+            .or_else(|| dirs::home_dir().map(|h| h.join("Documents")))
+            .ok_or("No se pudo encontrar directorio de documentos")?;
+
+        Ok(docs_dir.join("CajaChoca").join("Exportaciones"))
+    }
+}
@@ -0,0 +1,483 @@
+use crate::db::Database;
+use crate::models::{
+    Category, ImportColumnMapping, ImportResult, ImportRowError, ImportTransactionsRequest,
+    ImportedSession, ImportedTransaction,
+};
+use crate::services::transaction_service::TransactionService;
+use calamine::{Data, Reader, open_workbook_auto};
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use rusqlite::Result;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+const DEFAULT_OPERATOR: &str = "Importación";
+
+pub struct ImportService;
+
+/// Position of each mapped field in the source rows
+struct ColumnIndexes {
+    date: usize,
+    transaction_type: Option<usize>,
+    amount: usize,
+    concept: usize,
+    category: Option<usize>,
+    operator: Option<usize>,
+}
+
+/// Source rows as text, each with its line number in the file
+type SourceRows = Vec<(usize, Vec<String>)>;
+
+impl ImportService {
+    /// Import historical transactions from a CSV or XLSX file.
+    /// Every row goes through the same validations as a manual entry and rows
+    /// are grouped into one closed session per day, carrying the cash balance
+    /// from day to day. Nothing is written on a dry run or when any row fails;
+    /// otherwise the whole file is written in a single database transaction.
+    pub fn import_transactions(
+        db: &Database,
+        request: ImportTransactionsRequest,
+    ) -> Result<ImportResult> {
+        let mut balance = request.opening_amount.unwrap_or(0.0);
+        if balance < 0.0 {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "El saldo inicial no puede ser negativo".to_string(),
+            ));
+        }
+
+        let path = Path::new(&request.file_path);
+        let (headers, rows) = Self::read_file(path, &request)?;
+        let columns = Self::resolve_columns(&headers, &request.mapping)?;
+
+        let default_operator = request
+            .default_operator
+            .as_deref()
+            .map(str::trim)
+            .filter(|o| !o.is_empty())
+            .unwrap_or(DEFAULT_OPERATOR);
+        let create_categories = request.create_categories.unwrap_or(true);
+        let categories = db.get_all_categories()?;
+
+        let mut errors = Vec::new();
+        let mut total_rows = 0;
+        let mut days: BTreeMap<NaiveDate, Vec<(usize, ImportedTransaction)>> = BTreeMap::new();
+
+        for (line, cells) in &rows {
+            if cells.iter().all(|cell| cell.trim().is_empty()) {
+                continue;
+            }
+            total_rows += 1;
+
+            match Self::parse_row(
+                db,
+                cells,
+                &columns,
+                &request,
+                &categories,
+                create_categories,
+                default_operator,
+            ) {
+                Ok((date, transaction)) => days.entry(date).or_default().push((*line, transaction)),
+                Err(e) => errors.push(Self::row_error(*line, e)),
+            }
+        }
+
+        // Expenses are checked against the cash available at that point, as
+        // they would be in an open session
+        let mut sessions = Vec::new();
+        for (_, mut day_rows) in days {
+            day_rows.sort_by(|a, b| a.1.created_at.cmp(&b.1.created_at));
+
+            let opening_amount = balance;
+            let mut operators: Vec<String> = Vec::new();
+            let mut transactions = Vec::new();
+
+            for (line, transaction) in day_rows {
+                if transaction.transaction_type == "expense" {
+                    if let Err(e) = TransactionService::check_balance(balance, transaction.amount) {
+                        errors.push(Self::row_error(line, e));
+                        continue;
+                    }
+                    balance -= transaction.amount;
+                } else {
+                    balance += transaction.amount;
+                }
+                balance = (balance * 100.0).round() / 100.0;
+
+                if !operators.contains(&transaction.created_by) {
+                    operators.push(transaction.created_by.clone());
+                }
+                transactions.push(transaction);
+            }
+
+            if let (Some(first), Some(last)) = (transactions.first(), transactions.last()) {
+                sessions.push(ImportedSession {
+                    operator_name: operators.join(", "),
+                    opening_amount,
+                    closing_amount: balance,
+                    opened_at: first.created_at.clone(),
+                    closed_at: last.created_at.clone(),
+                    transactions,
+                });
+            }
+        }
+        errors.sort_by_key(|error| error.row);
+
+        let mut new_categories: Vec<String> = Vec::new();
+        for transaction in sessions.iter().flat_map(|s| &s.transactions) {
+            if let Some(name) = &transaction.new_category
+                && !new_categories
+                    .iter()
+                    .any(|existing| existing.to_lowercase() == name.to_lowercase())
+            {
+                new_categories.push(name.clone());
+            }
+        }
+
+        let valid_rows = sessions.iter().map(|s| s.transactions.len()).sum();
+        let committed = !request.dry_run && errors.is_empty() && !sessions.is_empty();
+
+        if committed {
+            let file_name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| request.file_path.clone());
+            db.import_sessions(&sessions, &format!("Importación de {}", file_name))?;
+        }
+
+        Ok(ImportResult {
+            dry_run: request.dry_run,
+            committed,
+            total_rows,
+            valid_rows,
+            session_count: sessions.len(),
+            new_categories,
+            errors,
+        })
+    }
+
+    /// Parse and validate one row into the day it belongs to and the
+    /// transaction to write
+    fn parse_row(
+        db: &Database,
+        cells: &[String],
+        columns: &ColumnIndexes,
+        request: &ImportTransactionsRequest,
+        categories: &[Category],
+        create_categories: bool,
+        default_operator: &str,
+    ) -> Result<(NaiveDate, ImportedTransaction)> {
+        let cell = |index: usize| cells.get(index).map(|c| c.trim()).unwrap_or("");
+
+        let date_text = cell(columns.date);
+        let created_at =
+            Self::parse_date(date_text, request.date_format.as_deref()).ok_or_else(|| {
+                rusqlite::Error::InvalidParameterName(format!("Fecha inválida: '{}'", date_text))
+            })?;
+
+        let amount_text = cell(columns.amount);
+        let amount = Self::parse_amount(amount_text, request.decimal_comma.unwrap_or(false))
+            .ok_or_else(|| {
+                rusqlite::Error::InvalidParameterName(format!("Monto inválido: '{}'", amount_text))
+            })?;
+
+        // Without a type column the sign of the amount tells the type
+        let (transaction_type, amount) = match columns.transaction_type {
+            Some(index) => (Self::parse_type(cell(index)), amount),
+            None if amount < 0.0 => ("expense".to_string(), -amount),
+            None => ("income".to_string(), amount),
+        };
+
+        let concept = cell(columns.concept);
+        TransactionService::validate_transaction_fields(&transaction_type, amount, concept)?;
+
+        let (category_id, new_category) = match columns.category.map(cell).filter(|c| !c.is_empty())
+        {
+            Some(name) => {
+                let lowercase = name.to_lowercase();
+                let matching = categories.iter().filter(|c| {
+                    c.category_type == transaction_type && c.name.trim().to_lowercase() == lowercase
+                });
+                // Prefer an active category when a name was reused
+                let existing = matching
+                    .clone()
+                    .find(|c| c.is_active)
+                    .or_else(|| matching.clone().next());
+
+                match existing {
+                    Some(category) => {
+                        TransactionService::validate_transaction_category(
+                            db,
+                            category.id,
+                            &transaction_type,
+                        )?;
+                        (Some(category.id), None)
+                    }
+                    None if create_categories => (None, Some(name.to_string())),
+                    None => {
+                        return Err(rusqlite::Error::InvalidParameterName(format!(
+                            "Categoría no encontrada: '{}'",
+                            name
+                        )));
+                    }
+                }
+            }
+            None => (None, None),
+        };
+
+        let created_by = columns
+            .operator
+            .map(cell)
+            .filter(|o| !o.is_empty())
+            .unwrap_or(default_operator);
+
+        Ok((
+            created_at.date(),
+            ImportedTransaction {
+                transaction_type,
+                amount,
+                concept: concept.to_string(),
+                category_id,
+                new_category,
+                created_at: Self::to_utc(created_at)
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string(),
+                created_by: created_by.to_string(),
+            },
+        ))
+    }
+
+    fn row_error(row: usize, error: rusqlite::Error) -> ImportRowError {
+        let message = match error {
+            rusqlite::Error::InvalidParameterName(message) => message,
+            other => other.to_string(),
+        };
+
+        ImportRowError { row, message }
+    }
+
+    fn read_file(
+        path: &Path,
+        request: &ImportTransactionsRequest,
+    ) -> Result<(Vec<String>, SourceRows)> {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_lowercase);
+
+        match extension.as_deref() {
+            Some("csv") | Some("txt") => Self::read_csv(path, request.delimiter.as_deref()),
+            Some("xlsx") | Some("xls") | Some("ods") => {
+                Self::read_workbook(path, request.sheet.as_deref(), request.decimal_comma)
+            }
+            _ => Err(rusqlite::Error::InvalidParameterName(
+                "Formato no soportado. Use un archivo .csv o .xlsx".to_string(),
+            )),
+        }
+    }
+
+    fn read_csv(path: &Path, delimiter: Option<&str>) -> Result<(Vec<String>, SourceRows)> {
+        let delimiter = match delimiter {
+            None => b',',
+            Some("\\t") | Some("tab") => b'\t',
+            Some(d) if d.len() == 1 => d.as_bytes()[0],
+            Some(_) => {
+                return Err(rusqlite::Error::InvalidParameterName(
+                    "El separador debe ser un solo carácter".to_string(),
+                ));
+            }
+        };
+
+        let content = std::fs::read(path).map_err(Self::file_error)?;
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .flexible(true)
+            .from_reader(content.as_slice());
+
+        let headers = reader
+            .headers()
+            .map_err(Self::file_error)?
+            .iter()
+            .map(|h| h.trim_start_matches('\u{feff}').to_string())
+            .collect();
+
+        let mut rows = Vec::new();
+        for record in reader.records() {
+            let record = record.map_err(Self::file_error)?;
+            // A record starts after any blank lines preceding it
+            let start = record.position().map(|p| p.byte() as usize).unwrap_or(0);
+            let start = start
+                + content[start..]
+                    .iter()
+                    .take_while(|b| **b == b'\n' || **b == b'\r')
+                    .count();
+            let line = 1 + content[..start].iter().filter(|b| **b == b'\n').count();
+            rows.push((line, record.iter().map(str::to_string).collect()));
+        }
+
+        Ok((headers, rows))
+    }
+
+    fn read_workbook(
+        path: &Path,
+        sheet: Option<&str>,
+        decimal_comma: Option<bool>,
+    ) -> Result<(Vec<String>, SourceRows)> {
+        let decimal_comma = decimal_comma.unwrap_or(false);
+        let mut workbook = open_workbook_auto(path).map_err(Self::file_error)?;
+
+        let sheet = match sheet {
+            Some(sheet) => sheet.to_string(),
+            None => workbook.sheet_names().first().cloned().ok_or_else(|| {
+                rusqlite::Error::InvalidParameterName("El libro no tiene hojas".to_string())
+            })?,
+        };
+        let range = workbook.worksheet_range(&sheet).map_err(Self::file_error)?;
+
+        // Line numbers as shown in the spreadsheet
+        let header_line = range.start().map(|(row, _)| row as usize + 1).unwrap_or(1);
+        let mut rows = range.rows().map(|row| {
+            row.iter()
+                .map(|cell| Self::cell_text(cell, decimal_comma))
+                .collect::<Vec<_>>()
+        });
+
+        let headers = rows.next().unwrap_or_default();
+        let rows = rows
+            .enumerate()
+            .map(|(index, cells)| (header_line + index + 1, cells))
+            .collect();
+
+        Ok((headers, rows))
+    }
+
+    /// Numbers are written with the decimal separator amounts are parsed with
+    fn cell_text(cell: &Data, decimal_comma: bool) -> String {
+        match cell {
+            Data::Float(value) if decimal_comma => value.to_string().replace('.', ","),
+            Data::DateTime(value) => value
+                .as_datetime()
+                .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_default(),
+            Data::Empty => String::new(),
+            other => other.to_string(),
+        }
+    }
+
+    fn file_error(error: impl std::fmt::Display) -> rusqlite::Error {
+        rusqlite::Error::InvalidParameterName(format!("No se pudo leer el archivo: {}", error))
+    }
+
+    /// Match each mapped header (case-insensitive) to its column
+    fn resolve_columns(headers: &[String], mapping: &ImportColumnMapping) -> Result<ColumnIndexes> {
+        let find = |name: &str| -> Result<usize> {
+            headers
+                .iter()
+                .position(|h| h.trim().to_lowercase() == name.trim().to_lowercase())
+                .ok_or_else(|| {
+                    rusqlite::Error::InvalidParameterName(format!(
+                        "No se encontró la columna '{}'",
+                        name
+                    ))
+                })
+        };
+        let find_optional = |name: &Option<String>| -> Result<Option<usize>> {
+            name.as_deref()
+                .filter(|n| !n.trim().is_empty())
+                .map(find)
+                .transpose()
+        };
+
+        Ok(ColumnIndexes {
+            date: find(&mapping.date)?,
+            transaction_type: find_optional(&mapping.transaction_type)?,
+            amount: find(&mapping.amount)?,
+            concept: find(&mapping.concept)?,
+            category: find_optional(&mapping.category)?,
+            operator: find_optional(&mapping.operator)?,
+        })
+    }
+
+    /// Date with optional time; rows without a time are placed at midnight
+    fn parse_date(text: &str, format: Option<&str>) -> Option<NaiveDateTime> {
+        let formats: &[&str] = match format {
+            Some(format) => &[format],
+            None => &[
+                "%Y-%m-%d %H:%M:%S",
+                "%Y-%m-%dT%H:%M:%S",
+                "%Y-%m-%d %H:%M",
+                "%d/%m/%Y %H:%M:%S",
+                "%d/%m/%Y %H:%M",
+                "%Y-%m-%d",
+                "%d/%m/%Y",
+                "%d-%m-%Y",
+            ],
+        };
+
+        formats.iter().find_map(|format| {
+            NaiveDateTime::parse_from_str(text, format)
+                .ok()
+                .or_else(|| {
+                    NaiveDate::parse_from_str(text, format)
+                        .ok()
+                        .map(|date| date.and_time(NaiveTime::MIN))
+                })
+        })
+    }
+
+    /// Source times are local; stored ones are UTC like those of datetime('now')
+    fn to_utc(local: NaiveDateTime) -> NaiveDateTime {
+        Local
+            .from_local_datetime(&local)
+            .earliest()
+            .map(|time| time.naive_utc())
+            .unwrap_or(local)
+    }
+
+    /// Amount with an optional currency sign, as 1,234.56 or with a decimal
+    /// comma as 1.234,56. Thousands groups must have three digits, so 50,5
+    /// without a decimal comma is rejected instead of read as 505.
+    fn parse_amount(text: &str, decimal_comma: bool) -> Option<f64> {
+        let cleaned: String = text
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '$')
+            .collect();
+        let (group, decimal) = if decimal_comma {
+            ('.', ',')
+        } else {
+            (',', '.')
+        };
+
+        let (integer, fraction) = match cleaned.split_once(decimal) {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (cleaned.as_str(), None),
+        };
+        let grouped = integer
+            .split(group)
+            .skip(1)
+            .all(|digits| digits.len() == 3 && digits.chars().all(|c| c.is_ascii_digit()));
+        if !grouped {
+            return None;
+        }
+
+        let integer = integer.replace(group, "");
+        let normalized = match fraction {
+            Some(fraction) => format!("{}.{}", integer, fraction),
+            None => integer,
+        };
+
+        normalized
+            .parse()
+            .ok()
+            .filter(|amount: &f64| amount.is_finite())
+    }
+
+    /// Accept the Spanish names used in spreadsheets; anything else is passed
+    /// through and rejected by the usual type validation
+    fn parse_type(text: &str) -> String {
+        match text.to_lowercase().as_str() {
+            "income" | "ingreso" | "i" => "income".to_string(),
+            "expense" | "egreso" | "gasto" | "e" => "expense".to_string(),
+            _ => text.to_string(),
+        }
+    }
+}
@@ -1,4 +1,11 @@
+pub mod analytics_service;
 pub mod backup_service;
+pub mod budget_service;
+pub mod export_service;
+pub mod import_service;
+pub mod printer_service;
+pub mod recurring_service;
 pub mod report_service;
+pub mod report_template_service;
 pub mod session_service;
 pub mod transaction_service;
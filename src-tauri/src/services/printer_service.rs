@@ -0,0 +1,518 @@
+use crate::db::Database;
+use crate::models::{PrinterTargetRequest, Transaction};
+use crate::services::session_service::{SessionService, SessionSummary};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Characters per line for Font A on 80mm paper
+const LINE_WIDTH: usize = 48;
+
+/// Default raw printing port (JetDirect / AppSocket)
+const DEFAULT_RAW_PORT: u16 = 9100;
+
+const ESC: u8 = 0x1B;
+const GS: u8 = 0x1D;
+const LF: u8 = 0x0A;
+
+/// ESC t n value for PC858 (Multilingual Latin I + Euro)
+const CODE_PAGE_PC858: u8 = 19;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Alignment {
+    Left,
+    Center,
+    Right,
+}
+
+/// Where the rendered byte stream is sent
+#[derive(Debug, Clone)]
+pub enum PrinterTarget {
+    /// A printer device node or share (e.g. /dev/usb/lp0, \\.\COM3, \\PC\TICKET)
+    Device(PathBuf),
+    /// A regular file, useful to inspect the output
+    File(PathBuf),
+    /// A network printer listening on a raw TCP port
+    Tcp { host: String, port: u16 },
+}
+
+impl PrinterTarget {
+    pub fn from_request(request: &PrinterTargetRequest) -> Result<Self, String> {
+        match request.target_type.as_str() {
+            "device" | "file" => {
+                let path = request
+                    .path
+                    .as_deref()
+                    .filter(|p| !p.trim().is_empty())
+                    .ok_or("Debe indicar la ruta de la impresora")?;
+
+                if request.target_type == "device" {
+                    Ok(PrinterTarget::Device(PathBuf::from(path)))
+                } else {
+                    Ok(PrinterTarget::File(PathBuf::from(path)))
+                }
+            }
+            "tcp" => {
+                let host = request
+                    .host
+                    .as_deref()
+                    .filter(|h| !h.trim().is_empty())
+                    .ok_or("Debe indicar la dirección de la impresora")?;
+
+                Ok(PrinterTarget::Tcp {
+                    host: host.to_string(),
+                    port: request.port.unwrap_or(DEFAULT_RAW_PORT),
+                })
+            }
+            _ => Err("Tipo de impresora no soportado. Use 'device', 'file' o 'tcp'".to_string()),
+        }
+    }
+
+    /// Write the raw bytes to the target, returning the number of bytes sent
+    pub fn send(&self, bytes: &[u8]) -> Result<usize, String> {
+        match self {
+            PrinterTarget::Device(path) => {
+                // Devices must already exist, never create or truncate them
+                let mut device = OpenOptions::new()
+                    .write(true)
+                    .open(path)
+                    .map_err(|e| format!("Error abriendo impresora: {}", e))?;
+                device
+                    .write_all(bytes)
+                    .map_err(|e| format!("Error enviando a la impresora: {}", e))?;
+                device
+                    .flush()
+                    .map_err(|e| format!("Error enviando a la impresora: {}", e))?;
+            }
+            PrinterTarget::File(path) => {
+                let mut file =
+                    File::create(path).map_err(|e| format!("Error creando archivo: {}", e))?;
+                file.write_all(bytes)
+                    .map_err(|e| format!("Error escribiendo archivo: {}", e))?;
+            }
+            PrinterTarget::Tcp { host, port } => {
+                let mut stream = TcpStream::connect((host.as_str(), *port))
+                    .map_err(|e| format!("Error conectando a {}:{}: {}", host, port, e))?;
+                stream
+                    .set_write_timeout(Some(Duration::from_secs(10)))
+                    .map_err(|e| e.to_string())?;
+                stream
+                    .write_all(bytes)
+                    .map_err(|e| format!("Error enviando a la impresora: {}", e))?;
+                stream
+                    .flush()
+                    .map_err(|e| format!("Error enviando a la impresora: {}", e))?;
+            }
+        }
+
+        Ok(bytes.len())
+    }
+}
+
+/// Raw ESC/POS byte stream builder
+pub struct EscPosDocument {
+    buf: Vec<u8>,
+}
+
+impl Default for EscPosDocument {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EscPosDocument {
+    /// Start a new document: reset the printer and select the PC858 code page
+    pub fn new() -> Self {
+        let mut doc = Self { buf: Vec::new() };
+        doc.buf.extend_from_slice(&[ESC, b'@']);
+        doc.buf.extend_from_slice(&[ESC, b't', CODE_PAGE_PC858]);
+        doc
+    }
+
+    pub fn text(&mut self, text: &str) -> &mut Self {
+        self.buf.extend(text.chars().map(encode_pc858));
+        self
+    }
+
+    pub fn line(&mut self, text: &str) -> &mut Self {
+        self.text(text);
+        self.buf.push(LF);
+        self
+    }
+
+    /// Write text wrapped at the line width, breaking on spaces when possible
+    pub fn wrapped(&mut self, text: &str) -> &mut Self {
+        for line in wrap_text(text, LINE_WIDTH) {
+            self.line(&line);
+        }
+        self
+    }
+
+    pub fn bold(&mut self, on: bool) -> &mut Self {
+        self.buf.extend_from_slice(&[ESC, b'E', on as u8]);
+        self
+    }
+
+    pub fn align(&mut self, alignment: Alignment) -> &mut Self {
+        let n = match alignment {
+            Alignment::Left => 0,
+            Alignment::Center => 1,
+            Alignment::Right => 2,
+        };
+        self.buf.extend_from_slice(&[ESC, b'a', n]);
+        self
+    }
+
+    /// Double width and height, used for headings and totals
+    pub fn double_size(&mut self, on: bool) -> &mut Self {
+        self.buf
+            .extend_from_slice(&[GS, b'!', if on { 0x11 } else { 0x00 }]);
+        self
+    }
+
+    pub fn separator(&mut self) -> &mut Self {
+        self.line(&"-".repeat(LINE_WIDTH))
+    }
+
+    /// A label on the left and its value flushed to the right margin
+    pub fn label_value(&mut self, label: &str, value: &str) -> &mut Self {
+        let used = label.chars().count() + value.chars().count();
+        if used >= LINE_WIDTH {
+            self.line(label);
+            self.align(Alignment::Right)
+                .line(value)
+                .align(Alignment::Left)
+        } else {
+            let padding = " ".repeat(LINE_WIDTH - used);
+            self.line(&format!("{}{}{}", label, padding, value))
+        }
+    }
+
+    pub fn feed(&mut self, lines: u8) -> &mut Self {
+        self.buf.extend_from_slice(&[ESC, b'd', lines]);
+        self
+    }
+
+    /// Print a QR code (model 2, error correction M) centered on the paper
+    pub fn qr_code(&mut self, data: &str, module_size: u8) -> &mut Self {
+        let data = data.as_bytes();
+        // Stored data is limited by the printer buffer; truncate rather than fail
+        let data = &data[..data.len().min(7089)];
+        let store_len = data.len() + 3;
+
+        self.align(Alignment::Center);
+        // Model 2
+        self.buf
+            .extend_from_slice(&[GS, b'(', b'k', 4, 0, 49, 65, 50, 0]);
+        // Module size
+        self.buf
+            .extend_from_slice(&[GS, b'(', b'k', 3, 0, 49, 67, module_size.clamp(1, 16)]);
+        // Error correction level M
+        self.buf
+            .extend_from_slice(&[GS, b'(', b'k', 3, 0, 49, 69, 49]);
+        // Store data
+        self.buf.extend_from_slice(&[
+            GS,
+            b'(',
+            b'k',
+            (store_len % 256) as u8,
+            (store_len / 256) as u8,
+            49,
+            80,
+            48,
+        ]);
+        self.buf.extend_from_slice(data);
+        // Print stored symbol
+        self.buf
+            .extend_from_slice(&[GS, b'(', b'k', 3, 0, 49, 81, 48]);
+        self.buf.push(LF);
+        self.align(Alignment::Left);
+        self
+    }
+
+    /// Feed past the tear bar and perform a partial cut
+    pub fn cut(&mut self) -> &mut Self {
+        self.buf.extend_from_slice(&[GS, b'V', 66, 3]);
+        self
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+pub struct PrinterService;
+
+impl PrinterService {
+    /// Render and print the receipt of a single transaction
+    pub fn print_transaction_receipt(
+        db: &Database,
+        transaction_id: i64,
+        target: &PrinterTarget,
+        include_qr: bool,
+    ) -> Result<usize, String> {
+        let transaction = db
+            .get_transaction_by_id(transaction_id)
+            .map_err(|e| format!("Error al obtener transacción: {}", e))?;
+        let session = db
+            .get_session_by_id(transaction.session_id)
+            .map_err(|e| format!("Error al obtener sesión: {}", e))?;
+
+        let bytes =
+            Self::render_transaction_receipt(&transaction, &session.operator_name, include_qr);
+        target.send(&bytes)
+    }
+
+    /// Render and print the closing summary of a session
+    pub fn print_session_close(
+        db: &Database,
+        session_id: i64,
+        target: &PrinterTarget,
+    ) -> Result<usize, String> {
+        let summary = SessionService::get_session_summary(db, session_id)
+            .map_err(|e| format!("Error al obtener resumen: {}", e))?;
+
+        let bytes = Self::render_session_close(&summary);
+        target.send(&bytes)
+    }
+
+    pub fn render_transaction_receipt(
+        transaction: &Transaction,
+        operator_name: &str,
+        include_qr: bool,
+    ) -> Vec<u8> {
+        let mut doc = EscPosDocument::new();
+        let title = if transaction.transaction_type == "income" {
+            "COMPROBANTE DE INGRESO"
+        } else {
+            "COMPROBANTE DE EGRESO"
+        };
+
+        Self::write_header(&mut doc, title);
+
+        doc.label_value("Número:", &transaction.transaction_number)
+            .label_value("Fecha:", &transaction.created_at)
+            .label_value("Sesión:", &format!("#{}", transaction.session_id))
+            .label_value("Operador:", operator_name)
+            .label_value(
+                "Categoría:",
+                if transaction.lines.is_empty() {
+                    transaction
+                        .category_name
+                        .as_deref()
+                        .unwrap_or("Sin categoría")
+                } else {
+                    "Dividida"
+                },
+            );
+
+        for line in &transaction.lines {
+            doc.label_value(
+                &format!(
+                    "  {}",
+                    line.category_name.as_deref().unwrap_or("Sin categoría")
+                ),
+                &format!("${:.2}", line.amount),
+            );
+        }
+
+        if let Some(counterparty) = &transaction.counterparty {
+            doc.label_value("Contraparte:", counterparty);
+        }
+
+        doc.separator()
+            .bold(true)
+            .line("Concepto:")
+            .bold(false)
+            .wrapped(&transaction.concept)
+            .separator()
+            .bold(true)
+            .double_size(true)
+            .align(Alignment::Right)
+            .line(&format!("${:.2}", transaction.amount))
+            .double_size(false)
+            .align(Alignment::Left)
+            .bold(false)
+            .separator()
+            .label_value("Registrado por:", &transaction.created_by);
+
+        if include_qr {
+            doc.feed(1).qr_code(&transaction.transaction_number, 6);
+        }
+
+        doc.feed(2)
+            .align(Alignment::Center)
+            .line("______________________")
+            .line("Firma")
+            .align(Alignment::Left);
+
+        Self::write_footer(&mut doc);
+        doc.into_bytes()
+    }
+
+    pub fn render_session_close(summary: &SessionSummary) -> Vec<u8> {
+        let mut doc = EscPosDocument::new();
+        let session = &summary.session;
+
+        Self::write_header(&mut doc, "CIERRE DE CAJA");
+
+        doc.label_value("Sesión:", &format!("#{}", session.id))
+            .label_value("Operador:", &session.operator_name)
+            .label_value("Apertura:", &session.opened_at)
+            .label_value(
+                "Cierre:",
+                session.closed_at.as_deref().unwrap_or("En curso"),
+            )
+            .separator()
+            .label_value(
+                "Monto de apertura:",
+                &format!("${:.2}", session.opening_amount),
+            )
+            .label_value(
+                &format!("Ingresos ({}):", summary.income_count),
+                &format!("${:.2}", summary.total_income),
+            )
+            .label_value(
+                &format!("Egresos ({}):", summary.expense_count),
+                &format!("${:.2}", summary.total_expense),
+            )
+            .separator()
+            .bold(true)
+            .label_value(
+                "Saldo esperado:",
+                &format!("${:.2}", summary.current_balance),
+            );
+
+        match session.closing_amount {
+            Some(closing_amount) => {
+                doc.label_value("Monto contado:", &format!("${:.2}", closing_amount))
+                    .label_value("Diferencia:", &format!("${:.2}", summary.difference));
+            }
+            None => {
+                doc.label_value("Monto contado:", "Pendiente");
+            }
+        }
+
+        doc.bold(false)
+            .separator()
+            .feed(3)
+            .align(Alignment::Center)
+            .line("______________________")
+            .line("Firma del operador")
+            .feed(2)
+            .line("______________________")
+            .line("Firma del supervisor")
+            .align(Alignment::Left);
+
+        Self::write_footer(&mut doc);
+        doc.into_bytes()
+    }
+
+    fn write_header(doc: &mut EscPosDocument, title: &str) {
+        doc.align(Alignment::Center)
+            .bold(true)
+            .double_size(true)
+            .line("CAJA CHOCA")
+            .double_size(false)
+            .line(title)
+            .bold(false)
+            .align(Alignment::Left)
+            .separator();
+    }
+
+    fn write_footer(doc: &mut EscPosDocument) {
+        let printed_at = chrono::Local::now().format("%Y-%m-%d %H:%M");
+        doc.feed(1)
+            .align(Alignment::Center)
+            .line(&format!("Impreso el: {}", printed_at))
+            .align(Alignment::Left)
+            .feed(4)
+            .cut();
+    }
+}
+
+/// Map a character to its PC858 byte, falling back to '?' for anything unprintable
+fn encode_pc858(c: char) -> u8 {
+    match c {
+        ' '..='~' => c as u8,
+        'Ç' => 0x80,
+        'ü' => 0x81,
+        'é' => 0x82,
+        'â' => 0x83,
+        'ä' => 0x84,
+        'à' => 0x85,
+        'ç' => 0x87,
+        'ê' => 0x88,
+        'è' => 0x8A,
+        'ï' => 0x8B,
+        'É' => 0x90,
+        'ô' => 0x93,
+        'ö' => 0x94,
+        'ò' => 0x95,
+        'û' => 0x96,
+        'ù' => 0x97,
+        'Ö' => 0x99,
+        'Ü' => 0x9A,
+        'á' => 0xA0,
+        'í' => 0xA1,
+        'ó' => 0xA2,
+        'ú' => 0xA3,
+        'ñ' => 0xA4,
+        'Ñ' => 0xA5,
+        'ª' => 0xA6,
+        'º' => 0xA7,
+        '¿' => 0xA8,
+        '¡' => 0xAD,
+        'Á' => 0xB5,
+        'Â' => 0xB6,
+        'À' => 0xB7,
+        '€' => 0xD5,
+        'Í' => 0xD6,
+        'Ó' => 0xE0,
+        'Ú' => 0xE9,
+        '°' => 0xF8,
+        '·' => 0xFA,
+        '\n' => LF,
+        _ => b'?',
+    }
+}
+
+fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+
+    for word in text.split_whitespace() {
+        let word_len = word.chars().count();
+        let current_len = current.chars().count();
+
+        if current_len > 0 && current_len + 1 + word_len > width {
+            lines.push(std::mem::take(&mut current));
+        }
+
+        if word_len > width {
+            // Hard-break words that do not fit on a single line
+            let chars: Vec<char> = word.chars().collect();
+            for chunk in chars.chunks(width) {
+                if chunk.len() == width {
+                    lines.push(chunk.iter().collect());
+                } else {
+                    current = chunk.iter().collect();
+                }
+            }
+            continue;
+        }
+
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+
+    if !current.is_empty() {
+        lines.push(current);
+    }
+
+    lines
+}
@@ -0,0 +1,220 @@
+use crate::db::Database;
+use crate::models::{
+    CreateRecurringTemplateRequest, CreateTransactionRequest, DueTemplate, RecurringTemplate,
+    Transaction,
+};
+use crate::services::session_service::SessionService;
+use crate::services::transaction_service::TransactionService;
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use rusqlite::Result;
+
+pub struct RecurringService;
+
+impl RecurringService {
+    /// Create a recurring transaction template.
+    /// 'monthly' is due from the first day of each month, 'weekly' on the given
+    /// weekday (1 = Monday ... 7 = Sunday) and 'fixed_day' on the given day of
+    /// the month (clamped to the last day of shorter months).
+    pub fn create_template(
+        db: &Database,
+        request: CreateRecurringTemplateRequest,
+    ) -> Result<RecurringTemplate> {
+        let name = request.name.trim();
+        if name.is_empty() {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "El nombre de la plantilla es requerido".to_string(),
+            ));
+        }
+
+        if request.concept.trim().is_empty() {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "El concepto es requerido".to_string(),
+            ));
+        }
+
+        if request.amount <= 0.0 {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "El monto debe ser mayor a cero".to_string(),
+            ));
+        }
+
+        if request.transaction_type != "income" && request.transaction_type != "expense" {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "El tipo de transacción debe ser 'income' o 'expense'".to_string(),
+            ));
+        }
+
+        let recurrence_day = match (request.recurrence.as_str(), request.recurrence_day) {
+            ("monthly", _) => None,
+            ("weekly", Some(day)) if (1..=7).contains(&day) => Some(day),
+            ("weekly", _) => {
+                return Err(rusqlite::Error::InvalidParameterName(
+                    "Indique el día de la semana (1 = lunes ... 7 = domingo)".to_string(),
+                ));
+            }
+            ("fixed_day", Some(day)) if (1..=31).contains(&day) => Some(day),
+            ("fixed_day", _) => {
+                return Err(rusqlite::Error::InvalidParameterName(
+                    "Indique el día del mes (1 a 31)".to_string(),
+                ));
+            }
+            _ => {
+                return Err(rusqlite::Error::InvalidParameterName(
+                    "La recurrencia debe ser 'monthly', 'weekly' o 'fixed_day'".to_string(),
+                ));
+            }
+        };
+
+        // Category rules are the same ones applied when the template is posted
+        if let Some(cat_id) = request.category_id {
+            TransactionService::validate_transaction_category(
+                db,
+                cat_id,
+                &request.transaction_type,
+            )?;
+        }
+
+        let start_date = match request.start_date.as_deref() {
+            Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| {
+                rusqlite::Error::InvalidParameterName("Fecha inválida. Use YYYY-MM-DD".to_string())
+            })?,
+            None => Local::now().date_naive(),
+        };
+
+        let counterparty = request
+            .counterparty
+            .as_deref()
+            .map(str::trim)
+            .filter(|c| !c.is_empty());
+
+        db.create_recurring_template(
+            name,
+            &request.transaction_type,
+            request.amount,
+            request.concept.trim(),
+            request.category_id,
+            counterparty,
+            &request.recurrence,
+            recurrence_day,
+            &start_date.format("%Y-%m-%d").to_string(),
+        )
+    }
+
+    /// Get all active templates
+    pub fn get_templates(db: &Database) -> Result<Vec<RecurringTemplate>> {
+        db.get_recurring_templates()
+    }
+
+    /// Delete (deactivate) a template
+    pub fn delete_template(db: &Database, template_id: i64) -> Result<()> {
+        let _ = db.get_recurring_template_by_id(template_id)?;
+        db.delete_recurring_template(template_id)
+    }
+
+    /// Templates due today that have not been posted in the current period
+    pub fn get_due_templates(db: &Database) -> Result<Vec<DueTemplate>> {
+        let today = Local::now().date_naive();
+
+        Ok(db
+            .get_recurring_templates()?
+            .into_iter()
+            .filter_map(|template| {
+                Self::due_date(&template, today).map(|due_date| DueTemplate {
+                    due_date: due_date.format("%Y-%m-%d").to_string(),
+                    template,
+                })
+            })
+            .collect())
+    }
+
+    /// Post a due template into the active session. The transaction goes through
+    /// `TransactionService::create_transaction`, so balance, category and budget
+    /// validations apply as for any manual entry.
+    pub fn post_template(db: &Database, template_id: i64, created_by: &str) -> Result<Transaction> {
+        let template = db.get_recurring_template_by_id(template_id)?;
+        let today = Local::now().date_naive();
+
+        if !template.is_active {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "La plantilla está desactivada".to_string(),
+            ));
+        }
+
+        if Self::due_date(&template, today).is_none() {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "La plantilla no está pendiente en este período".to_string(),
+            ));
+        }
+
+        let session = SessionService::get_active_session(db)?.ok_or_else(|| {
+            rusqlite::Error::InvalidParameterName("No hay una sesión activa".to_string())
+        })?;
+
+        let transaction = TransactionService::create_transaction(
+            db,
+            CreateTransactionRequest {
+                session_id: session.id,
+                transaction_type: template.transaction_type.clone(),
+                amount: template.amount,
+                concept: template.concept.clone(),
+                category_id: template.category_id,
+                counterparty: template.counterparty.clone(),
+                payment_method: None,
+                created_by: created_by.to_string(),
+                lines: Vec::new(),
+            },
+        )?;
+
+        db.mark_recurring_template_posted(template_id, &today.format("%Y-%m-%d").to_string())?;
+
+        Ok(transaction)
+    }
+
+    /// Due date of the template in the period containing `today`, if it is due
+    fn due_date(template: &RecurringTemplate, today: NaiveDate) -> Option<NaiveDate> {
+        if !template.is_active {
+            return None;
+        }
+
+        let month_start = today.with_day(1)?;
+        let (period_start, due) = match template.recurrence.as_str() {
+            "monthly" => (month_start, month_start),
+            "weekly" => {
+                let week_start =
+                    today - Duration::days(today.weekday().num_days_from_monday() as i64);
+                let day = template.recurrence_day?;
+                (week_start, week_start + Duration::days(day - 1))
+            }
+            "fixed_day" => {
+                let day = template
+                    .recurrence_day?
+                    .min(Self::days_in_month(month_start)? as i64);
+                (month_start, month_start.with_day(day as u32)?)
+            }
+            _ => return None,
+        };
+
+        let start_date = NaiveDate::parse_from_str(&template.start_date, "%Y-%m-%d").ok()?;
+        if today < due || due < start_date {
+            return None;
+        }
+
+        let posted_this_period = template
+            .last_posted_on
+            .as_deref()
+            .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+            .is_some_and(|posted| posted >= period_start);
+
+        if posted_this_period { None } else { Some(due) }
+    }
+
+    fn days_in_month(month_start: NaiveDate) -> Option<u32> {
+        let next_month = if month_start.month() == 12 {
+            NaiveDate::from_ymd_opt(month_start.year() + 1, 1, 1)
+        } else {
+            NaiveDate::from_ymd_opt(month_start.year(), month_start.month() + 1, 1)
+        }?;
+
+        Some(next_month.pred_opt()?.day())
+    }
+}
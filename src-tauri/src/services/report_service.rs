@@ -1,548 +1,4063 @@
 use crate::db::Database;
-use crate::models::Transaction;
+use crate::formatters;
+use crate::models::{
+    BudgetStatus, Category, ClosingDifference, GetTransactionsRequest, OperatorPerformance,
+    ReportAggregate, ReportCell, ReportChart, ReportColumn, ReportColumnKind, ReportCriterion,
+    ReportCriterionValue, ReportDocument, ReportFilters, ReportFormat, ReportFormula,
+    ReportGrouping, ReportKind, ReportRow, ReportRowStyle, ReportSection, ReportSpec,
+    ReportSummaryFormula, ReportSummaryItem, Session, Transaction,
+};
+use crate::services::budget_service::BudgetService;
+use crate::services::transaction_service::TransactionService;
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime};
 use printpdf::*;
 use rusqlite::Result;
-use rust_xlsxwriter::{Color, Format, Workbook};
+use rusqlite::types::Value;
+use rust_xlsxwriter::utility::column_number_to_name;
+use rust_xlsxwriter::{
+    Chart, ChartDataLabel, ChartType, Color, ExcelDateTime, Format, Formula, Table, Workbook,
+};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::File;
-use std::io::BufWriter;
-use std::path::PathBuf;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
 
 pub struct ReportService;
 
+/// Subtotal of a category including all of its subcategories
+#[derive(Debug, Clone)]
+pub struct CategorySubtotal {
+    pub category_id: Option<i64>,
+    pub name: String,
+    pub account_code: Option<String>,
+    pub transaction_type: String,
+    pub depth: usize,
+    pub amount: f64,
+    pub count: usize,
+}
+
+/// Kind of line in the cash book
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LedgerRowKind {
+    Opening,        // Session opening amount; the running balance restarts here
+    Movement,       // Income or expense
+    CarriedForward, // Balance at the end of a day
+}
+
+/// One line of the cash book with the balance after it
+#[derive(Debug, Clone)]
+pub struct LedgerRow {
+    pub kind: LedgerRowKind,
+    pub date: String, // Format: YYYY-MM-DD
+    pub number: String,
+    pub concept: String,
+    pub income: f64,
+    pub expense: f64,
+    pub balance: f64,
+}
+
+/// Totals of one ISO week with its category breakdown
+#[derive(Debug, Clone)]
+pub struct WeeklySummary {
+    pub year: i32,
+    pub week: u32,
+    pub start_date: String, // Monday, format: YYYY-MM-DD
+    pub end_date: String,   // Sunday
+    pub total_income: f64,
+    pub total_expense: f64,
+    pub income_count: usize,
+    pub expense_count: usize,
+    pub subtotals: Vec<CategorySubtotal>,
+}
+
+/// A session with the movement between its opening and closing
+#[derive(Debug, Clone)]
+pub struct SessionBalance {
+    pub session: Session,
+    pub total_income: f64,
+    pub total_expense: f64,
+    pub transaction_count: usize,
+    pub expected_closing: f64, // Opening plus income minus expense
+}
+
+/// Category totals of a period next to those of the period of the same
+/// length just before it
+#[derive(Debug, Clone)]
+pub struct CategoryBreakdown {
+    pub previous_start: String,
+    pub previous_end: String,
+    pub total_income: f64,
+    pub total_expense: f64,
+    pub rows: Vec<CategoryBreakdownRow>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CategoryBreakdownRow {
+    pub subtotal: CategorySubtotal,
+    pub share: f64,           // Percentage of the income or expense total
+    pub previous_amount: f64, // Same category in the previous period
+    pub change: Option<f64>,  // Percentage; None when the previous period was zero
+}
+
+/// Expense growth, in percent, highlighted when no threshold is given
+pub const DEFAULT_GROWTH_THRESHOLD: f64 = 10.0;
+
+/// Styles of HTML reports, inline so that the file stands alone
+const HTML_STYLE: &str = "<style>
+body { font-family: Helvetica, Arial, sans-serif; font-size: 13px; color: #222; margin: 24px; }
+h1 { font-size: 20px; color: #1f4e9a; margin: 0 0 4px; }
+h2 { font-size: 15px; margin: 20px 0 6px; }
+.subtitle { color: #555; margin: 0; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 12px; }
+th { background: #1f4e9a; color: #fff; text-align: left; padding: 4px 6px; }
+td { padding: 3px 6px; border-bottom: 1px solid #e5e5e5; }
+.num { text-align: right; white-space: nowrap; }
+.strong td, tfoot td { font-weight: bold; }
+.alert td { background: #fde2e1; color: #b00; font-weight: bold; }
+tfoot td { border-top: 2px solid #222; }
+table.summary { width: auto; }
+table.summary td { border: none; padding: 2px 16px 2px 0; }
+svg.chart { display: block; margin: 8px 0; font-size: 11px; }
+svg.chart .legend { fill: #666; }
+.note, footer { color: #666; font-size: 11px; }
+@page { size: A4; margin: 15mm; }
+@media print {
+  body { margin: 0; }
+  h2 { break-after: avoid; }
+  tr, svg.chart { break-inside: avoid; }
+  thead { display: table-header-group; }
+}
+</style>
+";
+
+const ODS_MIME_TYPE: &str = "application/vnd.oasis.opendocument.spreadsheet";
+
+const ODS_MANIFEST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<manifest:manifest xmlns:manifest="urn:oasis:names:tc:opendocument:xmlns:manifest:1.0" manifest:version="1.2">
+<manifest:file-entry manifest:full-path="/" manifest:version="1.2" manifest:media-type="application/vnd.oasis.opendocument.spreadsheet"/>
+<manifest:file-entry manifest:full-path="content.xml" manifest:media-type="text/xml"/>
+<manifest:file-entry manifest:full-path="settings.xml" manifest:media-type="text/xml"/>
+</manifest:manifest>
+"#;
+
+const ODS_CONTENT_HEADER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<office:document-content xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:style="urn:oasis:names:tc:opendocument:xmlns:style:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0" xmlns:table="urn:oasis:names:tc:opendocument:xmlns:table:1.0" xmlns:number="urn:oasis:names:tc:opendocument:xmlns:datastyle:1.0" xmlns:fo="urn:oasis:names:tc:opendocument:xmlns:xsl-fo-compatible:1.0" xmlns:of="urn:oasis:names:tc:opendocument:xmlns:of:1.2" office:version="1.2">
+"#;
+
+/// Number formats of ODS reports, the same as in Excel: 0, #,##0.00, 0.0%
+/// and dd/mm/yyyy
+const ODS_NUMBER_STYLES: &str = r#"<number:number-style style:name="N0"><number:number number:decimal-places="0" number:min-integer-digits="1"/></number:number-style>
+<number:number-style style:name="N2"><number:number number:decimal-places="2" number:min-integer-digits="1" number:grouping="true"/></number:number-style>
+<number:percentage-style style:name="P1"><number:number number:decimal-places="1" number:min-integer-digits="1"/><number:text>%</number:text></number:percentage-style>
+<number:date-style style:name="D1"><number:day number:style="long"/><number:text>/</number:text><number:month number:style="long"/><number:text>/</number:text><number:year number:style="long"/></number:date-style>
+"#;
+
+/// View settings of ODS reports around the per-sheet entries
+const ODS_SETTINGS_HEADER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<office:document-settings xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:config="urn:oasis:names:tc:opendocument:xmlns:config:1.0" office:version="1.2">
+<office:settings>
+<config:config-item-set config:name="ooo:view-settings">
+<config:config-item-map-indexed config:name="Views">
+<config:config-item-map-entry>
+<config:config-item config:name="ViewId" config:type="string">view1</config:config-item>
+"#;
+
+const ODS_SETTINGS_FOOTER: &str = r#"</config:config-item-map-entry>
+</config:config-item-map-indexed>
+</config:config-item-set>
+</office:settings>
+</office:document-settings>
+"#;
+
+/// Income, expense and category subtotals of two arbitrary periods
+#[derive(Debug, Clone)]
+pub struct PeriodComparison {
+    pub previous_start: String,
+    pub previous_end: String,
+    pub current_start: String,
+    pub current_end: String,
+    pub growth_threshold: f64,
+    pub previous_income: f64,
+    pub previous_expense: f64,
+    pub current_income: f64,
+    pub current_expense: f64,
+    pub rows: Vec<ComparisonRow>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ComparisonRow {
+    pub subtotal: CategorySubtotal, // Amount and count of the current period
+    pub previous_amount: f64,
+    pub variance: f64,
+    pub variance_percent: Option<f64>, // None when the previous period was zero
+    pub over_threshold: bool,          // Expense that grew beyond the threshold
+}
+
+/// Lays out lines top-down over as many A4 pages as needed
+struct PageFlow<'a> {
+    doc: &'a PdfDocumentReference,
+    layer: PdfLayerReference,
+    y: f32,
+}
+
+impl PageFlow<'_> {
+    /// Vertical position for a line of the given height, starting a new page
+    /// when the current one is full
+    fn line(&mut self, height: f32) -> f32 {
+        if !self.has_room(height) {
+            self.new_page();
+        }
+
+        let y = self.y;
+        self.y -= height;
+        y
+    }
+
+    fn has_room(&self, height: f32) -> bool {
+        self.y - height >= 20.0
+    }
+
+    fn new_page(&mut self) {
+        let (page, layer) = self.doc.add_page(Mm(210.0), Mm(297.0), "Layer 1");
+        self.layer = self.doc.get_page(page).get_layer(layer);
+        self.y = 280.0;
+    }
+}
+
+/// A sheet of a spreadsheet report before it is written as .xlsx or .ods
+struct SheetLayout {
+    name: String,
+    widths: Vec<u16>,             // Column widths in characters
+    cells: Vec<SheetCell>,        // Written cells only
+    merges: Vec<(u32, u16, u16)>, // Row, first and last column
+    charts: Vec<SheetChart>,
+    tables: Vec<SheetTable>,
+    frozen_rows: u32, // Rows kept in view when scrolling
+}
+
+impl SheetLayout {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            widths: Vec::new(),
+            cells: Vec::new(),
+            merges: Vec::new(),
+            charts: Vec::new(),
+            tables: Vec::new(),
+            frozen_rows: 0,
+        }
+    }
+
+    /// Cell at `row` and `col`. Empty cells without a formula are skipped.
+    fn push(
+        &mut self,
+        row: u32,
+        col: u16,
+        value: ReportCell,
+        formula: Option<SheetFormula>,
+        style: SheetStyle,
+    ) {
+        if value == ReportCell::Empty && formula.is_none() {
+            return;
+        }
+
+        self.cells.push(SheetCell {
+            row,
+            col,
+            value,
+            formula,
+            style,
+        });
+    }
+
+    fn text(&mut self, row: u32, col: u16, text: &str, style: SheetStyle) {
+        self.push(row, col, ReportCell::Text(text.to_string()), None, style);
+    }
+
+    /// Makes a column at least `width` characters wide
+    fn widen(&mut self, col: u16, width: u16) {
+        let col = col as usize;
+        if self.widths.len() <= col {
+            // Columns in between keep the spreadsheet default
+            self.widths.resize(col + 1, 9);
+        }
+        self.widths[col] = self.widths[col].max(width);
+    }
+}
+
+struct SheetCell {
+    row: u32,
+    col: u16,
+    value: ReportCell, // Cached result when there is a formula
+    formula: Option<SheetFormula>,
+    style: SheetStyle,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SheetStyle {
+    Title,
+    Header,                                        // Column labels
+    Heading,                                       // Section titles
+    Data(ReportColumnKind, ReportRowStyle, usize), // With the indent depth
+}
+
+impl SheetStyle {
+    const PLAIN: SheetStyle = SheetStyle::Data(ReportColumnKind::Text, ReportRowStyle::Normal, 0);
+}
+
+#[derive(Debug, Clone)]
+enum SheetFormula {
+    Column(ReportFormula),
+    Sum { first: u32, last: u32 }, // Rows of the same column
+    RowDifference { minuend: u32, subtrahend: u32 }, // Rows of the same column
+}
+
+/// Header row, last row and last column of a table starting on column A
+struct SheetTable {
+    first_row: u32,
+    last_row: u32,
+    last_col: u16,
+}
+
+/// Where the data rows of a section were laid out, for formulas over them
+#[derive(Debug, Clone, Default)]
+struct SectionRange {
+    sheet: String,
+    first_row: u32,
+    last_row: u32,
+}
+
+/// Pie of the last series and columns of every series of a chart data block
+struct SheetChart {
+    title: String,
+    section_title: String,
+    label_col: u16,
+    last_row: u32,              // Data starts on the row after the labels
+    series: Vec<(String, u16)>, // Name and column
+    row: u32,                   // Where the charts are placed
+}
+
 impl ReportService {
-    /// Generate a report in PDF or Excel format
-    pub fn generate_report(
-        db: &Database,
+    /// Generate the report described by `spec` in its format
+    pub fn generate_report(db: &Database, spec: &ReportSpec) -> Result<PathBuf, String> {
+        let document = Self::build_document(db, spec)?;
+
+        let (reports_dir, filename) = Self::prepare_output(
+            &Self::get_file_stem(spec),
+            &spec.start_date,
+            spec.options.download_path.as_deref(),
+        )?;
+
+        let extension = match spec.format {
+            ReportFormat::Pdf => "pdf",
+            ReportFormat::Excel => "xlsx",
+            ReportFormat::Ods => "ods",
+            ReportFormat::Html => "html",
+            ReportFormat::Markdown => "md",
+        };
+        let file_path = reports_dir.join(format!("{}.{}", filename, extension));
+
+        match spec.format {
+            ReportFormat::Pdf => Self::render_pdf(&document, &file_path)?,
+            ReportFormat::Excel => Self::render_excel(&document, &file_path)?,
+            ReportFormat::Ods => Self::render_ods(&document, &file_path)?,
+            ReportFormat::Html => Self::render_html(&document, &file_path)?,
+            ReportFormat::Markdown => Self::render_markdown(&document, &file_path)?,
+        }
+
+        Ok(file_path)
+    }
+
+    /// Content of the report described by `spec`. The frontend previews it
+    /// and `generate_report` renders the same document.
+    pub fn build_document(db: &Database, spec: &ReportSpec) -> Result<ReportDocument, String> {
+        Self::validate_spec(db, spec)?;
+
+        let currency = spec.options.currency.as_deref().unwrap_or("USD");
+        let (start_date, end_date) = (spec.start_date.as_str(), spec.end_date.as_str());
+
+        match spec.kind {
+            ReportKind::Budget => {
+                let statuses = BudgetService::get_budget_status(
+                    db,
+                    GetTransactionsRequest {
+                        start_date: Some(spec.start_date.clone()),
+                        end_date: Some(spec.end_date.clone()),
+                        ..Default::default()
+                    },
+                )
+                .map_err(|e| format!("Error al obtener presupuestos: {}", e))?;
+
+                Ok(Self::budget_document(&statuses, spec))
+            }
+            // The cash book has its own layout with a running balance
+            ReportKind::Ledger => {
+                let rows = Self::get_ledger_rows(db, start_date, end_date)?;
+
+                Ok(Self::ledger_document(&rows, spec))
+            }
+            // Sessions, movements and closing differences per operator
+            ReportKind::Operators => {
+                let operators = Self::get_operator_performance(db, start_date, end_date)?;
+
+                Ok(Self::operators_document(&operators, spec))
+            }
+            // Session balances: opening, movement and closing of each session
+            ReportKind::Balance => {
+                let balances = Self::get_session_balances(db, start_date, end_date)?;
+
+                Self::balance_document(&balances, spec, currency)
+            }
+            // Where the money goes: category shares against the previous period
+            ReportKind::Breakdown => {
+                let categories = db
+                    .get_all_categories()
+                    .map_err(|e| format!("Error al obtener categorías: {}", e))?;
+                let breakdown = Self::get_category_breakdown(db, start_date, end_date, categories)?;
+
+                Ok(Self::breakdown_document(&breakdown, spec))
+            }
+            // Category variance between the compared period and this one
+            ReportKind::Comparison => {
+                let (Some(compare_start), Some(compare_end)) = (
+                    spec.options.compare_start.as_deref(),
+                    spec.options.compare_end.as_deref(),
+                ) else {
+                    return Err("Indique el período con el que se compara".to_string());
+                };
+                let comparison = Self::get_period_comparison(
+                    db,
+                    (compare_start, compare_end),
+                    (start_date, end_date),
+                    spec.options
+                        .growth_threshold
+                        .unwrap_or(DEFAULT_GROWTH_THRESHOLD),
+                )?;
+
+                Self::comparison_document(&comparison, spec, currency)
+            }
+            ReportKind::Transactions => {
+                let transactions =
+                    Self::get_transactions_for_range(db, start_date, end_date, &spec.filters)?;
+
+                // Roll subtotals up the category tree
+                let categories = db
+                    .get_all_categories()
+                    .map_err(|e| format!("Error al obtener categorías: {}", e))?;
+
+                let mut document = match spec.grouping {
+                    // Weekly summary: totals and category breakdown per ISO week
+                    ReportGrouping::Week => {
+                        let weeks = Self::calculate_weekly_summaries(&transactions, categories);
+
+                        Self::weekly_document(&weeks, spec)
+                    }
+                    ReportGrouping::Category => {
+                        let subtotals =
+                            Self::calculate_category_subtotals(&transactions, categories);
+
+                        Self::transactions_document(&transactions, &subtotals, spec, currency)?
+                    }
+                };
+                document
+                    .subtitles
+                    .extend(Self::filter_lines(db, &spec.filters)?);
+
+                Ok(document)
+            }
+        }
+    }
+
+    /// Check a report definition before any query runs
+    pub fn validate_spec(db: &Database, spec: &ReportSpec) -> Result<(), String> {
+        Self::parse_range(&spec.start_date, &spec.end_date)?;
+
+        if spec.kind != ReportKind::Transactions {
+            if spec.filters != ReportFilters::default() {
+                return Err("Los filtros solo se aplican al reporte de movimientos".to_string());
+            }
+            if spec.grouping != ReportGrouping::default() {
+                return Err("La agrupación solo se aplica al reporte de movimientos".to_string());
+            }
+        }
+
+        if spec.kind == ReportKind::Comparison
+            && let (Some(compare_start), Some(compare_end)) = (
+                spec.options.compare_start.as_deref(),
+                spec.options.compare_end.as_deref(),
+            )
+        {
+            Self::parse_range(compare_start, compare_end)?;
+        }
+
+        let filters = &spec.filters;
+        if let Some(transaction_type) = filters.transaction_type.as_deref()
+            && transaction_type != "income"
+            && transaction_type != "expense"
+        {
+            return Err("El tipo debe ser 'income' o 'expense'".to_string());
+        }
+
+        for &category_id in &filters.category_ids {
+            let category = db
+                .get_category_by_id(category_id)
+                .map_err(|_| "Categoría no encontrada".to_string())?;
+            if let Some(transaction_type) = filters.transaction_type.as_deref()
+                && transaction_type != category.category_type
+            {
+                return Err("La categoría no corresponde al tipo seleccionado".to_string());
+            }
+        }
+
+        if let Some(tag_id) = filters.tag_id {
+            db.get_tag_by_id(tag_id)
+                .map_err(|_| "Etiqueta no encontrada".to_string())?;
+        }
+
+        Ok(())
+    }
+
+    /// Report name used in the file name, e.g. `transactions_expense_category_12`
+    fn get_file_stem(spec: &ReportSpec) -> String {
+        let mut stem = match spec.kind {
+            ReportKind::Transactions => "transactions",
+            ReportKind::Balance => "balance",
+            ReportKind::Ledger => "ledger",
+            ReportKind::Budget => "budget",
+            ReportKind::Breakdown => "breakdown",
+            ReportKind::Comparison => "comparison",
+            ReportKind::Operators => "operators",
+        }
+        .to_string();
+
+        if spec.grouping == ReportGrouping::Week {
+            stem.push_str("_weekly");
+        }
+        if let Some(transaction_type) = spec.filters.transaction_type.as_deref() {
+            stem.push('_');
+            stem.push_str(transaction_type);
+        }
+        if !spec.filters.category_ids.is_empty() {
+            let ids: Vec<String> = spec
+                .filters
+                .category_ids
+                .iter()
+                .map(i64::to_string)
+                .collect();
+            stem.push_str(&format!("_category_{}", ids.join("-")));
+        }
+        if let Some(tag_id) = spec.filters.tag_id {
+            stem.push_str(&format!("_tag_{}", tag_id));
+        }
+
+        stem
+    }
+
+    /// Reports directory (the custom one when given) and a timestamped file name
+    fn prepare_output(
         report_type: &str,
         start_date: &str,
-        end_date: &str,
-        format: &str,
         custom_download_path: Option<&str>,
-    ) -> Result<PathBuf, String> {
-        // Get transactions for the date range, filtered by report type
-        let transactions = Self::get_transactions_for_range(db, start_date, end_date, report_type)?;
+    ) -> Result<(PathBuf, String), String> {
+        // Generate filename
+        let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+        let filename = format!("{}_{}_{}", report_type, start_date, timestamp);
+
+        // Get reports directory (use custom path if provided)
+        let reports_dir = if let Some(path) = custom_download_path {
+            PathBuf::from(path)
+        } else {
+            Self::get_reports_directory()?
+        };
+
+        // Create directory if it doesn't exist
+        if !reports_dir.exists() {
+            std::fs::create_dir_all(&reports_dir)
+                .map_err(|e| format!("Error creating directory: {}", e))?;
+        }
+
+        Ok((reports_dir, filename))
+    }
+
+    fn get_transactions_for_range(
+        db: &Database,
+        start_date: &str,
+        end_date: &str,
+        filters: &ReportFilters,
+    ) -> Result<Vec<Transaction>, String> {
+        let conn = db.get_connection();
+        let conn = conn
+            .lock()
+            .map_err(|_| "Error al obtener conexión".to_string())?;
+
+        // Only placeholder numbers are formatted into the query; every value
+        // is bound
+        let mut params = vec![
+            Value::Text(start_date.to_string()),
+            Value::Text(end_date.to_string()),
+        ];
+        let mut amount = "t.amount";
+        let mut allocation_join = String::new();
+        let mut conditions = String::new();
+
+        // Category reports count only the part of each transaction that falls
+        // inside the categories or their subcategories
+        if !filters.category_ids.is_empty() {
+            params.push(Value::Text(
+                serde_json::to_string(&filters.category_ids).unwrap_or_else(|_| "[]".to_string()),
+            ));
+            amount = "a.amount";
+            allocation_join = format!(
+                "JOIN (
+                     SELECT transaction_id, SUM(amount) AS amount
+                     FROM transaction_allocations
+                     WHERE category_id IN (
+                         WITH RECURSIVE subtree(id) AS (
+                             SELECT value FROM json_each(?{})
+                             UNION
+                             SELECT sc.id FROM categories sc JOIN subtree ON sc.parent_id = subtree.id
+                         )
+                         SELECT id FROM subtree
+                     )
+                     GROUP BY transaction_id
+                 ) a ON a.transaction_id = t.id",
+                params.len()
+            );
+        }
+        if let Some(transaction_type) = &filters.transaction_type {
+            params.push(Value::Text(transaction_type.clone()));
+            conditions.push_str(&format!(" AND t.type = ?{}", params.len()));
+        }
+        // Profit and loss of everything carrying a tag (event or project)
+        if let Some(tag_id) = filters.tag_id {
+            params.push(Value::Integer(tag_id));
+            conditions.push_str(&format!(
+                " AND t.id IN (SELECT transaction_id FROM transaction_tags WHERE tag_id = ?{})",
+                params.len()
+            ));
+        }
+
+        // Times are local so that days and weeks match the range
+        let query = format!(
+            "SELECT 
+                t.id, t.session_id, t.transaction_number, t.type, {}, 
+                t.concept, t.category_id, c.name as category_name, {}, t.created_by,
+                t.counterparty, t.payment_method, t.voided_at
+             FROM transactions t
+             {}
+             LEFT JOIN categories c ON t.category_id = c.id
+             WHERE {} AND {}
+             AND t.voided_at IS NULL{}
+             ORDER BY t.created_at DESC",
+            amount,
+            Database::local_datetime("t.created_at"),
+            allocation_join,
+            Database::local_day_from("t.created_at", "?1"),
+            Database::local_day_until("t.created_at", "?2"),
+            conditions
+        );
+
+        let mut transactions = {
+            let mut stmt = conn
+                .prepare(&query)
+                .map_err(|e| format!("Error en query: {}", e))?;
+
+            let mapped = stmt
+                .query_map(rusqlite::params_from_iter(params.iter()), |row| {
+                    Ok(Transaction {
+                        id: row.get(0)?,
+                        session_id: row.get(1)?,
+                        transaction_number: row.get(2)?,
+                        transaction_type: row.get(3)?,
+                        amount: row.get(4)?,
+                        concept: row.get(5)?,
+                        category_id: row.get(6)?,
+                        category_name: row.get(7)?,
+                        created_at: row.get(8)?,
+                        created_by: row.get(9)?,
+                        counterparty: row.get(10)?,
+                        payment_method: row.get(11)?,
+                        voided_at: row.get(12)?,
+                        lines: Vec::new(),
+                        tags: Vec::new(),
+                    })
+                })
+                .map_err(|e| format!("Error mapeando resultados: {}", e))?;
+
+            mapped
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("Error colectando transacciones: {}", e))?
+        };
+
+        Database::load_transaction_details(&conn, &mut transactions)
+            .map_err(|e| format!("Error cargando detalle de transacciones: {}", e))?;
+
+        // Category reports only count the lines of split transactions that fall
+        // inside the categories
+        if !filters.category_ids.is_empty() {
+            let mut stmt = conn
+                .prepare(
+                    "WITH RECURSIVE subtree(id) AS (
+                         SELECT value FROM json_each(?1)
+                         UNION
+                         SELECT c.id FROM categories c JOIN subtree ON c.parent_id = subtree.id
+                     )
+                     SELECT id FROM subtree",
+                )
+                .map_err(|e| format!("Error en query: {}", e))?;
+            let category_ids =
+                serde_json::to_string(&filters.category_ids).unwrap_or_else(|_| "[]".to_string());
+            let subtree = stmt
+                .query_map([category_ids], |row| row.get::<_, i64>(0))
+                .and_then(|rows| rows.collect::<Result<HashSet<_>, _>>())
+                .map_err(|e| format!("Error obteniendo subcategorías: {}", e))?;
+
+            for transaction in &mut transactions {
+                transaction
+                    .lines
+                    .retain(|line| subtree.contains(&line.category_id));
+            }
+        }
+
+        Ok(transactions)
+    }
+
+    /// Cash book for the sessions opened in the range: each session starts from
+    /// its opening amount, movements follow in chronological order with the
+    /// balance after each one, and every day ends with its carried-forward balance.
+    pub fn get_ledger_rows(
+        db: &Database,
+        start_date: &str,
+        end_date: &str,
+    ) -> Result<Vec<LedgerRow>, String> {
+        let conn = db.get_connection();
+        let conn = conn
+            .lock()
+            .map_err(|_| "Error al obtener conexión".to_string())?;
+
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT s.id, s.operator_name, s.opening_amount, {},
+                    {}, t.transaction_number, t.concept, t.type, t.amount
+                 FROM sessions s
+                 LEFT JOIN transactions t ON t.session_id = s.id AND t.voided_at IS NULL
+                 WHERE {} AND {}
+                 ORDER BY s.opened_at, s.id, t.created_at, t.id",
+                Database::local_date("s.opened_at"),
+                Database::local_date("t.created_at"),
+                Database::local_day_from("s.opened_at", "?1"),
+                Database::local_day_until("s.opened_at", "?2")
+            ))
+            .map_err(|e| format!("Error en query: {}", e))?;
+
+        type LedgerQueryRow = (
+            i64,
+            String,
+            f64,
+            String,
+            Option<String>,
+            Option<String>,
+            Option<String>,
+            Option<String>,
+            Option<f64>,
+        );
+        let records = stmt
+            .query_map([start_date, end_date], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                    row.get(5)?,
+                    row.get(6)?,
+                    row.get(7)?,
+                    row.get(8)?,
+                ))
+            })
+            .and_then(|rows| rows.collect::<Result<Vec<LedgerQueryRow>, _>>())
+            .map_err(|e| format!("Error obteniendo movimientos: {}", e))?;
+
+        let mut rows: Vec<LedgerRow> = Vec::new();
+        let mut current_session = None;
+        let mut balance = 0.0;
+
+        for (session_id, operator, opening, opened_on, created_on, number, concept, kind, amount) in
+            records
+        {
+            if current_session != Some(session_id) {
+                current_session = Some(session_id);
+                Self::close_ledger_day(&mut rows, &opened_on, balance);
+                balance = opening;
+                rows.push(LedgerRow {
+                    kind: LedgerRowKind::Opening,
+                    date: opened_on,
+                    number: String::new(),
+                    concept: format!("Apertura de sesión #{} ({})", session_id, operator),
+                    income: 0.0,
+                    expense: 0.0,
+                    balance,
+                });
+            }
+
+            // Sessions without movements only have their opening line
+            let (Some(date), Some(amount)) = (created_on, amount) else {
+                continue;
+            };
+
+            Self::close_ledger_day(&mut rows, &date, balance);
+
+            let (income, expense) = if kind.as_deref() == Some("income") {
+                (amount, 0.0)
+            } else {
+                (0.0, amount)
+            };
+            balance += income - expense;
+
+            rows.push(LedgerRow {
+                kind: LedgerRowKind::Movement,
+                date,
+                number: number.unwrap_or_default(),
+                concept: concept.unwrap_or_default(),
+                income,
+                expense,
+                balance,
+            });
+        }
+
+        if let Some(last) = rows.last() {
+            let date = last.date.clone();
+            rows.push(LedgerRow {
+                kind: LedgerRowKind::CarriedForward,
+                concept: format!("Saldo al cierre del {}", date),
+                date,
+                number: String::new(),
+                income: 0.0,
+                expense: 0.0,
+                balance,
+            });
+        }
+
+        Ok(rows)
+    }
+
+    /// Sessions opened in the range with their income and expense totals
+    pub fn get_session_balances(
+        db: &Database,
+        start_date: &str,
+        end_date: &str,
+    ) -> Result<Vec<SessionBalance>, String> {
+        let conn = db.get_connection();
+        let conn = conn
+            .lock()
+            .map_err(|_| "Error al obtener conexión".to_string())?;
+
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT s.id, s.operator_name, s.opening_amount, s.closing_amount,
+                    {}, {}, s.is_active,
+                    COALESCE(SUM(CASE WHEN t.type = 'income' THEN t.amount END), 0),
+                    COALESCE(SUM(CASE WHEN t.type = 'expense' THEN t.amount END), 0),
+                    COUNT(t.id)
+                 FROM sessions s
+                 LEFT JOIN transactions t ON t.session_id = s.id AND t.voided_at IS NULL
+                 WHERE {} AND {}
+                 GROUP BY s.id
+                 ORDER BY s.opened_at, s.id",
+                Database::local_datetime("s.opened_at"),
+                Database::local_datetime("s.closed_at"),
+                Database::local_day_from("s.opened_at", "?1"),
+                Database::local_day_until("s.opened_at", "?2")
+            ))
+            .map_err(|e| format!("Error en query: {}", e))?;
+
+        stmt.query_map([start_date, end_date], |row| {
+            let session = Session {
+                id: row.get(0)?,
+                operator_name: row.get(1)?,
+                opening_amount: row.get(2)?,
+                closing_amount: row.get(3)?,
+                opened_at: row.get(4)?,
+                closed_at: row.get(5)?,
+                is_active: row.get(6)?,
+            };
+            let total_income: f64 = row.get(7)?;
+            let total_expense: f64 = row.get(8)?;
+
+            Ok(SessionBalance {
+                expected_closing: session.opening_amount + total_income - total_expense,
+                session,
+                total_income,
+                total_expense,
+                transaction_count: row.get::<_, i64>(9)? as usize,
+            })
+        })
+        .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| format!("Error obteniendo sesiones: {}", e))
+    }
+
+    /// Per-operator totals for the range: sessions come from `operator_name`
+    /// and movements from `created_by`, so an operator who recorded movements
+    /// in someone else's session is counted for those movements too
+    pub fn get_operator_performance(
+        db: &Database,
+        start_date: &str,
+        end_date: &str,
+    ) -> Result<Vec<OperatorPerformance>, String> {
+        let balances = Self::get_session_balances(db, start_date, end_date)?;
+
+        let movements: Vec<(String, i64, f64, f64)> = {
+            let conn = db.get_connection();
+            let conn = conn
+                .lock()
+                .map_err(|_| "Error al obtener conexión".to_string())?;
+
+            let mut stmt = conn
+                .prepare(&format!(
+                    "SELECT created_by, COUNT(*),
+                        COALESCE(SUM(CASE WHEN type = 'income' THEN amount END), 0),
+                        COALESCE(SUM(CASE WHEN type = 'expense' THEN amount END), 0)
+                     FROM transactions
+                     WHERE {} AND {}
+                     AND voided_at IS NULL
+                     GROUP BY created_by",
+                    Database::local_day_from("created_at", "?1"),
+                    Database::local_day_until("created_at", "?2")
+                ))
+                .map_err(|e| format!("Error en query: {}", e))?;
+
+            stmt.query_map([start_date, end_date], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("Error obteniendo movimientos: {}", e))?
+        };
+
+        fn entry<'a>(
+            operators: &'a mut BTreeMap<String, OperatorPerformance>,
+            name: &str,
+        ) -> &'a mut OperatorPerformance {
+            operators
+                .entry(name.to_string())
+                .or_insert_with(|| OperatorPerformance {
+                    operator_name: name.to_string(),
+                    session_count: 0,
+                    average_session_minutes: None,
+                    transaction_count: 0,
+                    total_income: 0.0,
+                    total_expense: 0.0,
+                    total_difference: 0.0,
+                    discrepancy_count: 0,
+                    closings: Vec::new(),
+                })
+        }
+
+        let mut operators = BTreeMap::new();
+        let mut durations: HashMap<String, Vec<f64>> = HashMap::new();
+
+        for balance in &balances {
+            let session = &balance.session;
+            let operator = entry(&mut operators, &session.operator_name);
+            operator.session_count += 1;
+
+            let (Some(closing_amount), Some(closed_at)) =
+                (session.closing_amount, session.closed_at.as_ref())
+            else {
+                continue;
+            };
+
+            if let (Ok(opened), Ok(closed)) = (
+                NaiveDateTime::parse_from_str(&session.opened_at, "%Y-%m-%d %H:%M:%S"),
+                NaiveDateTime::parse_from_str(closed_at, "%Y-%m-%d %H:%M:%S"),
+            ) {
+                durations
+                    .entry(session.operator_name.clone())
+                    .or_default()
+                    .push((closed - opened).num_seconds() as f64 / 60.0);
+            }
+
+            let difference = closing_amount - balance.expected_closing;
+            operator.total_difference += difference;
+            if difference.abs() >= 0.005 {
+                operator.discrepancy_count += 1;
+            }
+            operator.closings.push(ClosingDifference {
+                session_id: session.id,
+                opened_at: session.opened_at.clone(),
+                closed_at: closed_at.clone(),
+                expected_closing: balance.expected_closing,
+                closing_amount,
+                difference,
+            });
+        }
+
+        for (created_by, count, income, expense) in movements {
+            let operator = entry(&mut operators, &created_by);
+            operator.transaction_count = count;
+            operator.total_income = income;
+            operator.total_expense = expense;
+        }
+
+        for (name, minutes) in durations {
+            if let Some(operator) = operators.get_mut(&name) {
+                operator.average_session_minutes =
+                    Some(minutes.iter().sum::<f64>() / minutes.len() as f64);
+            }
+        }
+
+        Ok(operators.into_values().collect())
+    }
+
+    /// Group transactions by ISO week (Monday to Sunday), oldest week first.
+    /// Times must be local, as `get_transactions_for_range` returns them.
+    pub fn calculate_weekly_summaries(
+        transactions: &[Transaction],
+        categories: Vec<Category>,
+    ) -> Vec<WeeklySummary> {
+        let mut weeks: BTreeMap<(i32, u32), Vec<Transaction>> = BTreeMap::new();
+
+        for transaction in transactions {
+            let Some(date) = transaction
+                .created_at
+                .get(..10)
+                .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+            else {
+                continue;
+            };
+            let week = date.iso_week();
+            weeks
+                .entry((week.year(), week.week()))
+                .or_default()
+                .push(transaction.clone());
+        }
+
+        weeks
+            .into_iter()
+            .filter_map(|((year, week), transactions)| {
+                let monday = NaiveDate::from_isoywd_opt(year, week, chrono::Weekday::Mon)?;
+                let (total_income, total_expense, income_count, expense_count) =
+                    Self::calculate_totals(&transactions);
+
+                Some(WeeklySummary {
+                    year,
+                    week,
+                    start_date: monday.format("%Y-%m-%d").to_string(),
+                    end_date: (monday + Duration::days(6)).format("%Y-%m-%d").to_string(),
+                    total_income,
+                    total_expense,
+                    income_count,
+                    expense_count,
+                    subtotals: Self::calculate_category_subtotals(
+                        &transactions,
+                        categories.clone(),
+                    ),
+                })
+            })
+            .collect()
+    }
+
+    /// Category subtotals of the range with their share of the type total and
+    /// the change against the previous period of the same number of days
+    pub fn get_category_breakdown(
+        db: &Database,
+        start_date: &str,
+        end_date: &str,
+        categories: Vec<Category>,
+    ) -> Result<CategoryBreakdown, String> {
+        let (start, end) = Self::parse_range(start_date, end_date)?;
+
+        let previous_end = start - Duration::days(1);
+        let previous_start = previous_end - (end - start);
+        let previous_start = previous_start.format("%Y-%m-%d").to_string();
+        let previous_end = previous_end.format("%Y-%m-%d").to_string();
+
+        let current =
+            Self::get_transactions_for_range(db, start_date, end_date, &ReportFilters::default())?;
+        let previous = Self::get_transactions_for_range(
+            db,
+            &previous_start,
+            &previous_end,
+            &ReportFilters::default(),
+        )?;
+        let (total_income, total_expense, _, _) = Self::calculate_totals(&current);
+
+        let rows = Self::pair_category_subtotals(&current, &previous, categories)
+            .into_iter()
+            .map(|(subtotal, previous_amount)| {
+                let type_total = if subtotal.transaction_type == "income" {
+                    total_income
+                } else {
+                    total_expense
+                };
+
+                CategoryBreakdownRow {
+                    share: if type_total > 0.0 {
+                        subtotal.amount / type_total * 100.0
+                    } else {
+                        0.0
+                    },
+                    change: Self::percent_change(previous_amount, subtotal.amount),
+                    previous_amount,
+                    subtotal,
+                }
+            })
+            .collect();
+
+        Ok(CategoryBreakdown {
+            previous_start,
+            previous_end,
+            total_income,
+            total_expense,
+            rows,
+        })
+    }
+
+    /// Category subtotals of two periods side by side, with the income, expense
+    /// and net of each
+    pub fn get_period_comparison(
+        db: &Database,
+        previous: (&str, &str),
+        current: (&str, &str),
+        growth_threshold: f64,
+    ) -> Result<PeriodComparison, String> {
+        Self::parse_range(previous.0, previous.1)?;
+        Self::parse_range(current.0, current.1)?;
+        if growth_threshold < 0.0 {
+            return Err("El umbral de crecimiento no puede ser negativo".to_string());
+        }
+
+        let categories = db
+            .get_all_categories()
+            .map_err(|e| format!("Error al obtener categorías: {}", e))?;
+        let current_transactions =
+            Self::get_transactions_for_range(db, current.0, current.1, &ReportFilters::default())?;
+        let previous_transactions = Self::get_transactions_for_range(
+            db,
+            previous.0,
+            previous.1,
+            &ReportFilters::default(),
+        )?;
+
+        let (current_income, current_expense, _, _) = Self::calculate_totals(&current_transactions);
+        let (previous_income, previous_expense, _, _) =
+            Self::calculate_totals(&previous_transactions);
+
+        let rows = Self::pair_category_subtotals(
+            &current_transactions,
+            &previous_transactions,
+            categories,
+        )
+        .into_iter()
+        .map(|(subtotal, previous_amount)| {
+            let variance_percent = Self::percent_change(previous_amount, subtotal.amount);
+            // Spending that appears out of nothing also counts as growth
+            let grew = match variance_percent {
+                Some(percent) => percent > growth_threshold,
+                None => subtotal.amount > 0.0,
+            };
+
+            ComparisonRow {
+                over_threshold: subtotal.transaction_type == "expense" && grew,
+                variance: subtotal.amount - previous_amount,
+                variance_percent,
+                previous_amount,
+                subtotal,
+            }
+        })
+        .collect();
+
+        Ok(PeriodComparison {
+            previous_start: previous.0.to_string(),
+            previous_end: previous.1.to_string(),
+            current_start: current.0.to_string(),
+            current_end: current.1.to_string(),
+            growth_threshold,
+            previous_income,
+            previous_expense,
+            current_income,
+            current_expense,
+            rows,
+        })
+    }
+
+    /// Subtotals of the current period paired with the amount of the same
+    /// category in the previous one. Categories with movements in either
+    /// period are listed, so those that dropped to zero still show.
+    fn pair_category_subtotals(
+        current: &[Transaction],
+        previous: &[Transaction],
+        categories: Vec<Category>,
+    ) -> Vec<(CategorySubtotal, f64)> {
+        let key =
+            |subtotal: &CategorySubtotal| (subtotal.category_id, subtotal.transaction_type.clone());
+        let current_amounts: HashMap<_, _> =
+            Self::calculate_category_subtotals(current, categories.clone())
+                .into_iter()
+                .map(|subtotal| (key(&subtotal), (subtotal.amount, subtotal.count)))
+                .collect();
+        let previous_amounts: HashMap<_, _> =
+            Self::calculate_category_subtotals(previous, categories.clone())
+                .into_iter()
+                .map(|subtotal| (key(&subtotal), subtotal.amount))
+                .collect();
+
+        let both: Vec<Transaction> = current.iter().chain(previous.iter()).cloned().collect();
+
+        Self::calculate_category_subtotals(&both, categories)
+            .into_iter()
+            .map(|mut subtotal| {
+                let (amount, count) = current_amounts
+                    .get(&key(&subtotal))
+                    .copied()
+                    .unwrap_or((0.0, 0));
+                let previous_amount = previous_amounts
+                    .get(&key(&subtotal))
+                    .copied()
+                    .unwrap_or(0.0);
+
+                subtotal.amount = amount;
+                subtotal.count = count;
+                (subtotal, previous_amount)
+            })
+            .collect()
+    }
+
+    /// Percentage change from `previous` to `current`, relative to the size of
+    /// `previous` so a negative net that improves counts as growth. None when
+    /// there is nothing to compare against. Sheets use the same rule.
+    fn percent_change(previous: f64, current: f64) -> Option<f64> {
+        (previous != 0.0).then(|| (current - previous) / previous.abs() * 100.0)
+    }
+
+    fn parse_range(start_date: &str, end_date: &str) -> Result<(NaiveDate, NaiveDate), String> {
+        let start = NaiveDate::parse_from_str(start_date, "%Y-%m-%d")
+            .map_err(|_| format!("Fecha inválida: {}", start_date))?;
+        let end = NaiveDate::parse_from_str(end_date, "%Y-%m-%d")
+            .map_err(|_| format!("Fecha inválida: {}", end_date))?;
+        if end < start {
+            return Err(format!(
+                "La fecha de fin ({}) es anterior a la de inicio ({})",
+                end_date, start_date
+            ));
+        }
+
+        Ok((start, end))
+    }
+
+    /// Add the carried-forward line of the previous day when `date` starts a new one
+    fn close_ledger_day(rows: &mut Vec<LedgerRow>, date: &str, balance: f64) {
+        if let Some(last) = rows.last()
+            && last.date != date
+            && last.kind != LedgerRowKind::CarriedForward
+        {
+            let previous = last.date.clone();
+            rows.push(LedgerRow {
+                kind: LedgerRowKind::CarriedForward,
+                concept: format!("Saldo al cierre del {}", previous),
+                date: previous,
+                number: String::new(),
+                income: 0.0,
+                expense: 0.0,
+                balance,
+            });
+        }
+    }
+
+    fn get_reports_directory() -> Result<PathBuf, String> {
+        let docs_dir = dirs::document_dir()
+            .or_else(|| dirs::home_dir().map(|h| h.join("Documents")))
+            .ok_or("No se pudo encontrar directorio de documentos")?;
+
+        Ok(docs_dir.join("CajaChoca").join("Reportes"))
+    }
+
+    /// Document with the title of `spec` and nothing else yet
+    fn new_document(spec: &ReportSpec, subtitles: Vec<String>) -> ReportDocument {
+        ReportDocument {
+            title: Self::get_report_title(spec).to_string(),
+            subtitles,
+            generated_at: chrono::Local::now().format("%Y-%m-%d %H:%M").to_string(),
+            summary: Vec::new(),
+            summary_sheet: None,
+            sections: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    fn period_line(spec: &ReportSpec) -> String {
+        format!("Período: {} al {}", spec.start_date, spec.end_date)
+    }
+
+    /// Subtitles naming the category and the tag a movements report is limited to
+    fn filter_lines(db: &Database, filters: &ReportFilters) -> Result<Vec<String>, String> {
+        let mut lines = Vec::new();
+
+        if !filters.category_ids.is_empty() {
+            let names = filters
+                .category_ids
+                .iter()
+                .map(|&id| {
+                    db.get_category_by_id(id)
+                        .map(|category| category.name)
+                        .map_err(|_| "Categoría no encontrada".to_string())
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let label = if names.len() == 1 {
+                "Categoría"
+            } else {
+                "Categorías"
+            };
+            lines.push(format!("{}: {}", label, names.join(", ")));
+        }
+
+        if let Some(tag_id) = filters.tag_id {
+            let tag = db
+                .get_tag_by_id(tag_id)
+                .map_err(|_| "Etiqueta no encontrada".to_string())?;
+            lines.push(format!("Etiqueta: {}", tag.name));
+        }
+
+        Ok(lines)
+    }
+
+    fn column(label: &str, kind: ReportColumnKind, width: u16) -> ReportColumn {
+        ReportColumn {
+            label: label.to_string(),
+            kind,
+            width,
+            indent: false,
+            formula: None,
+        }
+    }
+
+    fn row(cells: Vec<ReportCell>) -> ReportRow {
+        ReportRow {
+            cells,
+            depth: 0,
+            style: ReportRowStyle::Normal,
+            literal: false,
+        }
+    }
+
+    fn text(value: impl Into<String>) -> ReportCell {
+        ReportCell::Text(value.into())
+    }
+
+    /// Amount cell that stays empty when there is nothing to show
+    fn amount_or_empty(amount: f64) -> ReportCell {
+        if amount > 0.0 {
+            ReportCell::Number(amount)
+        } else {
+            ReportCell::Empty
+        }
+    }
+
+    fn summary_item(label: &str, value: ReportCell, kind: ReportColumnKind) -> ReportSummaryItem {
+        ReportSummaryItem {
+            label: label.to_string(),
+            value,
+            kind,
+            strong: false,
+            formula: None,
+        }
+    }
+
+    fn type_label(transaction_type: &str) -> &'static str {
+        if transaction_type == "income" {
+            "Ingreso"
+        } else {
+            "Egreso"
+        }
+    }
+
+    /// Code, name, type, count and amount of a category subtotal
+    fn subtotal_columns() -> Vec<ReportColumn> {
+        vec![
+            Self::column("Código", ReportColumnKind::Text, 12),
+            ReportColumn {
+                indent: true,
+                ..Self::column("Categoría", ReportColumnKind::Text, 40)
+            },
+            Self::column("Tipo", ReportColumnKind::Text, 10),
+            Self::column("Cantidad", ReportColumnKind::Integer, 10),
+            Self::column("Subtotal", ReportColumnKind::Money, 15),
+        ]
+    }
+
+    fn subtotal_row(subtotal: &CategorySubtotal) -> ReportRow {
+        ReportRow {
+            depth: subtotal.depth,
+            style: if subtotal.depth == 0 {
+                ReportRowStyle::Strong
+            } else {
+                ReportRowStyle::Normal
+            },
+            ..Self::row(vec![
+                Self::text(subtotal.account_code.as_deref().unwrap_or("")),
+                Self::text(subtotal.name.as_str()),
+                Self::text(Self::type_label(&subtotal.transaction_type)),
+                ReportCell::Number(subtotal.count as f64),
+                ReportCell::Number(subtotal.amount),
+            ])
+        }
+    }
+
+    fn transactions_document(
+        transactions: &[Transaction],
+        subtotals: &[CategorySubtotal],
+        spec: &ReportSpec,
+        currency: &str,
+    ) -> Result<ReportDocument, String> {
+        let (total_income, total_expense, income_count, expense_count) =
+            Self::calculate_totals(transactions);
+        let balance = total_income - total_expense;
+
+        // Columns of the detail section, the first one, that the summary and
+        // the days are computed over
+        const DATE: usize = 0;
+        const TYPE: usize = 5;
+        const AMOUNT: usize = 6;
+        let type_is = |type_label: &str| ReportCriterion {
+            column: TYPE,
+            equals: ReportCriterionValue::Text(type_label.to_string()),
+        };
+        let by_type = |column: Option<usize>, type_label: &str| ReportAggregate {
+            section: 0,
+            column,
+            criteria: vec![type_is(type_label)],
+        };
+        let summary_aggregate = |label: &str, value: f64, kind, aggregate| ReportSummaryItem {
+            formula: Some(ReportSummaryFormula::Aggregate(aggregate)),
+            ..Self::summary_item(label, ReportCell::Number(value), kind)
+        };
+
+        let mut document = Self::new_document(spec, vec![Self::period_line(spec)]);
+        document.summary_sheet = Some("Resumen".to_string());
+        document.summary = vec![
+            summary_aggregate(
+                "Total ingresos",
+                total_income,
+                ReportColumnKind::Money,
+                by_type(Some(AMOUNT), "Ingreso"),
+            ),
+            summary_aggregate(
+                "Cantidad de ingresos",
+                income_count as f64,
+                ReportColumnKind::Integer,
+                by_type(None, "Ingreso"),
+            ),
+            summary_aggregate(
+                "Total egresos",
+                total_expense,
+                ReportColumnKind::Money,
+                by_type(Some(AMOUNT), "Egreso"),
+            ),
+            summary_aggregate(
+                "Cantidad de egresos",
+                expense_count as f64,
+                ReportColumnKind::Integer,
+                by_type(None, "Egreso"),
+            ),
+            ReportSummaryItem {
+                strong: true,
+                formula: Some(ReportSummaryFormula::Difference {
+                    minuend: 0,
+                    subtrahend: 2,
+                }),
+                ..Self::summary_item(
+                    "Balance",
+                    ReportCell::Number(balance),
+                    ReportColumnKind::Money,
+                )
+            },
+            Self::summary_item(
+                "Son",
+                Self::text(formatters::amount_to_words(balance, currency)?),
+                ReportColumnKind::Text,
+            ),
+        ];
+
+        // Income, expense and count of movements per day
+        let mut days: BTreeMap<&str, (f64, f64, usize)> = BTreeMap::new();
+        let rows = transactions
+            .iter()
+            .map(|transaction| {
+                let (date, time) = transaction
+                    .created_at
+                    .split_once([' ', 'T'])
+                    .unwrap_or((&transaction.created_at, ""));
+                // Split transactions list every category they touch
+                let category = if transaction.lines.is_empty() {
+                    transaction
+                        .category_name
+                        .clone()
+                        .unwrap_or_else(|| "Sin categoría".to_string())
+                } else {
+                    transaction
+                        .lines
+                        .iter()
+                        .map(|line| line.category_name.as_deref().unwrap_or("Sin categoría"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                };
+
+                let day = days.entry(date).or_default();
+                if transaction.transaction_type == "income" {
+                    day.0 += transaction.amount;
+                } else {
+                    day.1 += transaction.amount;
+                }
+                day.2 += 1;
+
+                Self::row(vec![
+                    Self::text(date),
+                    Self::text(time.get(..5).unwrap_or(time)),
+                    Self::text(transaction.transaction_number.as_str()),
+                    Self::text(transaction.concept.as_str()),
+                    Self::text(category),
+                    Self::text(Self::type_label(&transaction.transaction_type)),
+                    ReportCell::Number(transaction.amount),
+                    Self::text(transaction.created_by.as_str()),
+                ])
+            })
+            .collect();
+
+        document.sections.push(ReportSection {
+            title: "Detalle de transacciones".to_string(),
+            sheet: "Detalle".to_string(),
+            columns: vec![
+                Self::column("Fecha", ReportColumnKind::Date, 11),
+                Self::column("Hora", ReportColumnKind::Text, 7),
+                Self::column("Número", ReportColumnKind::Text, 14),
+                Self::column("Concepto", ReportColumnKind::Text, 35),
+                Self::column("Categoría", ReportColumnKind::Text, 18),
+                Self::column("Tipo", ReportColumnKind::Text, 10),
+                Self::column("Monto", ReportColumnKind::Money, 14),
+                Self::column("Registrado por", ReportColumnKind::Text, 15),
+            ],
+            rows,
+            totals: None,
+            chart: None,
+            table: true,
+        });
+
+        if !subtotals.is_empty() {
+            document.sections.push(ReportSection {
+                title: "Subtotales por categoría".to_string(),
+                sheet: "Por categoría".to_string(),
+                columns: Self::subtotal_columns(),
+                rows: subtotals.iter().map(Self::subtotal_row).collect(),
+                totals: None,
+                chart: None,
+                table: false,
+            });
+        }
+
+        let on_day = |column: Option<usize>, type_label: Option<&str>| {
+            let mut criteria = vec![ReportCriterion {
+                column: DATE,
+                equals: ReportCriterionValue::Column(0),
+            }];
+            criteria.extend(type_label.map(type_is));
+            Some(ReportFormula::Aggregate(ReportAggregate {
+                section: 0,
+                column,
+                criteria,
+            }))
+        };
+        let (day_income, day_expense, day_count) =
+            days.values().fold((0.0, 0.0, 0), |totals, day| {
+                (totals.0 + day.0, totals.1 + day.1, totals.2 + day.2)
+            });
+
+        document.sections.push(ReportSection {
+            title: "Movimientos por día".to_string(),
+            sheet: "Por día".to_string(),
+            columns: vec![
+                Self::column("Día", ReportColumnKind::Date, 12),
+                ReportColumn {
+                    formula: on_day(Some(AMOUNT), Some("Ingreso")),
+                    ..Self::column("Ingresos", ReportColumnKind::Money, 14)
+                },
+                ReportColumn {
+                    formula: on_day(Some(AMOUNT), Some("Egreso")),
+                    ..Self::column("Egresos", ReportColumnKind::Money, 14)
+                },
+                ReportColumn {
+                    formula: Some(ReportFormula::Difference {
+                        minuend: 1,
+                        subtrahend: 2,
+                    }),
+                    ..Self::column("Balance", ReportColumnKind::Money, 14)
+                },
+                ReportColumn {
+                    formula: on_day(None, None),
+                    ..Self::column("Movimientos", ReportColumnKind::Integer, 12)
+                },
+            ],
+            rows: days
+                .iter()
+                .map(|(date, (income, expense, count))| {
+                    Self::row(vec![
+                        Self::text(*date),
+                        ReportCell::Number(*income),
+                        ReportCell::Number(*expense),
+                        ReportCell::Number(income - expense),
+                        ReportCell::Number(*count as f64),
+                    ])
+                })
+                .collect(),
+            totals: (!days.is_empty()).then(|| ReportRow {
+                style: ReportRowStyle::Strong,
+                ..Self::row(vec![
+                    Self::text("TOTAL"),
+                    ReportCell::Number(day_income),
+                    ReportCell::Number(day_expense),
+                    ReportCell::Number(day_income - day_expense),
+                    ReportCell::Number(day_count as f64),
+                ])
+            }),
+            chart: None,
+            table: false,
+        });
+
+        Ok(document)
+    }
+
+    fn weekly_document(weeks: &[WeeklySummary], spec: &ReportSpec) -> ReportDocument {
+        let mut document = Self::new_document(spec, vec![Self::period_line(spec)]);
+
+        let week_label = |week: &WeeklySummary| format!("{}-W{:02}", week.year, week.week);
+
+        let rows = weeks
+            .iter()
+            .map(|week| {
+                Self::row(vec![
+                    Self::text(week_label(week)),
+                    Self::text(week.start_date.as_str()),
+                    Self::text(week.end_date.as_str()),
+                    ReportCell::Number(week.total_income),
+                    ReportCell::Number(week.total_expense),
+                    ReportCell::Number(week.total_income - week.total_expense),
+                    ReportCell::Number(week.income_count as f64),
+                    ReportCell::Number(week.expense_count as f64),
+                ])
+            })
+            .collect();
+        let total_income: f64 = weeks.iter().map(|week| week.total_income).sum();
+        let total_expense: f64 = weeks.iter().map(|week| week.total_expense).sum();
+
+        document.sections.push(ReportSection {
+            title: "Semanas".to_string(),
+            sheet: "Semanal".to_string(),
+            columns: vec![
+                Self::column("Semana", ReportColumnKind::Text, 12),
+                Self::column("Desde", ReportColumnKind::Date, 12),
+                Self::column("Hasta", ReportColumnKind::Date, 12),
+                Self::column("Ingresos", ReportColumnKind::Money, 15),
+                Self::column("Egresos", ReportColumnKind::Money, 15),
+                ReportColumn {
+                    formula: Some(ReportFormula::Difference {
+                        minuend: 3,
+                        subtrahend: 4,
+                    }),
+                    ..Self::column("Neto", ReportColumnKind::Money, 15)
+                },
+                Self::column("Cant. ingresos", ReportColumnKind::Integer, 12),
+                Self::column("Cant. egresos", ReportColumnKind::Integer, 12),
+            ],
+            rows,
+            totals: (!weeks.is_empty()).then(|| ReportRow {
+                style: ReportRowStyle::Strong,
+                ..Self::row(vec![
+                    Self::text("TOTAL"),
+                    ReportCell::Empty,
+                    ReportCell::Empty,
+                    ReportCell::Number(total_income),
+                    ReportCell::Number(total_expense),
+                    ReportCell::Number(total_income - total_expense),
+                    ReportCell::Empty,
+                    ReportCell::Empty,
+                ])
+            }),
+            chart: None,
+            table: false,
+        });
+
+        // Category breakdown of every week
+        let mut columns = vec![Self::column("Semana", ReportColumnKind::Text, 12)];
+        columns.extend(Self::subtotal_columns());
+        let rows = weeks
+            .iter()
+            .flat_map(|week| {
+                week.subtotals.iter().map(move |subtotal| {
+                    let mut row = Self::subtotal_row(subtotal);
+                    row.cells.insert(0, Self::text(week_label(week)));
+                    row
+                })
+            })
+            .collect();
+
+        document.sections.push(ReportSection {
+            title: "Por categoría".to_string(),
+            sheet: "Por categoría".to_string(),
+            columns,
+            rows,
+            totals: None,
+            chart: None,
+            table: false,
+        });
+
+        document
+    }
+
+    fn ledger_document(rows: &[LedgerRow], spec: &ReportSpec) -> ReportDocument {
+        let mut document = Self::new_document(spec, vec![Self::period_line(spec)]);
+
+        let report_rows = rows
+            .iter()
+            .map(|ledger_row| ReportRow {
+                style: if ledger_row.kind == LedgerRowKind::Movement {
+                    ReportRowStyle::Normal
+                } else {
+                    ReportRowStyle::Strong
+                },
+                // The running balance restarts from the amount counted at opening
+                literal: ledger_row.kind == LedgerRowKind::Opening,
+                ..Self::row(vec![
+                    Self::text(ledger_row.date.as_str()),
+                    Self::text(ledger_row.number.as_str()),
+                    Self::text(ledger_row.concept.as_str()),
+                    Self::amount_or_empty(ledger_row.income),
+                    Self::amount_or_empty(ledger_row.expense),
+                    ReportCell::Number(ledger_row.balance),
+                ])
+            })
+            .collect();
+
+        // Totals of the movements
+        let total_income: f64 = rows.iter().map(|row| row.income).sum();
+        let total_expense: f64 = rows.iter().map(|row| row.expense).sum();
+
+        document.sections.push(ReportSection {
+            title: "Movimientos".to_string(),
+            sheet: "Libro de caja".to_string(),
+            columns: vec![
+                Self::column("Fecha", ReportColumnKind::Date, 12),
+                Self::column("Número", ReportColumnKind::Text, 12),
+                Self::column("Concepto", ReportColumnKind::Text, 45),
+                Self::column("Ingreso", ReportColumnKind::Money, 14),
+                Self::column("Egreso", ReportColumnKind::Money, 14),
+                ReportColumn {
+                    formula: Some(ReportFormula::RunningBalance { plus: 3, minus: 4 }),
+                    ..Self::column("Saldo", ReportColumnKind::Money, 14)
+                },
+            ],
+            rows: report_rows,
+            totals: (!rows.is_empty()).then(|| ReportRow {
+                style: ReportRowStyle::Strong,
+                ..Self::row(vec![
+                    ReportCell::Empty,
+                    ReportCell::Empty,
+                    Self::text("TOTAL"),
+                    ReportCell::Number(total_income),
+                    ReportCell::Number(total_expense),
+                    ReportCell::Empty,
+                ])
+            }),
+            chart: None,
+            table: false,
+        });
+
+        document
+    }
+
+    fn balance_document(
+        balances: &[SessionBalance],
+        spec: &ReportSpec,
+        currency: &str,
+    ) -> Result<ReportDocument, String> {
+        let mut document = Self::new_document(spec, vec![Self::period_line(spec)]);
+
+        // Consolidated movement of the period
+        let total_income: f64 = balances.iter().map(|b| b.total_income).sum();
+        let total_expense: f64 = balances.iter().map(|b| b.total_expense).sum();
+        let net = total_income - total_expense;
+
+        if let (Some(first), Some(last)) = (balances.first(), balances.last()) {
+            document.summary.push(Self::summary_item(
+                &format!("Saldo de apertura (sesión #{})", first.session.id),
+                ReportCell::Number(first.session.opening_amount),
+                ReportColumnKind::Money,
+            ));
+            document.summary.push(Self::summary_item(
+                &format!("Saldo de cierre (sesión #{})", last.session.id),
+                last.session
+                    .closing_amount
+                    .map(ReportCell::Number)
+                    .unwrap_or_else(|| Self::text("sesión abierta")),
+                ReportColumnKind::Money,
+            ));
+        }
+        document.summary.extend([
+            Self::summary_item(
+                "Total ingresos",
+                ReportCell::Number(total_income),
+                ReportColumnKind::Money,
+            ),
+            Self::summary_item(
+                "Total egresos",
+                ReportCell::Number(total_expense),
+                ReportColumnKind::Money,
+            ),
+            ReportSummaryItem {
+                strong: true,
+                ..Self::summary_item(
+                    "Movimiento neto",
+                    ReportCell::Number(net),
+                    ReportColumnKind::Money,
+                )
+            },
+            Self::summary_item(
+                "Son",
+                Self::text(formatters::amount_to_words(net, currency)?),
+                ReportColumnKind::Text,
+            ),
+        ]);
+
+        // One row per session
+        let rows = balances
+            .iter()
+            .map(|balance| {
+                let session = &balance.session;
+                // Cash counted at closing that does not match the records
+                let difference = session
+                    .closing_amount
+                    .map(|closing| closing - balance.expected_closing);
+
+                ReportRow {
+                    style: if difference.is_some_and(|difference| difference.abs() >= 0.005) {
+                        ReportRowStyle::Alert
+                    } else {
+                        ReportRowStyle::Normal
+                    },
+                    ..Self::row(vec![
+                        ReportCell::Number(session.id as f64),
+                        Self::text(session.operator_name.as_str()),
+                        Self::text(session.opened_at.as_str()),
+                        Self::text(session.closed_at.as_deref().unwrap_or("Abierta")),
+                        ReportCell::Number(session.opening_amount),
+                        ReportCell::Number(balance.total_income),
+                        ReportCell::Number(balance.total_expense),
+                        ReportCell::Number(balance.expected_closing),
+                        session
+                            .closing_amount
+                            .map_or(ReportCell::Empty, ReportCell::Number),
+                        difference.map_or(ReportCell::Empty, ReportCell::Number),
+                        ReportCell::Number(balance.transaction_count as f64),
+                    ])
+                }
+            })
+            .collect();
+
+        document.sections.push(ReportSection {
+            title: "Sesiones".to_string(),
+            sheet: "Balance".to_string(),
+            columns: vec![
+                Self::column("Sesión", ReportColumnKind::Integer, 8),
+                Self::column("Operador", ReportColumnKind::Text, 18),
+                Self::column("Apertura", ReportColumnKind::Text, 18),
+                Self::column("Cierre", ReportColumnKind::Text, 18),
+                Self::column("Monto apertura", ReportColumnKind::Money, 14),
+                Self::column("Ingresos", ReportColumnKind::Money, 14),
+                Self::column("Egresos", ReportColumnKind::Money, 14),
+                ReportColumn {
+                    formula: Some(ReportFormula::Balance {
+                        base: 4,
+                        plus: 5,
+                        minus: 6,
+                    }),
+                    ..Self::column("Saldo esperado", ReportColumnKind::Money, 14)
+                },
+                Self::column("Monto cierre", ReportColumnKind::Money, 14),
+                ReportColumn {
+                    formula: Some(ReportFormula::Difference {
+                        minuend: 8,
+                        subtrahend: 7,
+                    }),
+                    ..Self::column("Diferencia", ReportColumnKind::Money, 14)
+                },
+                Self::column("Movimientos", ReportColumnKind::Integer, 12),
+            ],
+            rows,
+            totals: (!balances.is_empty()).then(|| {
+                let mut cells = vec![ReportCell::Empty; 11];
+                cells[1] = Self::text("TOTAL");
+                cells[5] = ReportCell::Number(total_income);
+                cells[6] = ReportCell::Number(total_expense);
+                ReportRow {
+                    style: ReportRowStyle::Strong,
+                    ..Self::row(cells)
+                }
+            }),
+            chart: None,
+            table: false,
+        });
+
+        Ok(document)
+    }
+
+    fn breakdown_document(breakdown: &CategoryBreakdown, spec: &ReportSpec) -> ReportDocument {
+        let mut document = Self::new_document(
+            spec,
+            vec![
+                Self::period_line(spec),
+                format!(
+                    "Comparado con: {} al {}",
+                    breakdown.previous_start, breakdown.previous_end
+                ),
+            ],
+        );
+        document.summary = vec![
+            Self::summary_item(
+                "Total ingresos",
+                ReportCell::Number(breakdown.total_income),
+                ReportColumnKind::Money,
+            ),
+            Self::summary_item(
+                "Total egresos",
+                ReportCell::Number(breakdown.total_expense),
+                ReportColumnKind::Money,
+            ),
+        ];
+
+        for (transaction_type, label) in [("income", "Ingresos"), ("expense", "Egresos")] {
+            let rows = breakdown
+                .rows
+                .iter()
+                .filter(|row| row.subtotal.transaction_type == transaction_type)
+                .map(|row| ReportRow {
+                    depth: row.subtotal.depth,
+                    style: if row.subtotal.depth == 0 {
+                        ReportRowStyle::Strong
+                    } else {
+                        ReportRowStyle::Normal
+                    },
+                    ..Self::row(vec![
+                        Self::text(row.subtotal.account_code.as_deref().unwrap_or("")),
+                        Self::text(row.subtotal.name.as_str()),
+                        ReportCell::Number(row.subtotal.count as f64),
+                        ReportCell::Number(row.subtotal.amount),
+                        ReportCell::Number(row.share / 100.0),
+                        ReportCell::Number(row.previous_amount),
+                        row.change.map_or(ReportCell::Empty, |change| {
+                            ReportCell::Number(change / 100.0)
+                        }),
+                    ])
+                })
+                .collect();
+
+            document.sections.push(ReportSection {
+                title: label.to_string(),
+                sheet: "Por categoría".to_string(),
+                columns: vec![
+                    Self::column("Código", ReportColumnKind::Text, 12),
+                    ReportColumn {
+                        indent: true,
+                        ..Self::column("Categoría", ReportColumnKind::Text, 40)
+                    },
+                    Self::column("Cantidad", ReportColumnKind::Integer, 10),
+                    Self::column("Período actual", ReportColumnKind::Money, 15),
+                    Self::column("% del total", ReportColumnKind::Percent, 12),
+                    Self::column("Período anterior", ReportColumnKind::Money, 16),
+                    ReportColumn {
+                        formula: Some(ReportFormula::Change {
+                            previous: 5,
+                            current: 3,
+                        }),
+                        ..Self::column("Variación", ReportColumnKind::Percent, 12)
+                    },
+                ],
+                rows,
+                totals: None,
+                // This period against the previous one, top-level categories
+                chart: Some(ReportChart {
+                    title: format!("{} por categoría", label),
+                    label_column: 1,
+                    value_columns: vec![5, 3],
+                }),
+                table: false,
+            });
+        }
+
+        document
+    }
+
+    fn comparison_document(
+        comparison: &PeriodComparison,
+        spec: &ReportSpec,
+        currency: &str,
+    ) -> Result<ReportDocument, String> {
+        let mut document = Self::new_document(
+            spec,
+            vec![
+                format!(
+                    "Período anterior: {} al {}",
+                    comparison.previous_start, comparison.previous_end
+                ),
+                format!(
+                    "Período actual: {} al {}",
+                    comparison.current_start, comparison.current_end
+                ),
+            ],
+        );
+
+        let previous_net = comparison.previous_income - comparison.previous_expense;
+        let current_net = comparison.current_income - comparison.current_expense;
+        document.summary = vec![
+            ReportSummaryItem {
+                strong: true,
+                ..Self::summary_item(
+                    "Neto del período actual",
+                    ReportCell::Number(current_net),
+                    ReportColumnKind::Money,
+                )
+            },
+            Self::summary_item(
+                "Son",
+                Self::text(formatters::amount_to_words(current_net, currency)?),
+                ReportColumnKind::Text,
+            ),
+        ];
+
+        // Variance columns are computed from the two amounts
+        let variance_columns = |first: usize| {
+            vec![
+                Self::column("Período anterior", ReportColumnKind::Money, 16),
+                Self::column("Período actual", ReportColumnKind::Money, 16),
+                ReportColumn {
+                    formula: Some(ReportFormula::Difference {
+                        minuend: first + 1,
+                        subtrahend: first,
+                    }),
+                    ..Self::column("Variación", ReportColumnKind::Money, 15)
+                },
+                ReportColumn {
+                    formula: Some(ReportFormula::Change {
+                        previous: first,
+                        current: first + 1,
+                    }),
+                    ..Self::column("Variación %", ReportColumnKind::Percent, 12)
+                },
+            ]
+        };
+        let variance_cells = |previous: f64, current: f64| {
+            vec![
+                ReportCell::Number(previous),
+                ReportCell::Number(current),
+                ReportCell::Number(current - previous),
+                Self::percent_change(previous, current).map_or(ReportCell::Empty, |percent| {
+                    ReportCell::Number(percent / 100.0)
+                }),
+            ]
+        };
+
+        // Income, expense and net of both periods
+        let mut columns = vec![Self::column("Concepto", ReportColumnKind::Text, 40)];
+        columns.extend(variance_columns(1));
+        let rows = [
+            (
+                "Ingresos",
+                comparison.previous_income,
+                comparison.current_income,
+                ReportRowStyle::Normal,
+            ),
+            (
+                "Egresos",
+                comparison.previous_expense,
+                comparison.current_expense,
+                ReportRowStyle::Normal,
+            ),
+            ("Neto", previous_net, current_net, ReportRowStyle::Strong),
+        ]
+        .into_iter()
+        .map(|(label, previous, current, style)| {
+            let mut cells = vec![Self::text(label)];
+            cells.extend(variance_cells(previous, current));
+            ReportRow {
+                style,
+                ..Self::row(cells)
+            }
+        })
+        .collect();
+
+        document.sections.push(ReportSection {
+            title: "Resumen".to_string(),
+            sheet: "Comparativo".to_string(),
+            columns,
+            rows,
+            totals: None,
+            chart: None,
+            table: false,
+        });
+
+        // Category detail, by type
+        for (transaction_type, label) in [("income", "Ingresos"), ("expense", "Egresos")] {
+            let mut columns = vec![
+                Self::column("Código", ReportColumnKind::Text, 12),
+                ReportColumn {
+                    indent: true,
+                    ..Self::column("Categoría", ReportColumnKind::Text, 40)
+                },
+            ];
+            columns.extend(variance_columns(2));
+            columns.push(Self::column("Alerta", ReportColumnKind::Text, 20));
+
+            let rows = comparison
+                .rows
+                .iter()
+                .filter(|row| row.subtotal.transaction_type == transaction_type)
+                .map(|row| {
+                    let mut cells = vec![
+                        Self::text(row.subtotal.account_code.as_deref().unwrap_or("")),
+                        Self::text(row.subtotal.name.as_str()),
+                    ];
+                    cells.extend(variance_cells(row.previous_amount, row.subtotal.amount));
+                    cells.push(if row.over_threshold {
+                        Self::text(format!("Creció más de {:.1}%", comparison.growth_threshold))
+                    } else {
+                        ReportCell::Empty
+                    });
+
+                    ReportRow {
+                        depth: row.subtotal.depth,
+                        style: if row.over_threshold {
+                            ReportRowStyle::Alert
+                        } else if row.subtotal.depth == 0 {
+                            ReportRowStyle::Strong
+                        } else {
+                            ReportRowStyle::Normal
+                        },
+                        ..Self::row(cells)
+                    }
+                })
+                .collect();
+
+            document.sections.push(ReportSection {
+                title: label.to_string(),
+                sheet: "Comparativo".to_string(),
+                columns,
+                rows,
+                totals: None,
+                chart: None,
+                table: false,
+            });
+        }
+
+        if comparison.rows.iter().any(|row| row.over_threshold) {
+            document.notes.push(format!(
+                "Resaltado: gasto que creció más de {:.1}% respecto al período anterior",
+                comparison.growth_threshold
+            ));
+        }
+
+        Ok(document)
+    }
+
+    fn operators_document(operators: &[OperatorPerformance], spec: &ReportSpec) -> ReportDocument {
+        let mut document = Self::new_document(spec, vec![Self::period_line(spec)]);
+
+        let rows = operators
+            .iter()
+            .map(|operator| {
+                Self::row(vec![
+                    Self::text(operator.operator_name.as_str()),
+                    ReportCell::Number(operator.session_count as f64),
+                    operator
+                        .average_session_minutes
+                        .map_or(ReportCell::Empty, ReportCell::Number),
+                    ReportCell::Number(operator.transaction_count as f64),
+                    ReportCell::Number(operator.total_income),
+                    ReportCell::Number(operator.total_expense),
+                    ReportCell::Number(operator.total_difference),
+                    ReportCell::Number(operator.discrepancy_count as f64),
+                ])
+            })
+            .collect();
+
+        document.sections.push(ReportSection {
+            title: "Operadores".to_string(),
+            sheet: "Operadores".to_string(),
+            columns: vec![
+                Self::column("Operador", ReportColumnKind::Text, 25),
+                Self::column("Sesiones", ReportColumnKind::Integer, 10),
+                Self::column("Duración promedio (min)", ReportColumnKind::Integer, 18),
+                Self::column("Movimientos", ReportColumnKind::Integer, 13),
+                Self::column("Ingresos", ReportColumnKind::Money, 15),
+                Self::column("Egresos", ReportColumnKind::Money, 15),
+                Self::column("Diferencia acumulada", ReportColumnKind::Money, 18),
+                Self::column("Cierres con diferencia", ReportColumnKind::Integer, 18),
+            ],
+            rows,
+            totals: None,
+            chart: None,
+            table: false,
+        });
+
+        // History of closing differences, one row per closed session
+        let closings: Vec<(&OperatorPerformance, &ClosingDifference)> = operators
+            .iter()
+            .flat_map(|operator| {
+                operator
+                    .closings
+                    .iter()
+                    .map(move |closing| (operator, closing))
+            })
+            .collect();
+        let total_difference: f64 = closings.iter().map(|(_, closing)| closing.difference).sum();
+
+        let rows = closings
+            .iter()
+            .map(|(operator, closing)| ReportRow {
+                style: if closing.difference.abs() >= 0.005 {
+                    ReportRowStyle::Strong
+                } else {
+                    ReportRowStyle::Normal
+                },
+                ..Self::row(vec![
+                    Self::text(operator.operator_name.as_str()),
+                    ReportCell::Number(closing.session_id as f64),
+                    Self::text(closing.opened_at.as_str()),
+                    Self::text(closing.closed_at.as_str()),
+                    ReportCell::Number(closing.expected_closing),
+                    ReportCell::Number(closing.closing_amount),
+                    ReportCell::Number(closing.difference),
+                ])
+            })
+            .collect();
+
+        document.sections.push(ReportSection {
+            title: "Diferencias de cierre".to_string(),
+            sheet: "Diferencias de cierre".to_string(),
+            columns: vec![
+                Self::column("Operador", ReportColumnKind::Text, 25),
+                Self::column("Sesión", ReportColumnKind::Integer, 10),
+                Self::column("Apertura", ReportColumnKind::Text, 20),
+                Self::column("Cierre", ReportColumnKind::Text, 20),
+                Self::column("Saldo esperado", ReportColumnKind::Money, 15),
+                Self::column("Monto contado", ReportColumnKind::Money, 15),
+                ReportColumn {
+                    formula: Some(ReportFormula::Difference {
+                        minuend: 5,
+                        subtrahend: 4,
+                    }),
+                    ..Self::column("Diferencia", ReportColumnKind::Money, 15)
+                },
+            ],
+            rows,
+            totals: (!closings.is_empty()).then(|| {
+                let mut cells = vec![ReportCell::Empty; 7];
+                cells[0] = Self::text("TOTAL");
+                cells[6] = ReportCell::Number(total_difference);
+                ReportRow {
+                    style: ReportRowStyle::Strong,
+                    ..Self::row(cells)
+                }
+            }),
+            chart: None,
+            table: false,
+        });
+
+        document
+    }
+
+    fn budget_document(statuses: &[BudgetStatus], spec: &ReportSpec) -> ReportDocument {
+        let mut document = Self::new_document(spec, vec![Self::period_line(spec)]);
+
+        let execution = |budget: f64, actual: f64| {
+            if budget == 0.0 {
+                ReportCell::Empty
+            } else {
+                ReportCell::Number(actual / budget)
+            }
+        };
+
+        let rows = statuses
+            .iter()
+            .map(|status| {
+                let budget = &status.budget;
+                let period_label = if budget.period_type == "month" {
+                    "Mensual"
+                } else {
+                    "Trimestral"
+                };
+
+                Self::row(vec![
+                    Self::text(budget.category_name.as_deref().unwrap_or("Sin categoría")),
+                    Self::text(period_label),
+                    Self::text(budget.period_start.as_str()),
+                    Self::text(budget.period_end.as_str()),
+                    ReportCell::Number(budget.amount),
+                    ReportCell::Number(status.actual),
+                    ReportCell::Number(budget.amount - status.actual),
+                    execution(budget.amount, status.actual),
+                ])
+            })
+            .collect();
+
+        // Totals
+        let total_budget: f64 = statuses.iter().map(|status| status.budget.amount).sum();
+        let total_actual: f64 = statuses.iter().map(|status| status.actual).sum();
+
+        document.sections.push(ReportSection {
+            title: "Ejecución".to_string(),
+            sheet: "Ejecución".to_string(),
+            columns: vec![
+                Self::column("Categoría", ReportColumnKind::Text, 30),
+                Self::column("Período", ReportColumnKind::Text, 10),
+                Self::column("Desde", ReportColumnKind::Date, 12),
+                Self::column("Hasta", ReportColumnKind::Date, 12),
+                Self::column("Presupuesto", ReportColumnKind::Money, 15),
+                Self::column("Ejecutado", ReportColumnKind::Money, 15),
+                ReportColumn {
+                    formula: Some(ReportFormula::Difference {
+                        minuend: 4,
+                        subtrahend: 5,
+                    }),
+                    ..Self::column("Saldo", ReportColumnKind::Money, 15)
+                },
+                ReportColumn {
+                    formula: Some(ReportFormula::Ratio {
+                        numerator: 5,
+                        denominator: 4,
+                    }),
+                    ..Self::column("% Ejecución", ReportColumnKind::Percent, 12)
+                },
+            ],
+            rows,
+            totals: (!statuses.is_empty()).then(|| ReportRow {
+                style: ReportRowStyle::Strong,
+                ..Self::row(vec![
+                    Self::text("TOTAL"),
+                    ReportCell::Empty,
+                    ReportCell::Empty,
+                    ReportCell::Empty,
+                    ReportCell::Number(total_budget),
+                    ReportCell::Number(total_actual),
+                    ReportCell::Number(total_budget - total_actual),
+                    execution(total_budget, total_actual),
+                ])
+            }),
+            chart: None,
+            table: false,
+        });
+
+        document
+    }
+
+    /// Lays a report out on A4 pages: the header and summary, then every
+    /// section as a table
+    fn render_pdf(document: &ReportDocument, file_path: &Path) -> Result<(), String> {
+        let (doc, page1, layer1) = PdfDocument::new(
+            format!("Reporte - {}", document.title),
+            Mm(210.0), // A4 width
+            Mm(297.0), // A4 height
+            "Layer 1",
+        );
+
+        let font = doc
+            .add_builtin_font(BuiltinFont::Helvetica)
+            .map_err(|e| e.to_string())?;
+        let font_bold = doc
+            .add_builtin_font(BuiltinFont::HelveticaBold)
+            .map_err(|e| e.to_string())?;
+
+        let mut flow = PageFlow {
+            layer: doc.get_page(page1).get_layer(layer1),
+            doc: &doc,
+            y: 270.0,
+        };
+
+        let y = flow.line(15.0);
+        flow.layer.use_text(
+            format!("CAJA CHOCA - {}", document.title),
+            18.0,
+            Mm(15.0),
+            Mm(y),
+            &font_bold,
+        );
+        for subtitle in &document.subtitles {
+            let y = flow.line(7.0);
+            flow.layer
+                .use_text(subtitle.as_str(), 11.0, Mm(15.0), Mm(y), &font);
+        }
+
+        if !document.summary.is_empty() {
+            let y = flow.line(12.0) - 4.0;
+            flow.layer
+                .use_text("RESUMEN", 14.0, Mm(15.0), Mm(y), &font_bold);
+            for item in &document.summary {
+                let y = flow.line(6.5);
+                let value = Self::format_summary_value(item);
+                // The amount in words can be long
+                let size = if item.kind == ReportColumnKind::Text {
+                    9.0
+                } else {
+                    11.0
+                };
+                flow.layer.use_text(
+                    format!("{}: {}", item.label, value),
+                    size,
+                    Mm(15.0),
+                    Mm(y),
+                    if item.strong { &font_bold } else { &font },
+                );
+            }
+        }
+
+        for section in &document.sections {
+            Self::render_pdf_section(&mut flow, section, &font, &font_bold);
+        }
+
+        for note in &document.notes {
+            let y = flow.line(7.0) - 2.0;
+            flow.layer
+                .use_text(note.as_str(), 9.0, Mm(15.0), Mm(y), &font);
+        }
+
+        flow.layer.use_text(
+            format!("Generado el: {}", document.generated_at),
+            8.0,
+            Mm(15.0),
+            Mm(12.0),
+            &font,
+        );
+
+        let file = File::create(file_path).map_err(|e| format!("Error creando archivo: {}", e))?;
+        let mut buf_writer = BufWriter::new(file);
+        doc.save(&mut buf_writer)
+            .map_err(|e| format!("Error guardando PDF: {}", e))?;
+
+        Ok(())
+    }
+
+    /// A section as a table below its chart. The header repeats on every
+    /// page, and a running balance is carried over between pages.
+    fn render_pdf_section(
+        flow: &mut PageFlow,
+        section: &ReportSection,
+        font: &IndirectFontRef,
+        font_bold: &IndirectFontRef,
+    ) {
+        let black = printpdf::Color::Rgb(Rgb::new(0.0, 0.0, 0.0, None));
+        let red = printpdf::Color::Rgb(Rgb::new(0.8, 0.1, 0.1, None));
+
+        // Column positions across the 180 mm between the margins
+        let total_width: f32 = section
+            .columns
+            .iter()
+            .map(|column| column.width as f32)
+            .sum::<f32>()
+            .max(1.0);
+        let mut x = 15.0;
+        let positions: Vec<(f32, f32)> = section
+            .columns
+            .iter()
+            .map(|column| {
+                let width = column.width as f32 / total_width * 180.0;
+                let position = (x, width);
+                x += width;
+                position
+            })
+            .collect();
+        let size = if section.columns.len() > 7 { 7.5 } else { 9.0 };
+
+        // Keep the title with the first rows
+        if !flow.has_room(40.0) {
+            flow.new_page();
+        }
+        let y = flow.line(12.0) - 4.0;
+        flow.layer.use_text(
+            section.title.to_uppercase(),
+            13.0,
+            Mm(15.0),
+            Mm(y),
+            font_bold,
+        );
+
+        if let Some(chart) = &section.chart {
+            Self::render_pdf_chart(flow, section, chart, font);
+        }
+
+        let header: Vec<ReportCell> = section
+            .columns
+            .iter()
+            .map(|column| Self::text(column.label.as_str()))
+            .collect();
+        let write_header = |flow: &mut PageFlow| {
+            let y = flow.line(7.0) - 2.0;
+            Self::write_pdf_row(
+                &flow.layer,
+                y,
+                &section.columns,
+                &positions,
+                &header,
+                0,
+                size,
+                font_bold,
+            );
+            flow.layer.add_line(Line {
+                points: vec![
+                    (Point::new(Mm(15.0), Mm(y - 2.0)), false),
+                    (Point::new(Mm(195.0), Mm(y - 2.0)), false),
+                ],
+                is_closed: false,
+            });
+        };
+        write_header(flow);
+
+        if section.rows.is_empty() {
+            let y = flow.line(7.0) - 2.0;
+            flow.layer
+                .use_text("Sin movimientos", size, Mm(15.0), Mm(y), font);
+        }
+
+        let running_balance = section.columns.iter().position(|column| {
+            matches!(column.formula, Some(ReportFormula::RunningBalance { .. }))
+        });
+        let mut carried = None;
+
+        for row in &section.rows {
+            if !flow.has_room(5.5) {
+                if let Some(balance) = carried {
+                    flow.layer.use_text(
+                        format!("Van: ${:.2}", balance),
+                        9.0,
+                        Mm(165.0),
+                        Mm(16.0),
+                        font_bold,
+                    );
+                }
+                flow.new_page();
+                write_header(flow);
+                if let Some(balance) = carried {
+                    let y = flow.line(6.0) - 2.0;
+                    flow.layer.use_text(
+                        format!("Vienen: ${:.2}", balance),
+                        9.0,
+                        Mm(165.0),
+                        Mm(y),
+                        font_bold,
+                    );
+                }
+            }
+
+            let y = flow.line(5.5) - 2.0;
+            let row_font = match row.style {
+                ReportRowStyle::Normal => font,
+                ReportRowStyle::Strong | ReportRowStyle::Alert => font_bold,
+            };
+            if row.style == ReportRowStyle::Alert {
+                flow.layer.set_fill_color(red.clone());
+            }
+            Self::write_pdf_row(
+                &flow.layer,
+                y,
+                &section.columns,
+                &positions,
+                &row.cells,
+                row.depth,
+                size,
+                row_font,
+            );
+            flow.layer.set_fill_color(black.clone());
+
+            if let Some(column) = running_balance
+                && let Some(ReportCell::Number(balance)) = row.cells.get(column)
+            {
+                carried = Some(*balance);
+            }
+        }
+
+        if let Some(totals) = &section.totals {
+            let y = flow.line(7.0) - 3.0;
+            flow.layer.add_line(Line {
+                points: vec![
+                    (Point::new(Mm(15.0), Mm(y + 4.0)), false),
+                    (Point::new(Mm(195.0), Mm(y + 4.0)), false),
+                ],
+                is_closed: false,
+            });
+            Self::write_pdf_row(
+                &flow.layer,
+                y,
+                &section.columns,
+                &positions,
+                &totals.cells,
+                0,
+                size,
+                font_bold,
+            );
+        }
+
+        flow.line(4.0);
+    }
+
+    /// Text on the left of its column, numbers on the right
+    #[allow(clippy::too_many_arguments)]
+    fn write_pdf_row(
+        layer: &PdfLayerReference,
+        y: f32,
+        columns: &[ReportColumn],
+        positions: &[(f32, f32)],
+        cells: &[ReportCell],
+        depth: usize,
+        size: f32,
+        font: &IndirectFontRef,
+    ) {
+        for ((column, (x, width)), cell) in columns.iter().zip(positions).zip(cells) {
+            let indent = if column.indent {
+                4.0 * depth as f32
+            } else {
+                0.0
+            };
+
+            match cell {
+                ReportCell::Empty => {}
+                ReportCell::Text(text) => {
+                    let text = Self::fit_text(text, width - indent - 1.5, size);
+                    layer.use_text(text, size, Mm(x + indent), Mm(y), font);
+                }
+                ReportCell::Number(_) => {
+                    let text = Self::format_cell(cell, column.kind);
+                    let text_width = Self::text_width(&text, size);
+                    layer.use_text(text, size, Mm(x + width - 2.0 - text_width), Mm(y), font);
+                }
+            }
+        }
+    }
+
+    /// Horizontal bars of the top-level rows: the main series in color and
+    /// the others in grey below it
+    fn render_pdf_chart(
+        flow: &mut PageFlow,
+        section: &ReportSection,
+        chart: &ReportChart,
+        font: &IndirectFontRef,
+    ) {
+        let Some((main, others)) = chart.value_columns.split_last() else {
+            return;
+        };
+        let (rows, max_value) = Self::chart_rows(section, chart);
+        if rows.is_empty() {
+            return;
+        }
+
+        let black = printpdf::Color::Rgb(Rgb::new(0.0, 0.0, 0.0, None));
+        let grey = printpdf::Color::Rgb(Rgb::new(0.75, 0.75, 0.75, None));
+        let color = printpdf::Color::Rgb(Rgb::new(0.2, 0.45, 0.7, None));
+
+        let bar_width = |amount: f64| {
+            if max_value > 0.0 {
+                (amount.max(0.0) / max_value * 90.0) as f32
+            } else {
+                0.0
+            }
+        };
+
+        let y = flow.line(7.0) - 1.0;
+        let legend = Self::chart_legend(section, chart);
+        flow.layer.use_text(legend, 8.0, Mm(15.0), Mm(y), font);
+
+        for row in rows {
+            let y = flow.line(6.5 + 2.5 * others.len() as f32);
+            let name = match row.cells.get(chart.label_column) {
+                Some(ReportCell::Text(name)) => name.chars().take(28).collect(),
+                _ => String::new(),
+            };
+            flow.layer.use_text(name, 9.0, Mm(15.0), Mm(y), font);
+
+            flow.layer.set_fill_color(color.clone());
+            flow.layer.add_rect(
+                Rect::new(
+                    Mm(70.0),
+                    Mm(y - 0.5),
+                    Mm(70.0 + bar_width(Self::chart_value(row, *main))),
+                    Mm(y + 3.0),
+                )
+                .with_mode(path::PaintMode::Fill),
+            );
+            flow.layer.set_fill_color(grey.clone());
+            for (index, column) in others.iter().enumerate() {
+                let top = y - 1.0 - 2.5 * index as f32;
+                flow.layer.add_rect(
+                    Rect::new(
+                        Mm(70.0),
+                        Mm(top - 2.0),
+                        Mm(70.0 + bar_width(Self::chart_value(row, *column))),
+                        Mm(top),
+                    )
+                    .with_mode(path::PaintMode::Fill),
+                );
+            }
+            flow.layer.set_fill_color(black.clone());
+
+            flow.layer.use_text(
+                Self::format_cell(
+                    &ReportCell::Number(Self::chart_value(row, *main)),
+                    section.columns[*main].kind,
+                ),
+                8.0,
+                Mm(165.0),
+                Mm(y),
+                font,
+            );
+        }
+
+        flow.line(3.0);
+    }
+
+    /// Top-level rows a chart draws, and the largest value among them
+    fn chart_rows<'a>(
+        section: &'a ReportSection,
+        chart: &ReportChart,
+    ) -> (Vec<&'a ReportRow>, f64) {
+        let rows: Vec<&ReportRow> = section.rows.iter().filter(|row| row.depth == 0).collect();
+        let max_value = rows
+            .iter()
+            .flat_map(|row| {
+                chart
+                    .value_columns
+                    .iter()
+                    .map(|column| Self::chart_value(row, *column))
+            })
+            .fold(0.0, f64::max);
+
+        (rows, max_value)
+    }
+
+    fn chart_value(row: &ReportRow, column: usize) -> f64 {
+        match row.cells.get(column) {
+            Some(ReportCell::Number(value)) => *value,
+            _ => 0.0,
+        }
+    }
+
+    /// Which series is drawn in color and which in grey
+    fn chart_legend(section: &ReportSection, chart: &ReportChart) -> String {
+        let Some((main, others)) = chart.value_columns.split_last() else {
+            return String::new();
+        };
+
+        match others.is_empty() {
+            true => section.columns[*main].label.clone(),
+            false => format!(
+                "En color: {}; en gris: {}",
+                section.columns[*main].label,
+                others
+                    .iter()
+                    .map(|column| section.columns[*column].label.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+
+    /// Summary amounts carry the currency sign
+    fn format_summary_value(item: &ReportSummaryItem) -> String {
+        match (&item.value, item.kind) {
+            (ReportCell::Number(amount), ReportColumnKind::Money) => format!("${:.2}", amount),
+            (value, kind) => Self::format_cell(value, kind),
+        }
+    }
+
+    fn format_cell(cell: &ReportCell, kind: ReportColumnKind) -> String {
+        match (cell, kind) {
+            (ReportCell::Empty, _) => String::new(),
+            (ReportCell::Text(text), _) => text.clone(),
+            (ReportCell::Number(value), ReportColumnKind::Money) => format!("{:.2}", value),
+            (ReportCell::Number(value), ReportColumnKind::Percent) => {
+                format!("{:.1}%", value * 100.0)
+            }
+            (ReportCell::Number(value), _) => format!("{:.0}", value),
+        }
+    }
+
+    /// Approximate width in mm of Helvetica text
+    fn text_width(text: &str, size: f32) -> f32 {
+        let em: f32 = text
+            .chars()
+            .map(|c| match c {
+                '0'..='9' | '$' => 0.556,
+                '.' | ',' | ' ' => 0.278,
+                '-' => 0.333,
+                '%' => 0.889,
+                'A'..='Z' => 0.667,
+                _ => 0.5,
+            })
+            .sum();
+        em * size * 0.3528
+    }
+
+    /// Cuts text that does not fit in `width` mm
+    fn fit_text(text: &str, width: f32, size: f32) -> String {
+        if Self::text_width(text, size) <= width {
+            return text.to_string();
+        }
+
+        let mut fitted = String::new();
+        for c in text.chars() {
+            fitted.push(c);
+            if Self::text_width(&fitted, size) + Self::text_width("...", size) > width {
+                fitted.pop();
+                break;
+            }
+        }
+        fitted.push_str("...");
+        fitted
+    }
+
+    /// Lays a report out as spreadsheet sheets: the header and summary open
+    /// the first sheet, sections go to their sheets and chart data to a sheet
+    /// of its own. The same layout is written as .xlsx and .ods.
+    fn layout_sheets(document: &ReportDocument) -> (Vec<SheetLayout>, Vec<SectionRange>) {
+        // Sheets in the order their sections appear
+        let mut sheets: Vec<SheetLayout> = Vec::new();
+        if let Some(summary_sheet) = &document.summary_sheet {
+            sheets.push(SheetLayout::new(summary_sheet));
+        }
+        for section in &document.sections {
+            if !sheets.iter().any(|sheet| sheet.name == section.sheet) {
+                sheets.push(SheetLayout::new(&section.sheet));
+            }
+        }
+        if sheets.is_empty() {
+            sheets.push(SheetLayout::new("Reporte"));
+        }
+
+        let mut ranges = vec![SectionRange::default(); document.sections.len()];
+        for (index, sheet) in sheets.iter_mut().enumerate() {
+            let name = sheet.name.clone();
+            let mut row = 0;
+
+            if index == 0 {
+                sheet.text(0, 0, "CAJA CHOCA", SheetStyle::Title);
+                sheet.text(1, 0, &document.title, SheetStyle::PLAIN);
+                row = 2;
+                for subtitle in &document.subtitles {
+                    sheet.text(row, 0, subtitle, SheetStyle::PLAIN);
+                    row += 1;
+                }
+
+                // Numbers next to their labels so that they can be computed on
+                if !document.summary.is_empty() {
+                    row += 1;
+                    sheet.text(row, 0, "RESUMEN", SheetStyle::Heading);
+                    row += 1;
+                    let first_item = row;
+                    for item in &document.summary {
+                        let style = if item.strong {
+                            ReportRowStyle::Strong
+                        } else {
+                            ReportRowStyle::Normal
+                        };
+                        sheet.text(
+                            row,
+                            0,
+                            &item.label,
+                            SheetStyle::Data(ReportColumnKind::Text, style, 0),
+                        );
+                        sheet.merges.push((row, 0, 1));
+
+                        let formula = match &item.formula {
+                            Some(ReportSummaryFormula::Aggregate(aggregate))
+                                if Self::has_data(document, aggregate) =>
+                            {
+                                Some(SheetFormula::Column(ReportFormula::Aggregate(
+                                    aggregate.clone(),
+                                )))
+                            }
+                            Some(ReportSummaryFormula::Difference {
+                                minuend,
+                                subtrahend,
+                            }) => Some(SheetFormula::RowDifference {
+                                minuend: first_item + *minuend as u32,
+                                subtrahend: first_item + *subtrahend as u32,
+                            }),
+                            _ => None,
+                        };
+                        sheet.push(
+                            row,
+                            2,
+                            item.value.clone(),
+                            formula,
+                            SheetStyle::Data(item.kind, style, 0),
+                        );
+                        row += 1;
+                    }
+
+                    if document.summary_sheet.as_ref() == Some(&name) {
+                        sheet.widen(0, 14);
+                        sheet.widen(2, 16);
+                    }
+                }
+                row += 1;
+            }
+
+            for (section_index, section) in document
+                .sections
+                .iter()
+                .enumerate()
+                .filter(|(_, section)| section.sheet == name)
+            {
+                // A section that opens its sheet is titled by the sheet name
+                if row > 0 {
+                    sheet.text(row, 0, &section.title, SheetStyle::Heading);
+                    row += 1;
+                }
+
+                let header_row = row;
+                ranges[section_index] = SectionRange {
+                    sheet: name.clone(),
+                    first_row: header_row + 1,
+                    last_row: header_row + section.rows.len() as u32,
+                };
+                if section.table {
+                    // A table keeps at least one row, even if empty
+                    sheet.tables.push(SheetTable {
+                        first_row: header_row,
+                        last_row: header_row + section.rows.len().max(1) as u32,
+                        last_col: section.columns.len().saturating_sub(1) as u16,
+                    });
+                    if sheet.frozen_rows == 0 {
+                        sheet.frozen_rows = header_row + 1;
+                    }
+                }
+
+                row = Self::layout_section(sheet, document, section, row) + 1;
+
+                for (col, column) in section.columns.iter().enumerate() {
+                    sheet.widen(col as u16, column.width);
+                }
+            }
+        }
+
+        // Top-level rows of every charted section side by side as chart
+        // data, then the charts below
+        let charted: Vec<(&ReportSection, &ReportChart)> = document
+            .sections
+            .iter()
+            .filter_map(|section| section.chart.as_ref().map(|chart| (section, chart)))
+            .collect();
+        if !charted.is_empty() {
+            let mut sheet = SheetLayout::new("Gráficos");
+
+            let top_level = |section: &'_ ReportSection| -> Vec<ReportRow> {
+                section
+                    .rows
+                    .iter()
+                    .filter(|row| row.depth == 0)
+                    .cloned()
+                    .collect()
+            };
+            let longest = charted
+                .iter()
+                .map(|(section, _)| top_level(section).len() as u32)
+                .max()
+                .unwrap_or(0);
+            let chart_row = (longest + 2).max(20);
+
+            let mut first_col = 0;
+            for (block, (section, chart)) in charted.iter().enumerate() {
+                let rows = top_level(section);
+
+                sheet.widen(first_col, 30);
+                sheet.text(0, first_col, &section.title, SheetStyle::Header);
+                for (offset, column) in chart.value_columns.iter().enumerate() {
+                    let col = first_col + 1 + offset as u16;
+                    sheet.widen(col, section.columns[*column].width);
+                    sheet.text(0, col, &section.columns[*column].label, SheetStyle::Header);
+                }
+                for (index, row) in rows.iter().enumerate() {
+                    let data_row = 1 + index as u32;
+                    if let Some(label) = row.cells.get(chart.label_column) {
+                        sheet.push(data_row, first_col, label.clone(), None, SheetStyle::PLAIN);
+                    }
+                    for (offset, column) in chart.value_columns.iter().enumerate() {
+                        sheet.push(
+                            data_row,
+                            first_col + 1 + offset as u16,
+                            row.cells.get(*column).cloned().unwrap_or(ReportCell::Empty),
+                            None,
+                            SheetStyle::Data(
+                                section.columns[*column].kind,
+                                ReportRowStyle::Normal,
+                                0,
+                            ),
+                        );
+                    }
+                }
+
+                let series_count = chart.value_columns.len() as u16;
+                if !rows.is_empty() && series_count > 0 {
+                    // One pair of charts per section
+                    sheet.charts.push(SheetChart {
+                        title: chart.title.clone(),
+                        section_title: section.title.clone(),
+                        label_col: first_col,
+                        last_row: rows.len() as u32,
+                        series: chart
+                            .value_columns
+                            .iter()
+                            .enumerate()
+                            .map(|(offset, column)| {
+                                (
+                                    section.columns[*column].label.clone(),
+                                    first_col + 1 + offset as u16,
+                                )
+                            })
+                            .collect(),
+                        row: chart_row + (block * 16) as u32,
+                    });
+                }
+
+                first_col += series_count + 2;
+            }
+
+            sheets.push(sheet);
+        }
+
+        (sheets, ranges)
+    }
+
+    /// Whether an aggregate has rows to be computed over; otherwise its
+    /// value is written as is
+    fn has_data(document: &ReportDocument, aggregate: &ReportAggregate) -> bool {
+        document
+            .sections
+            .get(aggregate.section)
+            .is_some_and(|section| !section.rows.is_empty())
+    }
+
+    /// Header, rows and totals of a section starting at `first_row`. Returns
+    /// the row after the last one laid out.
+    fn layout_section(
+        sheet: &mut SheetLayout,
+        document: &ReportDocument,
+        section: &ReportSection,
+        first_row: u32,
+    ) -> u32 {
+        for (col, column) in section.columns.iter().enumerate() {
+            sheet.text(first_row, col as u16, &column.label, SheetStyle::Header);
+        }
+
+        let data_row = first_row + 1;
+        for (index, report_row) in section.rows.iter().enumerate() {
+            let row = data_row + index as u32;
+
+            for (col, (column, cell)) in section.columns.iter().zip(&report_row.cells).enumerate() {
+                let depth = if column.indent { report_row.depth } else { 0 };
+                let formula = match &column.formula {
+                    Some(ReportFormula::Aggregate(aggregate))
+                        if !Self::has_data(document, aggregate) =>
+                    {
+                        None
+                    }
+                    Some(formula) if !report_row.literal && *cell != ReportCell::Empty => {
+                        Some(SheetFormula::Column(formula.clone()))
+                    }
+                    _ => None,
+                };
+
+                sheet.push(
+                    row,
+                    col as u16,
+                    cell.clone(),
+                    formula,
+                    SheetStyle::Data(column.kind, report_row.style, depth),
+                );
+            }
+        }
+
+        let mut next_row = data_row + section.rows.len() as u32;
+        if let Some(totals) = &section.totals {
+            let row = next_row;
+
+            for (col, (column, cell)) in section.columns.iter().zip(&totals.cells).enumerate() {
+                let formula = match (cell, &column.formula) {
+                    // Ratios are computed on the totals instead of summed
+                    (
+                        ReportCell::Number(_),
+                        Some(
+                            formula @ (ReportFormula::Ratio { .. } | ReportFormula::Change { .. }),
+                        ),
+                    ) => Some(SheetFormula::Column(formula.clone())),
+                    (ReportCell::Number(_), _) if !section.rows.is_empty() => {
+                        Some(SheetFormula::Sum {
+                            first: data_row,
+                            last: row - 1,
+                        })
+                    }
+                    _ => None,
+                };
+
+                sheet.push(
+                    row,
+                    col as u16,
+                    cell.clone(),
+                    formula,
+                    SheetStyle::Data(column.kind, totals.style, 0),
+                );
+            }
+
+            next_row += 1;
+        }
+
+        next_row
+    }
+
+    /// Formula text of a cell on `row` and `col`: A1 references for Excel,
+    /// OpenFormula for OpenDocument
+    fn sheet_formula(
+        formula: &SheetFormula,
+        col: u16,
+        row: u32,
+        ranges: &[SectionRange],
+        open_formula: bool,
+    ) -> String {
+        let cell = |col: usize, row: u32| Self::sheet_reference(None, col, row, row, open_formula);
+        let separator = if open_formula { ";" } else { "," };
+
+        let expression = match formula {
+            SheetFormula::Sum { first, last } => format!(
+                "SUM({})",
+                Self::sheet_reference(None, col as usize, *first, *last, open_formula)
+            ),
+            SheetFormula::RowDifference {
+                minuend,
+                subtrahend,
+            } => format!(
+                "{}-{}",
+                cell(col as usize, *minuend),
+                cell(col as usize, *subtrahend)
+            ),
+            SheetFormula::Column(ReportFormula::Difference {
+                minuend,
+                subtrahend,
+            }) => format!("{}-{}", cell(*minuend, row), cell(*subtrahend, row)),
+            SheetFormula::Column(ReportFormula::Balance { base, plus, minus }) => format!(
+                "{}+{}-{}",
+                cell(*base, row),
+                cell(*plus, row),
+                cell(*minus, row)
+            ),
+            SheetFormula::Column(ReportFormula::RunningBalance { plus, minus }) => format!(
+                "{}+{}-{}",
+                cell(col as usize, row - 1),
+                cell(*plus, row),
+                cell(*minus, row)
+            ),
+            SheetFormula::Column(ReportFormula::Ratio {
+                numerator,
+                denominator,
+            }) => format!(
+                "IF({1}=0{2}\"\"{2}{0}/{1})",
+                cell(*numerator, row),
+                cell(*denominator, row),
+                separator
+            ),
+            SheetFormula::Column(ReportFormula::Change { previous, current }) => format!(
+                "IF({1}=0{2}\"\"{2}({0}-{1})/ABS({1}))",
+                cell(*current, row),
+                cell(*previous, row),
+                separator
+            ),
+            SheetFormula::Column(ReportFormula::Aggregate(aggregate)) => {
+                let range = &ranges[aggregate.section];
+                let column = |col: usize| {
+                    Self::sheet_reference(
+                        Some(&range.sheet),
+                        col,
+                        range.first_row,
+                        range.last_row,
+                        open_formula,
+                    )
+                };
+
+                let mut arguments: Vec<String> = aggregate.column.map(column).into_iter().collect();
+                for criterion in &aggregate.criteria {
+                    arguments.push(column(criterion.column));
+                    arguments.push(match &criterion.equals {
+                        ReportCriterionValue::Text(text) => {
+                            format!("\"{}\"", text.replace('"', "\"\""))
+                        }
+                        ReportCriterionValue::Column(col) => cell(*col, row),
+                    });
+                }
+
+                let function = match (aggregate.column, aggregate.criteria.is_empty()) {
+                    (Some(_), false) => "SUMIFS",
+                    (Some(_), true) => "SUM",
+                    (None, false) => "COUNTIFS",
+                    (None, true) => {
+                        arguments.push(column(0));
+                        "ROWS"
+                    }
+                };
+                format!("{}({})", function, arguments.join(separator))
+            }
+        };
+
+        if open_formula {
+            format!("of:={}", expression)
+        } else {
+            format!("={}", expression)
+        }
+    }
+
+    /// A cell, or a range of rows of a column, optionally on another sheet:
+    /// 'Detalle'!G2:G41 for Excel, [$'Detalle'.G2:.G41] for OpenDocument
+    fn sheet_reference(
+        sheet: Option<&str>,
+        col: usize,
+        first_row: u32,
+        last_row: u32,
+        open_formula: bool,
+    ) -> String {
+        let letter = column_number_to_name(col as u16);
+        let quoted = |sheet: &str| format!("'{}'", sheet.replace('\'', "''"));
+        // Spreadsheet rows are 1-based in formulas
+        let (first, last) = (first_row + 1, last_row + 1);
+
+        if open_formula {
+            let sheet = sheet
+                .map(|sheet| format!("${}", quoted(sheet)))
+                .unwrap_or_default();
+            if first == last {
+                format!("[{sheet}.{letter}{first}]")
+            } else {
+                format!("[{sheet}.{letter}{first}:.{letter}{last}]")
+            }
+        } else {
+            let sheet = sheet
+                .map(|sheet| format!("{}!", quoted(sheet)))
+                .unwrap_or_default();
+            if first == last {
+                format!("{sheet}{letter}{first}")
+            } else {
+                format!("{sheet}{letter}{first}:{letter}{last}")
+            }
+        }
+    }
+
+    /// Writes the sheet layout of a report as an Excel workbook, with native
+    /// charts on the charts sheet
+    fn render_excel(document: &ReportDocument, file_path: &Path) -> Result<(), String> {
+        let mut workbook = Workbook::new();
+
+        let (sheets, ranges) = Self::layout_sheets(document);
+        for sheet in sheets {
+            let worksheet = workbook.add_worksheet();
+            worksheet.set_name(&sheet.name).map_err(|e| e.to_string())?;
+
+            for cell in &sheet.cells {
+                let format = Self::excel_format(cell.style);
+
+                if let Some(&(_, _, last_col)) = sheet
+                    .merges
+                    .iter()
+                    .find(|(row, col, _)| *row == cell.row && *col == cell.col)
+                {
+                    let text = match &cell.value {
+                        ReportCell::Text(text) => text.as_str(),
+                        _ => "",
+                    };
+                    worksheet
+                        .merge_range(cell.row, cell.col, cell.row, last_col, text, &format)
+                        .map_err(|e| e.to_string())?;
+                    continue;
+                }
+
+                match &cell.formula {
+                    Some(formula) => {
+                        // The value computed by the report as the cached result
+                        let mut excel_formula = Formula::new(Self::sheet_formula(
+                            formula, cell.col, cell.row, &ranges, false,
+                        ));
+                        if let ReportCell::Number(value) = cell.value {
+                            excel_formula = excel_formula.set_result(value.to_string());
+                        }
+                        worksheet
+                            .write_formula_with_format(cell.row, cell.col, excel_formula, &format)
+                            .map(|_| ())
+                    }
+                    None => match &cell.value {
+                        _ if let Some(date) = Self::sheet_date(cell) => ExcelDateTime::from_ymd(
+                            date.year() as u16,
+                            date.month() as u8,
+                            date.day() as u8,
+                        )
+                        .and_then(|date| {
+                            worksheet.write_date_with_format(cell.row, cell.col, &date, &format)
+                        })
+                        .map(|_| ()),
+                        ReportCell::Number(value) => worksheet
+                            .write_number_with_format(cell.row, cell.col, *value, &format)
+                            .map(|_| ()),
+                        ReportCell::Text(text) => worksheet
+                            .write_string_with_format(cell.row, cell.col, text, &format)
+                            .map(|_| ()),
+                        ReportCell::Empty => Ok(()),
+                    },
+                }
+                .map_err(|e| e.to_string())?;
+            }
+
+            for (col, width) in sheet.widths.iter().enumerate() {
+                worksheet
+                    .set_column_width(col as u16, *width)
+                    .map_err(|e| e.to_string())?;
+            }
+
+            // Tables take their headers from the labels already written
+            for table in &sheet.tables {
+                worksheet
+                    .add_table(
+                        table.first_row,
+                        0,
+                        table.last_row,
+                        table.last_col,
+                        &Table::new(),
+                    )
+                    .map_err(|e| e.to_string())?;
+            }
+            if sheet.frozen_rows > 0 {
+                worksheet
+                    .set_freeze_panes(sheet.frozen_rows, 0)
+                    .map_err(|e| e.to_string())?;
+            }
+
+            for chart in &sheet.charts {
+                let names = (
+                    sheet.name.as_str(),
+                    1,
+                    chart.label_col,
+                    chart.last_row,
+                    chart.label_col,
+                );
+                let Some((_, main_col)) = chart.series.last() else {
+                    continue;
+                };
+
+                let mut pie = Chart::new(ChartType::Pie);
+                pie.add_series()
+                    .set_categories(names)
+                    .set_values((sheet.name.as_str(), 1, *main_col, chart.last_row, *main_col))
+                    .set_data_label(ChartDataLabel::new().show_percentage());
+                pie.title().set_name(&chart.title);
+
+                let mut bars = Chart::new(ChartType::Column);
+                for (name, col) in &chart.series {
+                    bars.add_series()
+                        .set_categories(names)
+                        .set_values((sheet.name.as_str(), 1, *col, chart.last_row, *col))
+                        .set_name(name.as_str());
+                }
+                bars.title().set_name(&chart.section_title);
+
+                worksheet
+                    .insert_chart(chart.row, 0, &pie)
+                    .map_err(|e| e.to_string())?;
+                worksheet
+                    .insert_chart(chart.row, 5, &bars)
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+
+        workbook
+            .save(file_path)
+            .map_err(|e| format!("Error guardando Excel: {}", e))?;
 
-        // Generate filename
-        let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
-        let filename = format!("{}_{}_{}", report_type, start_date, timestamp);
+        Ok(())
+    }
 
-        // Get reports directory (use custom path if provided)
-        let reports_dir = if let Some(path) = custom_download_path {
-            PathBuf::from(path)
-        } else {
-            Self::get_reports_directory()?
+    fn excel_format(style: SheetStyle) -> Format {
+        let (kind, row_style, depth) = match style {
+            SheetStyle::Title => {
+                return Format::new()
+                    .set_bold()
+                    .set_font_size(16)
+                    .set_font_color(Color::Blue);
+            }
+            SheetStyle::Header => {
+                return Format::new()
+                    .set_bold()
+                    .set_background_color(Color::Blue)
+                    .set_font_color(Color::White);
+            }
+            SheetStyle::Heading => return Format::new().set_bold(),
+            SheetStyle::Data(kind, row_style, depth) => (kind, row_style, depth),
         };
 
-        // Create directory if it doesn't exist
-        if !reports_dir.exists() {
-            std::fs::create_dir_all(&reports_dir)
-                .map_err(|e| format!("Error creating directory: {}", e))?;
+        let mut format = match kind {
+            ReportColumnKind::Text => Format::new(),
+            ReportColumnKind::Integer => Format::new().set_num_format("0"),
+            ReportColumnKind::Money => Format::new().set_num_format("#,##0.00"),
+            ReportColumnKind::Percent => Format::new().set_num_format("0.0%"),
+            ReportColumnKind::Date => Format::new().set_num_format("dd/mm/yyyy"),
+        };
+        if depth > 0 {
+            format = format.set_indent(depth as u8);
         }
 
-        match format {
-            "pdf" => Self::generate_pdf_report(
-                &transactions,
-                report_type,
-                start_date,
-                end_date,
-                &reports_dir,
-                &filename,
-            ),
-            "excel" => Self::generate_excel_report(
-                &transactions,
-                report_type,
-                start_date,
-                end_date,
-                &reports_dir,
-                &filename,
-            ),
-            _ => Err("Formato no soportado. Use 'pdf' o 'excel'".to_string()),
+        match row_style {
+            ReportRowStyle::Normal => format,
+            ReportRowStyle::Strong => format.set_bold(),
+            ReportRowStyle::Alert => format
+                .set_bold()
+                .set_font_color(Color::Red)
+                .set_background_color(Color::RGB(0xFDE2E1)),
         }
     }
 
-    fn get_transactions_for_range(
-        db: &Database,
-        start_date: &str,
-        end_date: &str,
-        report_type: &str,
-    ) -> Result<Vec<Transaction>, String> {
-        let conn = db.get_connection();
-        let conn = conn
-            .lock()
-            .map_err(|_| "Error al obtener conexión".to_string())?;
+    /// The date of a cell of a date column, when its text is one
+    fn sheet_date(cell: &SheetCell) -> Option<NaiveDate> {
+        match (&cell.value, cell.style) {
+            (ReportCell::Text(text), SheetStyle::Data(ReportColumnKind::Date, _, _)) => {
+                NaiveDate::parse_from_str(text, "%Y-%m-%d").ok()
+            }
+            _ => None,
+        }
+    }
 
-        // Build query based on report type
-        let query = if report_type.starts_with("category_") {
-            // Category-based report
-            let parts: Vec<&str> = report_type.split('_').collect();
-            if parts.len() >= 3 {
-                let category_id: i64 = parts[1].parse().unwrap_or(0);
-                let transaction_type = parts[2];
-
-                if transaction_type == "income" {
-                    format!(
-                        "SELECT 
-                    t.id, t.session_id, t.transaction_number, t.type, t.amount, 
-                    t.concept, t.category_id, c.name as category_name, t.created_at, t.created_by
-                 FROM transactions t
-                 LEFT JOIN categories c ON t.category_id = c.id
-                 WHERE date(t.created_at) >= date('{}') AND date(t.created_at) <= date('{}')
-                 AND t.type = 'income' AND t.category_id = {}
-                 ORDER BY t.created_at DESC",
-                        start_date, end_date, category_id
-                    )
-                } else {
-                    format!(
-                        "SELECT 
-                    t.id, t.session_id, t.transaction_number, t.type, t.amount, 
-                    t.concept, t.category_id, c.name as category_name, t.created_at, t.created_by
-                 FROM transactions t
-                 LEFT JOIN categories c ON t.category_id = c.id
-                 WHERE date(t.created_at) >= date('{}') AND date(t.created_at) <= date('{}')
-                 AND t.type = 'expense' AND t.category_id = {}
-                 ORDER BY t.created_at DESC",
-                        start_date, end_date, category_id
-                    )
+    /// Writes the sheet layout of a report as an OpenDocument spreadsheet
+    /// with the same sheets, number formats and formulas as the workbook.
+    /// Charts are not embedded; their data sheet is kept.
+    fn render_ods(document: &ReportDocument, file_path: &Path) -> Result<(), String> {
+        let (sheets, ranges) = Self::layout_sheets(document);
+
+        // One automatic style per distinct cell style and column width
+        let mut styles: Vec<SheetStyle> = Vec::new();
+        let mut widths: Vec<u16> = Vec::new();
+        for sheet in &sheets {
+            for cell in &sheet.cells {
+                if !styles.contains(&cell.style) {
+                    styles.push(cell.style);
                 }
-            } else {
-                return Err("Tipo de reporte de categoría inválido".to_string());
             }
-        } else {
-            match report_type {
-                "income" => "SELECT 
-                    t.id, t.session_id, t.transaction_number, t.type, t.amount, 
-                    t.concept, t.category_id, c.name as category_name, t.created_at, t.created_by
-                 FROM transactions t
-                 LEFT JOIN categories c ON t.category_id = c.id
-                 WHERE date(t.created_at) >= date(?) AND date(t.created_at) <= date(?)
-                 AND t.type = 'income'
-                 ORDER BY t.created_at DESC"
-                    .to_string(),
-                "expense" => "SELECT 
-                    t.id, t.session_id, t.transaction_number, t.type, t.amount, 
-                    t.concept, t.category_id, c.name as category_name, t.created_at, t.created_by
-                 FROM transactions t
-                 LEFT JOIN categories c ON t.category_id = c.id
-                 WHERE date(t.created_at) >= date(?) AND date(t.created_at) <= date(?)
-                 AND t.type = 'expense'
-                 ORDER BY t.created_at DESC"
-                    .to_string(),
-                _ => "SELECT 
-                    t.id, t.session_id, t.transaction_number, t.type, t.amount, 
-                    t.concept, t.category_id, c.name as category_name, t.created_at, t.created_by
-                 FROM transactions t
-                 LEFT JOIN categories c ON t.category_id = c.id
-                 WHERE date(t.created_at) >= date(?) AND date(t.created_at) <= date(?)
-                 ORDER BY t.created_at DESC"
-                    .to_string(),
+            for width in &sheet.widths {
+                if !widths.contains(width) {
+                    widths.push(*width);
+                }
             }
-        };
+        }
 
-        let transactions = if report_type.starts_with("category_") {
-            // For category reports, query is already a formatted String
-            let results: Result<Vec<Transaction>, String> = {
-                let mut stmt = conn
-                    .prepare(&query)
-                    .map_err(|e| format!("Error en query: {}", e))?;
-
-                let mapped = stmt
-                    .query_map([], |row| {
-                        Ok(Transaction {
-                            id: row.get(0)?,
-                            session_id: row.get(1)?,
-                            transaction_number: row.get(2)?,
-                            transaction_type: row.get(3)?,
-                            amount: row.get(4)?,
-                            concept: row.get(5)?,
-                            category_id: row.get(6)?,
-                            category_name: row.get(7)?,
-                            created_at: row.get(8)?,
-                            created_by: row.get(9)?,
-                        })
-                    })
-                    .map_err(|e| format!("Error mapeando resultados: {}", e))?;
+        let mut content = String::from(ODS_CONTENT_HEADER);
+        content.push_str("<office:automatic-styles>\n");
+        content.push_str(ODS_NUMBER_STYLES);
+        for width in &widths {
+            // Excel widths are in characters of about 0.19 cm
+            content.push_str(&format!(
+                "<style:style style:name=\"co{0}\" style:family=\"table-column\">\
+                 <style:table-column-properties style:column-width=\"{1:.2}cm\"/></style:style>\n",
+                width,
+                *width as f64 * 0.19 + 0.2
+            ));
+        }
+        for (index, style) in styles.iter().enumerate() {
+            content.push_str(&Self::ods_cell_style(index, *style));
+        }
+        content.push_str("</office:automatic-styles>\n<office:body>\n<office:spreadsheet>\n");
 
-                let collected = mapped
-                    .collect::<Result<Vec<_>, _>>()
-                    .map_err(|e| format!("Error colectando transacciones: {}", e))?;
+        for sheet in &sheets {
+            content.push_str(&format!(
+                "<table:table table:name=\"{}\">\n",
+                Self::escape_html(&sheet.name)
+            ));
+            for width in &sheet.widths {
+                content.push_str(&format!(
+                    "<table:table-column table:style-name=\"co{}\"/>\n",
+                    width
+                ));
+            }
 
-                Ok(collected)
-            };
-            results?
-        } else {
-            // For regular reports, use parameters
-            let results: Result<Vec<Transaction>, String> = {
-                let mut stmt = conn
-                    .prepare(&query)
-                    .map_err(|e| format!("Error en query: {}", e))?;
-
-                let mapped = stmt
-                    .query_map([start_date, end_date], |row| {
-                        Ok(Transaction {
-                            id: row.get(0)?,
-                            session_id: row.get(1)?,
-                            transaction_number: row.get(2)?,
-                            transaction_type: row.get(3)?,
-                            amount: row.get(4)?,
-                            concept: row.get(5)?,
-                            category_id: row.get(6)?,
-                            category_name: row.get(7)?,
-                            created_at: row.get(8)?,
-                            created_by: row.get(9)?,
-                        })
-                    })
-                    .map_err(|e| format!("Error mapeando resultados: {}", e))?;
+            // Chart data is laid out block by block; rows are written in order
+            let mut cells: Vec<&SheetCell> = sheet.cells.iter().collect();
+            cells.sort_by_key(|cell| (cell.row, cell.col));
 
-                let collected = mapped
-                    .collect::<Result<Vec<_>, _>>()
-                    .map_err(|e| format!("Error colectando transacciones: {}", e))?;
+            let mut next_row = 0;
+            for row_cells in cells.chunk_by(|a, b| a.row == b.row) {
+                let row = row_cells[0].row;
+                if row > next_row {
+                    content.push_str(&format!(
+                        "<table:table-row table:number-rows-repeated=\"{}\"><table:table-cell/></table:table-row>\n",
+                        row - next_row
+                    ));
+                }
 
-                Ok(collected)
-            };
-            results?
-        };
+                content.push_str("<table:table-row>");
+                let mut next_col = 0;
+                for cell in row_cells {
+                    match cell.col - next_col {
+                        0 => {}
+                        1 => content.push_str("<table:table-cell/>"),
+                        gap => content.push_str(&format!(
+                            "<table:table-cell table:number-columns-repeated=\"{}\"/>",
+                            gap
+                        )),
+                    }
 
-        Ok(transactions)
-    }
+                    let span = sheet
+                        .merges
+                        .iter()
+                        .find(|(row, col, _)| *row == cell.row && *col == cell.col)
+                        .map(|(_, col, last_col)| last_col - col + 1);
+                    let style = styles
+                        .iter()
+                        .position(|style| *style == cell.style)
+                        .unwrap_or(0);
+                    content.push_str(&Self::ods_cell(cell, style, span, &ranges));
 
-    fn get_reports_directory() -> Result<PathBuf, String> {
-        let docs_dir = dirs::document_dir()
-            .or_else(|| dirs::home_dir().map(|h| h.join("Documents")))
-            .ok_or("No se pudo encontrar directorio de documentos")?;
+                    next_col = cell.col + 1;
+                    if let Some(span) = span {
+                        for _ in 1..span {
+                            content.push_str("<table:covered-table-cell/>");
+                        }
+                        next_col = cell.col + span;
+                    }
+                }
+                content.push_str("</table:table-row>\n");
 
-        Ok(docs_dir.join("CajaChoca").join("Reportes"))
-    }
+                next_row = row + 1;
+            }
+            if sheet.cells.is_empty() {
+                content.push_str("<table:table-row><table:table-cell/></table:table-row>\n");
+            }
 
-    fn generate_pdf_report(
-        transactions: &[Transaction],
-        report_type: &str,
-        start_date: &str,
-        end_date: &str,
-        reports_dir: &PathBuf,
-        filename: &str,
-    ) -> Result<PathBuf, String> {
-        let file_path = reports_dir.join(format!("{}.pdf", filename));
+            content.push_str("</table:table>\n");
+        }
 
-        // Create PDF document
-        let (doc, page1, layer1) = PdfDocument::new(
-            &format!("Reporte - {}", Self::get_report_title(report_type)),
-            Mm(210.0), // A4 width
-            Mm(297.0), // A4 height
-            "Layer 1",
-        );
+        // Tables are filterable ranges; their frozen headers are view settings
+        let mut database_ranges = String::new();
+        let mut frozen = String::new();
+        for (index, sheet) in sheets.iter().enumerate() {
+            let name = Self::escape_html(&sheet.name.replace('\'', "''"));
+            for table in &sheet.tables {
+                database_ranges.push_str(&format!(
+                    "<table:database-range table:name=\"__Anonymous_Sheet_DB__{}\" \
+                     table:target-range-address=\"'{name}'.A{}:'{name}'.{}{}\" \
+                     table:display-filter-buttons=\"true\"/>\n",
+                    index,
+                    table.first_row + 1,
+                    column_number_to_name(table.last_col),
+                    table.last_row + 1
+                ));
+            }
+            if sheet.frozen_rows > 0 {
+                frozen.push_str(&format!(
+                    "<config:config-item-map-entry config:name=\"{}\">\
+                     <config:config-item config:name=\"VerticalSplitMode\" config:type=\"short\">2</config:config-item>\
+                     <config:config-item config:name=\"VerticalSplitPosition\" config:type=\"int\">{1}</config:config-item>\
+                     <config:config-item config:name=\"ActiveSplitRange\" config:type=\"short\">2</config:config-item>\
+                     <config:config-item config:name=\"PositionTop\" config:type=\"int\">0</config:config-item>\
+                     <config:config-item config:name=\"PositionBottom\" config:type=\"int\">{1}</config:config-item>\
+                     </config:config-item-map-entry>\n",
+                    Self::escape_html(&sheet.name),
+                    sheet.frozen_rows
+                ));
+            }
+        }
+        if !database_ranges.is_empty() {
+            content.push_str(&format!(
+                "<table:database-ranges>\n{}</table:database-ranges>\n",
+                database_ranges
+            ));
+        }
+        content.push_str("</office:spreadsheet>\n</office:body>\n</office:document-content>\n");
 
-        let current_layer = doc.get_page(page1).get_layer(layer1);
+        let settings = format!(
+            "{}<config:config-item-map-named config:name=\"Tables\">\n{}</config:config-item-map-named>\n{}",
+            ODS_SETTINGS_HEADER, frozen, ODS_SETTINGS_FOOTER
+        );
 
-        // Load fonts
-        let font = doc
-            .add_builtin_font(BuiltinFont::Helvetica)
-            .map_err(|e| e.to_string())?;
-        let font_bold = doc
-            .add_builtin_font(BuiltinFont::HelveticaBold)
-            .map_err(|e| e.to_string())?;
+        let file = File::create(file_path).map_err(|e| format!("Error creando archivo: {}", e))?;
+        let mut zip = ZipWriter::new(file);
+        // The media type goes first and uncompressed so that the format can
+        // be recognised from the first bytes of the file
+        let entries: [(&str, CompressionMethod, &[u8]); 4] = [
+            (
+                "mimetype",
+                CompressionMethod::Stored,
+                ODS_MIME_TYPE.as_bytes(),
+            ),
+            (
+                "META-INF/manifest.xml",
+                CompressionMethod::Deflated,
+                ODS_MANIFEST.as_bytes(),
+            ),
+            (
+                "content.xml",
+                CompressionMethod::Deflated,
+                content.as_bytes(),
+            ),
+            (
+                "settings.xml",
+                CompressionMethod::Deflated,
+                settings.as_bytes(),
+            ),
+        ];
+        for (name, method, data) in entries {
+            zip.start_file(
+                name,
+                SimpleFileOptions::default().compression_method(method),
+            )
+            .map_err(|e| format!("Error guardando ODS: {}", e))?;
+            zip.write_all(data)
+                .map_err(|e| format!("Error guardando ODS: {}", e))?;
+        }
 
-        // Title
-        current_layer.use_text(
-            &format!("CAJA CHOCA - {}", Self::get_report_title(report_type)),
-            20.0,
-            Mm(20.0),
-            Mm(270.0),
-            &font_bold,
-        );
+        zip.finish()
+            .map_err(|e| format!("Error guardando ODS: {}", e))?;
 
-        // Date range
-        current_layer.use_text(
-            &format!("Período: {} al {}", start_date, end_date),
-            12.0,
-            Mm(20.0),
-            Mm(255.0),
-            &font,
-        );
+        Ok(())
+    }
 
-        // Calculate totals
-        let (total_income, total_expense, income_count, expense_count) =
-            Self::calculate_totals(transactions);
+    /// Cell with its value, the displayed text and the formula, if any
+    fn ods_cell(
+        cell: &SheetCell,
+        style: usize,
+        span: Option<u16>,
+        ranges: &[SectionRange],
+    ) -> String {
+        let mut attributes = format!(" table:style-name=\"ce{}\"", style);
+        if let Some(span) = span {
+            attributes.push_str(&format!(
+                " table:number-columns-spanned=\"{}\" table:number-rows-spanned=\"1\"",
+                span
+            ));
+        }
+        if let Some(formula) = &cell.formula {
+            attributes.push_str(&format!(
+                " table:formula=\"{}\"",
+                Self::escape_html(&Self::sheet_formula(
+                    formula, cell.col, cell.row, ranges, true
+                ))
+            ));
+        }
 
-        // Summary section
-        current_layer.use_text("RESUMEN", 14.0, Mm(20.0), Mm(240.0), &font_bold);
-        current_layer.use_text(
-            &format!(
-                "Total Ingresos: ${:.2} ({} transacciones)",
-                total_income, income_count
+        let kind = match cell.style {
+            SheetStyle::Data(kind, _, _) => kind,
+            _ => ReportColumnKind::Text,
+        };
+        match &cell.value {
+            _ if let Some(date) = Self::sheet_date(cell) => format!(
+                "<table:table-cell{} office:value-type=\"date\" office:date-value=\"{}\"><text:p>{}</text:p></table:table-cell>",
+                attributes,
+                date.format("%Y-%m-%d"),
+                date.format("%d/%m/%Y")
             ),
-            11.0,
-            Mm(20.0),
-            Mm(228.0),
-            &font,
-        );
-        current_layer.use_text(
-            &format!(
-                "Total Egresos: ${:.2} ({} transacciones)",
-                total_expense, expense_count
+            ReportCell::Number(value) => format!(
+                "<table:table-cell{} office:value-type=\"{}\" office:value=\"{}\"><text:p>{}</text:p></table:table-cell>",
+                attributes,
+                if kind == ReportColumnKind::Percent {
+                    "percentage"
+                } else {
+                    "float"
+                },
+                value,
+                Self::escape_html(&Self::format_cell(&cell.value, kind))
             ),
-            11.0,
-            Mm(20.0),
-            Mm(220.0),
-            &font,
-        );
-        current_layer.use_text(
-            &format!("Balance: ${:.2}", total_income - total_expense),
-            11.0,
-            Mm(20.0),
-            Mm(212.0),
-            &font_bold,
-        );
+            ReportCell::Text(text) => format!(
+                "<table:table-cell{} office:value-type=\"string\"><text:p>{}</text:p></table:table-cell>",
+                attributes,
+                Self::escape_html(text)
+            ),
+            ReportCell::Empty => format!("<table:table-cell{}/>", attributes),
+        }
+    }
 
-        // Table header
-        let y_start = 195.0;
-        current_layer.use_text(
-            "DETALLE DE TRANSACCIONES",
-            14.0,
-            Mm(20.0),
-            Mm(y_start),
-            &font_bold,
+    /// Automatic cell style `ce{index}`, matching the Excel format of the
+    /// same style
+    fn ods_cell_style(index: usize, style: SheetStyle) -> String {
+        let (data_style, bold, color, background, font_size, depth) = match style {
+            SheetStyle::Title => (None, true, Some("#0000ff"), None, Some(16), 0),
+            SheetStyle::Header => (None, true, Some("#ffffff"), Some("#0000ff"), None, 0),
+            SheetStyle::Heading => (None, true, None, None, None, 0),
+            SheetStyle::Data(kind, row_style, depth) => {
+                let data_style = match kind {
+                    ReportColumnKind::Text => None,
+                    ReportColumnKind::Integer => Some("N0"),
+                    ReportColumnKind::Money => Some("N2"),
+                    ReportColumnKind::Percent => Some("P1"),
+                    ReportColumnKind::Date => Some("D1"),
+                };
+                match row_style {
+                    ReportRowStyle::Normal => (data_style, false, None, None, None, depth),
+                    ReportRowStyle::Strong => (data_style, true, None, None, None, depth),
+                    ReportRowStyle::Alert => (
+                        data_style,
+                        true,
+                        Some("#ff0000"),
+                        Some("#fde2e1"),
+                        None,
+                        depth,
+                    ),
+                }
+            }
+        };
+
+        let mut xml = format!(
+            "<style:style style:name=\"ce{}\" style:family=\"table-cell\"",
+            index
         );
+        if let Some(data_style) = data_style {
+            xml.push_str(&format!(" style:data-style-name=\"{}\"", data_style));
+        }
+        xml.push('>');
+        if let Some(background) = background {
+            xml.push_str(&format!(
+                "<style:table-cell-properties fo:background-color=\"{}\"/>",
+                background
+            ));
+        }
+        if depth > 0 {
+            xml.push_str(&format!(
+                "<style:paragraph-properties fo:margin-left=\"{:.2}cm\"/>",
+                0.35 * depth as f64
+            ));
+        }
+        if bold || color.is_some() || font_size.is_some() {
+            xml.push_str("<style:text-properties");
+            if bold {
+                xml.push_str(" fo:font-weight=\"bold\"");
+            }
+            if let Some(color) = color {
+                xml.push_str(&format!(" fo:color=\"{}\"", color));
+            }
+            if let Some(font_size) = font_size {
+                xml.push_str(&format!(" fo:font-size=\"{}pt\"", font_size));
+            }
+            xml.push_str("/>");
+        }
+        xml.push_str("</style:style>\n");
 
-        // Column headers
-        let y_header = y_start - 15.0;
-        current_layer.use_text("Fecha", 10.0, Mm(20.0), Mm(y_header), &font_bold);
-        current_layer.use_text("Número", 10.0, Mm(55.0), Mm(y_header), &font_bold);
-        current_layer.use_text("Concepto", 10.0, Mm(90.0), Mm(y_header), &font_bold);
-        current_layer.use_text("Tipo", 10.0, Mm(150.0), Mm(y_header), &font_bold);
-        current_layer.use_text("Monto", 10.0, Mm(175.0), Mm(y_header), &font_bold);
-
-        // Separator line
-        current_layer.add_line(Line {
-            points: vec![
-                (Point::new(Mm(15.0), Mm(y_header - 3.0)), false),
-                (Point::new(Mm(195.0), Mm(y_header - 3.0)), false),
-            ],
-            is_closed: false,
-        });
+        xml
+    }
 
-        // Transaction rows
-        let mut y_pos = y_header - 10.0;
-        for transaction in transactions.iter().take(40) {
-            // Limit to 40 rows per page for simplicity
-            let date = transaction
-                .created_at
-                .split('T')
-                .next()
-                .unwrap_or(&transaction.created_at);
-            let type_label = if transaction.transaction_type == "income" {
-                "Ingreso"
-            } else {
-                "Egreso"
-            };
+    /// Self-contained HTML page: inline styles that also print on A4, and
+    /// charts as inline SVG
+    fn render_html(document: &ReportDocument, file_path: &Path) -> Result<(), String> {
+        let mut html = String::from("<!DOCTYPE html>\n<html lang=\"es\">\n<head>\n");
+        html.push_str("<meta charset=\"utf-8\">\n");
+        html.push_str(&format!(
+            "<title>{}</title>\n",
+            Self::escape_html(&document.title)
+        ));
+        html.push_str(HTML_STYLE);
+        html.push_str("</head>\n<body>\n");
 
-            current_layer.use_text(date, 9.0, Mm(20.0), Mm(y_pos), &font);
-            current_layer.use_text(
-                &transaction.transaction_number,
-                9.0,
-                Mm(55.0),
-                Mm(y_pos),
-                &font,
-            );
+        html.push_str(&format!(
+            "<h1>CAJA CHOCA - {}</h1>\n",
+            Self::escape_html(&document.title)
+        ));
+        for subtitle in &document.subtitles {
+            html.push_str(&format!(
+                "<p class=\"subtitle\">{}</p>\n",
+                Self::escape_html(subtitle)
+            ));
+        }
 
-            // Truncate concept if too long
-            let concept = if transaction.concept.len() > 30 {
-                format!("{}...", &transaction.concept[..27])
-            } else {
-                transaction.concept.clone()
-            };
-            current_layer.use_text(&concept, 9.0, Mm(90.0), Mm(y_pos), &font);
-            current_layer.use_text(type_label, 9.0, Mm(150.0), Mm(y_pos), &font);
-            current_layer.use_text(
-                &format!("${:.2}", transaction.amount),
-                9.0,
-                Mm(175.0),
-                Mm(y_pos),
-                &font,
-            );
+        if !document.summary.is_empty() {
+            html.push_str("<h2>Resumen</h2>\n<table class=\"summary\">\n");
+            for item in &document.summary {
+                html.push_str(&format!(
+                    "<tr{}><td>{}</td><td{}>{}</td></tr>\n",
+                    if item.strong { " class=\"strong\"" } else { "" },
+                    Self::escape_html(&item.label),
+                    if Self::aligns_left(item.kind) {
+                        ""
+                    } else {
+                        " class=\"num\""
+                    },
+                    Self::escape_html(&Self::format_summary_value(item))
+                ));
+            }
+            html.push_str("</table>\n");
+        }
 
-            y_pos -= 6.0;
+        for section in &document.sections {
+            html.push_str(&format!("<h2>{}</h2>\n", Self::escape_html(&section.title)));
+            if let Some(chart) = &section.chart {
+                html.push_str(&Self::html_chart(section, chart));
+            }
 
-            if y_pos < 20.0 {
-                // Would need to add a new page here for a full implementation
-                break;
+            html.push_str("<table>\n<thead><tr>");
+            for column in &section.columns {
+                html.push_str(&format!(
+                    "<th{}>{}</th>",
+                    Self::html_cell_class(column),
+                    Self::escape_html(&column.label)
+                ));
+            }
+            html.push_str("</tr></thead>\n<tbody>\n");
+
+            if section.rows.is_empty() {
+                html.push_str(&format!(
+                    "<tr><td colspan=\"{}\">Sin movimientos</td></tr>\n",
+                    section.columns.len()
+                ));
+            }
+            for row in &section.rows {
+                html.push_str(&Self::html_row(&section.columns, row));
+            }
+            html.push_str("</tbody>\n");
+
+            if let Some(totals) = &section.totals {
+                html.push_str("<tfoot>\n");
+                html.push_str(&Self::html_row(&section.columns, totals));
+                html.push_str("</tfoot>\n");
             }
+            html.push_str("</table>\n");
         }
 
-        // Footer
-        let generated_at = chrono::Local::now().format("%Y-%m-%d %H:%M");
-        current_layer.use_text(
-            &format!("Generado el: {}", generated_at),
-            8.0,
-            Mm(20.0),
-            Mm(15.0),
-            &font,
-        );
+        for note in &document.notes {
+            html.push_str(&format!(
+                "<p class=\"note\">{}</p>\n",
+                Self::escape_html(note)
+            ));
+        }
 
-        // Save PDF
-        let file = File::create(&file_path).map_err(|e| format!("Error creando archivo: {}", e))?;
-        let mut buf_writer = BufWriter::new(file);
-        doc.save(&mut buf_writer)
-            .map_err(|e| format!("Error guardando PDF: {}", e))?;
+        html.push_str(&format!(
+            "<footer>Generado el: {}</footer>\n</body>\n</html>\n",
+            Self::escape_html(&document.generated_at)
+        ));
 
-        Ok(file_path)
+        std::fs::write(file_path, html).map_err(|e| format!("Error guardando HTML: {}", e))
     }
 
-    fn generate_excel_report(
-        transactions: &[Transaction],
-        report_type: &str,
-        start_date: &str,
-        end_date: &str,
-        reports_dir: &PathBuf,
-        filename: &str,
-    ) -> Result<PathBuf, String> {
-        let file_path = reports_dir.join(format!("{}.xlsx", filename));
-
-        let mut workbook = Workbook::new();
-        let worksheet = workbook.add_worksheet();
-
-        // Set column widths
-        worksheet
-            .set_column_width(0, 15)
-            .map_err(|e| e.to_string())?; // Date
-        worksheet
-            .set_column_width(1, 15)
-            .map_err(|e| e.to_string())?; // Number
-        worksheet
-            .set_column_width(2, 35)
-            .map_err(|e| e.to_string())?; // Concept
-        worksheet
-            .set_column_width(3, 15)
-            .map_err(|e| e.to_string())?; // Category
-        worksheet
-            .set_column_width(4, 12)
-            .map_err(|e| e.to_string())?; // Type
-        worksheet
-            .set_column_width(5, 15)
-            .map_err(|e| e.to_string())?; // Amount
-        worksheet
-            .set_column_width(6, 15)
-            .map_err(|e| e.to_string())?; // Created by
-
-        // Title format
-        let title_format = Format::new()
-            .set_bold()
-            .set_font_size(16)
-            .set_font_color(Color::Blue);
-
-        // Header format
-        let header_format = Format::new()
-            .set_bold()
-            .set_background_color(Color::Blue)
-            .set_font_color(Color::White);
-
-        // Write title
-        worksheet
-            .write_string_with_format(0, 0, "CAJA CHOCA", &title_format)
-            .map_err(|e| e.to_string())?;
-        worksheet
-            .write_string(1, 0, Self::get_report_title(report_type))
-            .map_err(|e| e.to_string())?;
-        worksheet
-            .write_string(2, 0, &format!("Período: {} al {}", start_date, end_date))
-            .map_err(|e| e.to_string())?;
+    fn html_row(columns: &[ReportColumn], row: &ReportRow) -> String {
+        let class = match row.style {
+            ReportRowStyle::Normal => "",
+            ReportRowStyle::Strong => " class=\"strong\"",
+            ReportRowStyle::Alert => " class=\"alert\"",
+        };
 
-        // Calculate totals
-        let (total_income, total_expense, income_count, expense_count) =
-            Self::calculate_totals(transactions);
+        let mut html = format!("<tr{}>", class);
+        for (column, cell) in columns.iter().zip(&row.cells) {
+            let indent = if column.indent && row.depth > 0 {
+                format!(
+                    " style=\"padding-left: {}em\"",
+                    0.5 + 1.5 * row.depth as f32
+                )
+            } else {
+                String::new()
+            };
+            html.push_str(&format!(
+                "<td{}{}>{}</td>",
+                Self::html_cell_class(column),
+                indent,
+                Self::escape_html(&Self::format_cell(cell, column.kind))
+            ));
+        }
+        html.push_str("</tr>\n");
 
-        // Write summary
-        worksheet
-            .write_string(4, 0, "RESUMEN")
-            .map_err(|e| e.to_string())?;
-        worksheet
-            .write_string(5, 0, &format!("Total Ingresos: ${:.2}", total_income))
-            .map_err(|e| e.to_string())?;
-        worksheet
-            .write_string(5, 2, &format!("({} transacciones)", income_count))
-            .map_err(|e| e.to_string())?;
-        worksheet
-            .write_string(6, 0, &format!("Total Egresos: ${:.2}", total_expense))
-            .map_err(|e| e.to_string())?;
-        worksheet
-            .write_string(6, 2, &format!("({} transacciones)", expense_count))
-            .map_err(|e| e.to_string())?;
+        html
+    }
 
-        let balance_format = Format::new().set_bold();
-        worksheet
-            .write_string_with_format(
-                7,
-                0,
-                &format!("Balance: ${:.2}", total_income - total_expense),
-                &balance_format,
-            )
-            .map_err(|e| e.to_string())?;
+    fn html_cell_class(column: &ReportColumn) -> &'static str {
+        if Self::aligns_left(column.kind) {
+            ""
+        } else {
+            " class=\"num\""
+        }
+    }
 
-        // Write table headers
-        let header_row = 9;
-        let headers = [
-            "Fecha",
-            "Número",
-            "Concepto",
-            "Categoría",
-            "Tipo",
-            "Monto",
-            "Registrado por",
-        ];
-        for (col, header) in headers.iter().enumerate() {
-            worksheet
-                .write_string_with_format(header_row, col as u16, *header, &header_format)
-                .map_err(|e| e.to_string())?;
+    /// Horizontal bars of the top-level rows, like the PDF chart
+    fn html_chart(section: &ReportSection, chart: &ReportChart) -> String {
+        let Some((main, others)) = chart.value_columns.split_last() else {
+            return String::new();
+        };
+        let (rows, max_value) = Self::chart_rows(section, chart);
+        if rows.is_empty() {
+            return String::new();
         }
 
-        // Write transaction data
-        let mut row = header_row + 1;
-        for transaction in transactions {
-            let date = transaction
-                .created_at
-                .split('T')
-                .next()
-                .unwrap_or(&transaction.created_at);
-            let type_label = if transaction.transaction_type == "income" {
-                "Ingreso"
+        let bar_width = |amount: f64| {
+            if max_value > 0.0 {
+                amount.max(0.0) / max_value * 300.0
             } else {
-                "Egreso"
+                0.0
+            }
+        };
+        let band = 16.0 + 6.0 * others.len() as f64;
+        let height = 24.0 + band * rows.len() as f64;
+
+        let mut svg = format!(
+            "<svg class=\"chart\" xmlns=\"http://www.w3.org/2000/svg\" width=\"640\" height=\"{0}\" \
+             viewBox=\"0 0 640 {0}\" role=\"img\" aria-label=\"{1}\">\n",
+            height,
+            Self::escape_html(&chart.title)
+        );
+        svg.push_str(&format!(
+            "<text x=\"0\" y=\"12\" class=\"legend\">{}</text>\n",
+            Self::escape_html(&Self::chart_legend(section, chart))
+        ));
+
+        for (index, row) in rows.iter().enumerate() {
+            let y = 24.0 + band * index as f64;
+            let name: String = match row.cells.get(chart.label_column) {
+                Some(ReportCell::Text(name)) => name.chars().take(28).collect(),
+                _ => String::new(),
             };
-            let category = transaction
-                .category_name
-                .as_deref()
-                .unwrap_or("Sin categoría");
+            let value = Self::chart_value(row, *main);
 
-            worksheet
-                .write_string(row, 0, date)
-                .map_err(|e| e.to_string())?;
-            worksheet
-                .write_string(row, 1, &transaction.transaction_number)
-                .map_err(|e| e.to_string())?;
-            worksheet
-                .write_string(row, 2, &transaction.concept)
-                .map_err(|e| e.to_string())?;
-            worksheet
-                .write_string(row, 3, category)
-                .map_err(|e| e.to_string())?;
-            worksheet
-                .write_string(row, 4, type_label)
-                .map_err(|e| e.to_string())?;
-            worksheet
-                .write_number(row, 5, transaction.amount)
-                .map_err(|e| e.to_string())?;
-            worksheet
-                .write_string(row, 6, &transaction.created_by)
-                .map_err(|e| e.to_string())?;
+            svg.push_str(&format!(
+                "<text x=\"0\" y=\"{:.1}\">{}</text>\n",
+                y + 10.0,
+                Self::escape_html(&name)
+            ));
+            svg.push_str(&format!(
+                "<rect x=\"200\" y=\"{:.1}\" width=\"{:.1}\" height=\"10\" fill=\"#3373b3\"/>\n",
+                y,
+                bar_width(value)
+            ));
+            for (offset, column) in others.iter().enumerate() {
+                svg.push_str(&format!(
+                    "<rect x=\"200\" y=\"{:.1}\" width=\"{:.1}\" height=\"4\" fill=\"#bfbfbf\"/>\n",
+                    y + 12.0 + 6.0 * offset as f64,
+                    bar_width(Self::chart_value(row, *column))
+                ));
+            }
+            svg.push_str(&format!(
+                "<text x=\"640\" y=\"{:.1}\" text-anchor=\"end\">{}</text>\n",
+                y + 10.0,
+                Self::format_cell(&ReportCell::Number(value), section.columns[*main].kind)
+            ));
+        }
+        svg.push_str("</svg>\n");
+
+        svg
+    }
+
+    /// Text and dates line up on the left, numbers on the right
+    fn aligns_left(kind: ReportColumnKind) -> bool {
+        matches!(kind, ReportColumnKind::Text | ReportColumnKind::Date)
+    }
+
+    fn escape_html(text: &str) -> String {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+    }
 
-            row += 1;
+    /// Markdown with the summary as a list and each section as a table, to be
+    /// pasted into e-mails and the intranet. Charts are left out; their data
+    /// is in the tables.
+    fn render_markdown(document: &ReportDocument, file_path: &Path) -> Result<(), String> {
+        let mut markdown = format!("# CAJA CHOCA - {}\n\n", document.title);
+        for subtitle in &document.subtitles {
+            markdown.push_str(&format!("{}  \n", Self::escape_markdown(subtitle)));
         }
 
-        // Save workbook
-        workbook
-            .save(&file_path)
-            .map_err(|e| format!("Error guardando Excel: {}", e))?;
+        if !document.summary.is_empty() {
+            markdown.push_str("\n## Resumen\n\n");
+            for item in &document.summary {
+                let line = format!(
+                    "{}: {}",
+                    Self::escape_markdown(&item.label),
+                    Self::escape_markdown(&Self::format_summary_value(item))
+                );
+                if item.strong {
+                    markdown.push_str(&format!("- **{}**\n", line));
+                } else {
+                    markdown.push_str(&format!("- {}\n", line));
+                }
+            }
+        }
 
-        Ok(file_path)
+        for section in &document.sections {
+            markdown.push_str(&format!("\n## {}\n\n", section.title));
+
+            if section.rows.is_empty() {
+                markdown.push_str("_Sin movimientos_\n");
+                continue;
+            }
+
+            let labels: Vec<String> = section
+                .columns
+                .iter()
+                .map(|column| Self::escape_markdown(&column.label))
+                .collect();
+            markdown.push_str(&format!("| {} |\n", labels.join(" | ")));
+            let alignments: Vec<&str> = section
+                .columns
+                .iter()
+                .map(|column| {
+                    if Self::aligns_left(column.kind) {
+                        ":---"
+                    } else {
+                        "---:"
+                    }
+                })
+                .collect();
+            markdown.push_str(&format!("| {} |\n", alignments.join(" | ")));
+
+            for row in section.rows.iter().chain(&section.totals) {
+                let bold = row.style != ReportRowStyle::Normal
+                    || section
+                        .totals
+                        .as_ref()
+                        .is_some_and(|totals| std::ptr::eq(row, totals));
+                let cells: Vec<String> = section
+                    .columns
+                    .iter()
+                    .zip(&row.cells)
+                    .map(|(column, cell)| {
+                        let text = Self::escape_markdown(&Self::format_cell(cell, column.kind));
+                        // Non-breaking spaces survive the trimming of cells
+                        let indent = if column.indent {
+                            "\u{a0}\u{a0}".repeat(row.depth)
+                        } else {
+                            String::new()
+                        };
+                        match bold && !text.is_empty() {
+                            true => format!("{}**{}**", indent, text),
+                            false => format!("{}{}", indent, text),
+                        }
+                    })
+                    .collect();
+                markdown.push_str(&format!("| {} |\n", cells.join(" | ")));
+            }
+        }
+
+        if !document.notes.is_empty() {
+            markdown.push('\n');
+            for note in &document.notes {
+                markdown.push_str(&format!("_{}_\n", Self::escape_markdown(note)));
+            }
+        }
+
+        markdown.push_str(&format!("\nGenerado el: {}\n", document.generated_at));
+
+        std::fs::write(file_path, markdown).map_err(|e| format!("Error guardando Markdown: {}", e))
+    }
+
+    fn escape_markdown(text: &str) -> String {
+        let mut escaped = String::with_capacity(text.len());
+        for c in text.chars() {
+            match c {
+                '\\' | '|' | '*' | '_' | '`' => {
+                    escaped.push('\\');
+                    escaped.push(c);
+                }
+                '\n' => escaped.push(' '),
+                _ => escaped.push(c),
+            }
+        }
+        escaped
     }
 
     fn calculate_totals(transactions: &[Transaction]) -> (f64, f64, usize, usize) {
@@ -564,13 +4079,134 @@ impl ReportService {
         (total_income, total_expense, income_count, expense_count)
     }
 
-    fn get_report_title(report_type: &str) -> &'static str {
-        match report_type {
-            "income" => "REPORTE DE INGRESOS",
-            "expense" => "REPORTE DE EGRESOS",
-            "balance" => "BALANCE CONSOLIDADO",
-            "weekly" => "RESUMEN SEMANAL",
-            _ => "REPORTE",
+    /// Subtotals per category, where each category also accumulates the amounts
+    /// of its subcategories. Rows are ordered depth-first; categories without
+    /// movements in the report are left out.
+    pub fn calculate_category_subtotals(
+        transactions: &[Transaction],
+        categories: Vec<Category>,
+    ) -> Vec<CategorySubtotal> {
+        let parents: HashMap<i64, Option<i64>> =
+            categories.iter().map(|c| (c.id, c.parent_id)).collect();
+        // Amount and distinct transactions per category
+        let mut totals: HashMap<i64, (f64, HashSet<i64>)> = HashMap::new();
+        let mut uncategorized: HashMap<&str, (f64, usize)> = HashMap::new();
+
+        for transaction in transactions {
+            // Split transactions contribute each line to its own category
+            let allocations: Vec<(Option<i64>, f64)> = if transaction.lines.is_empty() {
+                vec![(transaction.category_id, transaction.amount)]
+            } else {
+                transaction
+                    .lines
+                    .iter()
+                    .map(|line| (Some(line.category_id), line.amount))
+                    .collect()
+            };
+
+            for (category_id, amount) in allocations {
+                match category_id {
+                    Some(category_id) if parents.contains_key(&category_id) => {
+                        // Add the amount to the category and every ancestor
+                        let mut current = Some(category_id);
+                        let mut steps = 0;
+                        while let Some(id) = current {
+                            let entry = totals.entry(id).or_default();
+                            entry.0 += amount;
+                            entry.1.insert(transaction.id);
+
+                            steps += 1;
+                            if steps > parents.len() {
+                                break; // Guard against cycles
+                            }
+                            current = parents.get(&id).copied().flatten();
+                        }
+                    }
+                    _ => {
+                        let entry = uncategorized
+                            .entry(transaction.transaction_type.as_str())
+                            .or_insert((0.0, 0));
+                        entry.0 += amount;
+                        entry.1 += 1;
+                    }
+                }
+            }
+        }
+
+        fn visit(
+            node: &crate::models::CategoryNode,
+            depth: usize,
+            totals: &HashMap<i64, (f64, HashSet<i64>)>,
+            rows: &mut Vec<CategorySubtotal>,
+        ) {
+            if let Some((amount, transaction_ids)) = totals.get(&node.category.id) {
+                rows.push(CategorySubtotal {
+                    category_id: Some(node.category.id),
+                    name: node.category.name.clone(),
+                    account_code: node.category.account_code.clone(),
+                    transaction_type: node.category.category_type.clone(),
+                    depth,
+                    amount: *amount,
+                    count: transaction_ids.len(),
+                });
+
+                for child in &node.children {
+                    visit(child, depth + 1, totals, rows);
+                }
+            }
+        }
+
+        let tree = TransactionService::build_category_tree(categories);
+        let mut rows = Vec::new();
+
+        for transaction_type in ["income", "expense"] {
+            for root in tree
+                .iter()
+                .filter(|node| node.category.category_type == transaction_type)
+            {
+                visit(root, 0, &totals, &mut rows);
+            }
+
+            if let Some((amount, count)) = uncategorized.get(transaction_type) {
+                rows.push(CategorySubtotal {
+                    category_id: None,
+                    name: "Sin categoría".to_string(),
+                    account_code: None,
+                    transaction_type: transaction_type.to_string(),
+                    depth: 0,
+                    amount: *amount,
+                    count: *count,
+                });
+            }
+        }
+
+        rows
+    }
+
+    fn get_report_title(spec: &ReportSpec) -> &'static str {
+        match spec.kind {
+            ReportKind::Transactions => {
+                let filters = &spec.filters;
+                if spec.grouping == ReportGrouping::Week {
+                    "RESUMEN SEMANAL"
+                } else if filters.tag_id.is_some() {
+                    "RESULTADOS POR ETIQUETA"
+                } else if !filters.category_ids.is_empty() {
+                    "REPORTE POR CATEGORÍA"
+                } else {
+                    match filters.transaction_type.as_deref() {
+                        Some("income") => "REPORTE DE INGRESOS",
+                        Some("expense") => "REPORTE DE EGRESOS",
+                        _ => "REPORTE",
+                    }
+                }
+            }
+            ReportKind::Balance => "BALANCE CONSOLIDADO",
+            ReportKind::Ledger => "LIBRO DE CAJA",
+            ReportKind::Budget => "EJECUCIÓN PRESUPUESTARIA",
+            ReportKind::Breakdown => "DISTRIBUCIÓN POR CATEGORÍA",
+            ReportKind::Comparison => "COMPARATIVO DE PERÍODOS",
+            ReportKind::Operators => "DESEMPEÑO POR OPERADOR",
         }
     }
 }
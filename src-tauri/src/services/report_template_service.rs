@@ -0,0 +1,250 @@
+use crate::db::Database;
+use crate::models::{
+    RelativeDateRange, ReportFormat, ReportKind, ReportSpec, ReportTemplate,
+    SaveReportTemplateRequest,
+};
+use crate::services::report_service::ReportService;
+use chrono::{Datelike, Local, Months, NaiveDate};
+use std::path::PathBuf;
+
+pub struct ReportTemplateService;
+
+impl ReportTemplateService {
+    /// Save a report definition under a name. The format of the spec is the
+    /// default format of the template.
+    pub fn create_template(
+        db: &Database,
+        request: SaveReportTemplateRequest,
+    ) -> Result<ReportTemplate, String> {
+        let (name, spec, output_folder) = Self::validate_request(db, &request, None)?;
+
+        db.create_report_template(&name, &spec, request.date_range, output_folder.as_deref())
+            .map_err(|e| format!("Error al guardar plantilla: {}", e))
+    }
+
+    pub fn update_template(
+        db: &Database,
+        template_id: i64,
+        request: SaveReportTemplateRequest,
+    ) -> Result<ReportTemplate, String> {
+        Self::get_template(db, template_id)?;
+        let (name, spec, output_folder) = Self::validate_request(db, &request, Some(template_id))?;
+
+        db.update_report_template(
+            template_id,
+            &name,
+            &spec,
+            request.date_range,
+            output_folder.as_deref(),
+        )
+        .map_err(|e| format!("Error al guardar plantilla: {}", e))
+    }
+
+    /// Get all active templates
+    pub fn get_templates(db: &Database) -> Result<Vec<ReportTemplate>, String> {
+        db.get_report_templates()
+            .map_err(|e| format!("Error al obtener plantillas: {}", e))
+    }
+
+    /// Delete (deactivate) a template
+    pub fn delete_template(db: &Database, template_id: i64) -> Result<(), String> {
+        Self::get_template(db, template_id)?;
+
+        db.delete_report_template(template_id)
+            .map_err(|e| format!("Error al eliminar plantilla: {}", e))
+    }
+
+    /// Generate the report of a template over its period as of today, in its
+    /// default format unless another one is given. Without an output folder
+    /// the report goes to `download_path`.
+    pub fn run_template(
+        db: &Database,
+        template_id: i64,
+        format: Option<ReportFormat>,
+        download_path: Option<String>,
+    ) -> Result<PathBuf, String> {
+        let template = Self::get_template(db, template_id)?;
+
+        let mut spec = Self::resolve_spec(db, &template, Local::now().date_naive())?;
+        if let Some(format) = format {
+            spec.format = format;
+        }
+        if spec.options.download_path.is_none() {
+            spec.options.download_path = download_path;
+        }
+
+        let file_path = ReportService::generate_report(db, &spec)?;
+
+        db.mark_report_template_run(
+            template_id,
+            &Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        )
+        .map_err(|e| format!("Error al actualizar plantilla: {}", e))?;
+
+        Ok(file_path)
+    }
+
+    /// The spec a template runs with on `today`: its relative period resolved
+    /// (and the one a comparison is made with) and its output folder applied
+    pub fn resolve_spec(
+        db: &Database,
+        template: &ReportTemplate,
+        today: NaiveDate,
+    ) -> Result<ReportSpec, String> {
+        let mut spec = template.spec.clone();
+
+        if let Some(date_range) = template.date_range {
+            let (start, end) = Self::resolve_date_range(db, date_range, today)?;
+            spec.start_date = start.format("%Y-%m-%d").to_string();
+            spec.end_date = end.format("%Y-%m-%d").to_string();
+
+            if spec.kind == ReportKind::Comparison {
+                let (compare_start, compare_end) =
+                    Self::previous_date_range(date_range, start, end)?;
+                spec.options.compare_start = Some(compare_start.format("%Y-%m-%d").to_string());
+                spec.options.compare_end = Some(compare_end.format("%Y-%m-%d").to_string());
+            }
+        }
+        spec.options.download_path = template.output_folder.clone();
+
+        Ok(spec)
+    }
+
+    /// First and last day of a relative period as of `today`
+    pub fn resolve_date_range(
+        db: &Database,
+        date_range: RelativeDateRange,
+        today: NaiveDate,
+    ) -> Result<(NaiveDate, NaiveDate), String> {
+        match date_range {
+            RelativeDateRange::LastMonth => {
+                let end = today
+                    .with_day(1)
+                    .and_then(|first| first.pred_opt())
+                    .ok_or("Fecha fuera de rango")?;
+                let start = end.with_day(1).ok_or("Fecha fuera de rango")?;
+
+                Ok((start, end))
+            }
+            RelativeDateRange::YearToDate => {
+                let start =
+                    NaiveDate::from_ymd_opt(today.year(), 1, 1).ok_or("Fecha fuera de rango")?;
+
+                Ok((start, today))
+            }
+            RelativeDateRange::CurrentTerm => {
+                let (start, end) = db
+                    .get_term_covering(&today.format("%Y-%m-%d").to_string())
+                    .map_err(|e| format!("Error al obtener el período: {}", e))?
+                    .ok_or(
+                        "Ningún presupuesto por período ('term') incluye la fecha de hoy. \
+                         Registre uno para definir el período actual",
+                    )?;
+
+                let parse = |date: &str| {
+                    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                        .map_err(|_| "Fecha inválida en el período".to_string())
+                };
+
+                Ok((parse(&start)?, parse(&end)?))
+            }
+        }
+    }
+
+    /// Period a relative period is compared with: the month before, the same
+    /// days of the previous year, or as many days right before the term
+    pub fn previous_date_range(
+        date_range: RelativeDateRange,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<(NaiveDate, NaiveDate), String> {
+        match date_range {
+            RelativeDateRange::LastMonth => {
+                let end = start.pred_opt().ok_or("Fecha fuera de rango")?;
+                let start = end.with_day(1).ok_or("Fecha fuera de rango")?;
+
+                Ok((start, end))
+            }
+            RelativeDateRange::YearToDate => {
+                let year_before = |date: NaiveDate| {
+                    date.checked_sub_months(Months::new(12))
+                        .ok_or("Fecha fuera de rango")
+                };
+
+                Ok((year_before(start)?, year_before(end)?))
+            }
+            RelativeDateRange::CurrentTerm => {
+                let previous_end = start.pred_opt().ok_or("Fecha fuera de rango")?;
+
+                Ok((previous_end - (end - start), previous_end))
+            }
+        }
+    }
+
+    fn get_template(db: &Database, template_id: i64) -> Result<ReportTemplate, String> {
+        let template = db
+            .get_report_template_by_id(template_id)
+            .map_err(|_| "Plantilla no encontrada".to_string())?;
+
+        if !template.is_active {
+            return Err("La plantilla está eliminada".to_string());
+        }
+
+        Ok(template)
+    }
+
+    /// Name, spec and output folder to save from a request. The spec is
+    /// checked as it would run today.
+    fn validate_request(
+        db: &Database,
+        request: &SaveReportTemplateRequest,
+        template_id: Option<i64>,
+    ) -> Result<(String, ReportSpec, Option<String>), String> {
+        let name = request.name.trim().to_string();
+        if name.is_empty() {
+            return Err("El nombre de la plantilla es requerido".to_string());
+        }
+
+        let existing = db
+            .find_report_template_by_name(&name)
+            .map_err(|e| format!("Error al buscar plantillas: {}", e))?;
+        if let Some(existing) = existing
+            && Some(existing.id) != template_id
+        {
+            return Err(format!(
+                "Ya existe una plantilla llamada '{}'",
+                existing.name
+            ));
+        }
+
+        let output_folder = request
+            .output_folder
+            .as_deref()
+            .map(str::trim)
+            .filter(|folder| !folder.is_empty())
+            .map(str::to_string);
+
+        // The folder is kept with the template, not in the spec
+        let mut spec = request.spec.clone();
+        spec.options.download_path = None;
+
+        // A comparison over a relative period is compared with the period
+        // before it, resolved on each run
+        if request.date_range.is_some() && spec.kind == ReportKind::Comparison {
+            spec.options.compare_start = None;
+            spec.options.compare_end = None;
+        }
+
+        // A relative period is checked as a one-day range; the term may not
+        // have been defined yet when the template is saved
+        let mut check = spec.clone();
+        if request.date_range.is_some() {
+            let today = Local::now().format("%Y-%m-%d").to_string();
+            check.start_date = today.clone();
+            check.end_date = today;
+        }
+        ReportService::validate_spec(db, &check)?;
+
+        Ok((name, spec, output_folder))
+    }
+}
@@ -67,7 +67,7 @@ impl SessionService {
         let (total_income, income_count): (f64, i64) = conn.query_row(
             "SELECT COALESCE(SUM(amount), 0), COUNT(*) 
              FROM transactions 
-             WHERE session_id = ? AND type = 'income'",
+             WHERE session_id = ? AND type = 'income' AND voided_at IS NULL",
             [session_id],
             |row| Ok((row.get(0)?, row.get(1)?)),
         )?;
@@ -75,7 +75,7 @@ impl SessionService {
         let (total_expense, expense_count): (f64, i64) = conn.query_row(
             "SELECT COALESCE(SUM(amount), 0), COUNT(*) 
              FROM transactions 
-             WHERE session_id = ? AND type = 'expense'",
+             WHERE session_id = ? AND type = 'expense' AND voided_at IS NULL",
             [session_id],
             |row| Ok((row.get(0)?, row.get(1)?)),
         )?;
@@ -1,8 +1,13 @@
 use crate::db::Database;
-use crate::models::{Category, CreateTransactionRequest, Transaction};
+use crate::models::{
+    Category, CategoryNode, CreateTransactionRequest, GetTransactionsRequest, Page, Tag,
+    Transaction, TransactionLineRequest, TransactionSearchResult,
+};
+use crate::services::budget_service::BudgetService;
 use crate::services::session_service::SessionService;
 use chrono;
 use rusqlite::Result;
+use std::collections::{HashMap, HashSet};
 
 pub struct TransactionService;
 
@@ -12,25 +17,13 @@ impl TransactionService {
         db: &Database,
         request: CreateTransactionRequest,
     ) -> Result<Transaction> {
-        // Validate input
-        if request.concept.trim().is_empty() {
-            return Err(rusqlite::Error::InvalidParameterName(
-                "El concepto es requerido".to_string(),
-            ));
-        }
-
-        if request.amount <= 0.0 {
-            return Err(rusqlite::Error::InvalidParameterName(
-                "El monto debe ser mayor a cero".to_string(),
-            ));
-        }
-
-        // Validate transaction type
-        if request.transaction_type != "income" && request.transaction_type != "expense" {
-            return Err(rusqlite::Error::InvalidParameterName(
-                "El tipo de transacción debe ser 'income' o 'expense'".to_string(),
-            ));
-        }
+        Self::validate_transaction_fields(
+            &request.transaction_type,
+            request.amount,
+            &request.concept,
+        )?;
+        let payment_method = request.payment_method.as_deref().unwrap_or("cash");
+        Self::validate_payment_method(payment_method)?;
 
         // Verify session exists and is active
         match SessionService::get_active_session(db) {
@@ -52,22 +45,30 @@ impl TransactionService {
         // For expenses, verify there's enough balance
         if request.transaction_type == "expense" {
             let summary = SessionService::get_session_summary(db, request.session_id)?;
-            if request.amount > summary.current_balance {
-                return Err(rusqlite::Error::InvalidParameterName(format!(
-                    "Saldo insuficiente. Balance actual: ${:.2}",
-                    summary.current_balance
-                )));
-            }
+            Self::check_balance(summary.current_balance, request.amount)?;
         }
 
-        // Validate category if provided
-        if let Some(cat_id) = request.category_id {
-            let category = db.get_category_by_id(cat_id)?;
-            if category.category_type != request.transaction_type {
-                return Err(rusqlite::Error::InvalidParameterName(
-                    "La categoría no coincide con el tipo de transacción".to_string(),
-                ));
+        // Validate the category, or every line of a split transaction
+        if request.lines.is_empty() {
+            if let Some(cat_id) = request.category_id {
+                Self::validate_transaction_category(db, cat_id, &request.transaction_type)?;
             }
+        } else {
+            Self::validate_transaction_lines(
+                db,
+                &request.lines,
+                request.category_id,
+                request.amount,
+                &request.transaction_type,
+            )?;
+        }
+
+        // Enforce budgets configured to block when exceeded
+        if request.transaction_type == "expense" {
+            BudgetService::check_expense(
+                db,
+                &Self::category_allocations(request.category_id, request.amount, &request.lines),
+            )?;
         }
 
         db.create_transaction(
@@ -76,7 +77,14 @@ impl TransactionService {
             request.amount,
             &request.concept,
             request.category_id,
+            request
+                .counterparty
+                .as_deref()
+                .map(str::trim)
+                .filter(|c| !c.is_empty()),
+            payment_method,
             &request.created_by,
+            &request.lines,
         )
     }
 
@@ -85,82 +93,65 @@ impl TransactionService {
         db.get_transaction_by_id(transaction_id)
     }
 
-    /// Get transactions with filters and pagination
+    /// Get a page of transactions matching a structured query
     pub fn get_transactions(
         db: &Database,
-        session_id: Option<i64>,
-        transaction_type: Option<String>,
-        start_date: Option<String>,
-        end_date: Option<String>,
-        limit: i64,
-        offset: i64,
-    ) -> Result<(Vec<Transaction>, i64)> {
-        // Get filtered transactions
-        let transactions = db.get_transactions(
-            session_id,
-            transaction_type.as_deref(),
-            start_date.as_deref(),
-            end_date.as_deref(),
-            limit,
-            offset,
-        )?;
-
-        // Get total count for pagination
-        let total_count =
-            Self::get_transaction_count(db, session_id, transaction_type, start_date, end_date)?;
-
-        Ok((transactions, total_count))
+        query: GetTransactionsRequest,
+    ) -> Result<Page<Transaction>> {
+        db.query_transactions(&Self::prepare_query(query)?)
     }
 
-    /// Get total count of transactions matching filters
-    fn get_transaction_count(
-        db: &Database,
-        session_id: Option<i64>,
-        transaction_type: Option<String>,
-        start_date: Option<String>,
-        end_date: Option<String>,
-    ) -> Result<i64> {
-        let conn = db.get_connection();
-        let conn = conn.lock().unwrap();
-
-        let mut query = String::from("SELECT COUNT(*) FROM transactions WHERE 1=1");
-
-        let mut params: Vec<String> = Vec::new();
-
-        if let Some(id) = session_id {
-            query.push_str(" AND session_id = ?");
-            params.push(id.to_string());
+    /// Validate a structured transaction query and normalize its tags
+    pub fn prepare_query(mut query: GetTransactionsRequest) -> Result<GetTransactionsRequest> {
+        if let Some(t) = query.transaction_type.as_deref()
+            && t != "income"
+            && t != "expense"
+        {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "El tipo de transacción debe ser 'income' o 'expense'".to_string(),
+            ));
         }
 
-        if let Some(t) = transaction_type {
-            query.push_str(" AND type = ?");
-            params.push(t);
+        if let (Some(min), Some(max)) = (query.min_amount, query.max_amount)
+            && min > max
+        {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "El monto mínimo no puede ser mayor al máximo".to_string(),
+            ));
         }
 
-        if let Some(start) = start_date {
-            query.push_str(" AND date(created_at) >= date(?)");
-            params.push(start);
+        if query.limit.is_some_and(|limit| limit <= 0)
+            || query.offset.is_some_and(|offset| offset < 0)
+        {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "Paginación inválida".to_string(),
+            ));
         }
 
-        if let Some(end) = end_date {
-            query.push_str(" AND date(created_at) <= date(?)");
-            params.push(end);
+        if let Some(payment_method) = query.payment_method.as_deref() {
+            Self::validate_payment_method(payment_method)?;
         }
 
-        let mut stmt = conn.prepare(&query)?;
-        let param_refs: Vec<&dyn rusqlite::ToSql> =
-            params.iter().map(|p| p as &dyn rusqlite::ToSql).collect();
+        query.tags = query.tags.map(Self::normalize_tags);
 
-        stmt.query_row(param_refs.as_slice(), |row| row.get(0))
+        Ok(query)
     }
 
-    /// Get recent transactions for the dashboard
+    /// Get recent transactions for the dashboard, newest first. Pass the
+    /// `next_cursor` of a page to continue after it.
     pub fn get_recent_transactions(
         db: &Database,
         session_id: Option<i64>,
         limit: i64,
-    ) -> Result<Vec<Transaction>> {
-        db.get_transactions(session_id, None, None, None, limit, 0)
+        cursor: Option<String>,
+    ) -> Result<Page<Transaction>> {
+        db.query_transactions(&GetTransactionsRequest {
+            session_id,
+            limit: Some(limit),
+            cursor,
+            include_total: Some(false),
+            ..Default::default()
+        })
     }
 
     /// Get all categories
@@ -179,8 +170,56 @@ impl TransactionService {
         db.get_categories(Some(&category_type))
     }
 
-    /// Create a new category
-    pub fn create_category(db: &Database, name: String, category_type: String) -> Result<Category> {
+    /// Get categories by type as a nested tree
+    pub fn get_category_tree(db: &Database, category_type: String) -> Result<Vec<CategoryNode>> {
+        let categories = Self::get_categories_by_type(db, category_type)?;
+        Ok(Self::build_category_tree(categories))
+    }
+
+    /// Arrange a flat list of categories into trees. Categories whose parent is
+    /// not in the list are treated as roots.
+    pub fn build_category_tree(categories: Vec<Category>) -> Vec<CategoryNode> {
+        let ids: HashSet<i64> = categories.iter().map(|c| c.id).collect();
+        let mut children: HashMap<i64, Vec<Category>> = HashMap::new();
+        let mut roots = Vec::new();
+
+        for category in categories {
+            match category.parent_id {
+                Some(parent_id) if ids.contains(&parent_id) => {
+                    children.entry(parent_id).or_default().push(category)
+                }
+                _ => roots.push(category),
+            }
+        }
+
+        fn attach(category: Category, children: &mut HashMap<i64, Vec<Category>>) -> CategoryNode {
+            let nodes = children
+                .remove(&category.id)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|child| attach(child, children))
+                .collect();
+
+            CategoryNode {
+                category,
+                children: nodes,
+            }
+        }
+
+        roots
+            .into_iter()
+            .map(|root| attach(root, &mut children))
+            .collect()
+    }
+
+    /// Create a new category, optionally under a parent category
+    pub fn create_category(
+        db: &Database,
+        name: String,
+        category_type: String,
+        parent_id: Option<i64>,
+        account_code: Option<String>,
+    ) -> Result<Category> {
         // Validate input
         if name.trim().is_empty() {
             return Err(rusqlite::Error::InvalidParameterName(
@@ -194,11 +233,27 @@ impl TransactionService {
             ));
         }
 
-        db.create_category(&name, &category_type)
+        Self::ensure_unique_category_name(db, &name, &category_type, None)?;
+
+        if let Some(parent_id) = parent_id {
+            Self::validate_parent_category(db, parent_id, &category_type)?;
+        }
+
+        db.create_category(
+            name.trim(),
+            &category_type,
+            parent_id,
+            Self::normalize_account_code(account_code.as_deref()),
+        )
     }
 
-    /// Update a category name
-    pub fn update_category(db: &Database, category_id: i64, name: String) -> Result<Category> {
+    /// Update a category name and accounting code
+    pub fn update_category(
+        db: &Database,
+        category_id: i64,
+        name: String,
+        account_code: Option<String>,
+    ) -> Result<Category> {
         // Validate input
         if name.trim().is_empty() {
             return Err(rusqlite::Error::InvalidParameterName(
@@ -207,9 +262,50 @@ impl TransactionService {
         }
 
         // Check if category exists
-        let _ = db.get_category_by_id(category_id)?;
+        let current = db.get_category_by_id(category_id)?;
+
+        if current.is_active {
+            Self::ensure_unique_category_name(
+                db,
+                &name,
+                &current.category_type,
+                Some(category_id),
+            )?;
+        }
+
+        // Keep the current code unless a new one is given; an empty code clears it
+        let account_code = match account_code.as_deref() {
+            Some(code) => Self::normalize_account_code(Some(code)),
+            None => current.account_code.as_deref(),
+        };
 
-        db.update_category(category_id, &name)
+        db.update_category(category_id, name.trim(), account_code)
+    }
+
+    /// Move a category under another parent, or to the root when `parent_id` is None
+    pub fn move_category(
+        db: &Database,
+        category_id: i64,
+        parent_id: Option<i64>,
+    ) -> Result<Category> {
+        let category = db.get_category_by_id(category_id)?;
+
+        if let Some(parent_id) = parent_id {
+            Self::validate_parent_category(db, parent_id, &category.category_type)?;
+
+            // Walk up from the new parent to make sure we are not creating a cycle
+            let mut ancestor = Some(parent_id);
+            while let Some(id) = ancestor {
+                if id == category_id {
+                    return Err(rusqlite::Error::InvalidParameterName(
+                        "Una categoría no puede ser subcategoría de sí misma".to_string(),
+                    ));
+                }
+                ancestor = db.get_category_by_id(id)?.parent_id;
+            }
+        }
+
+        db.set_category_parent(category_id, parent_id)
     }
 
     /// Delete a category (soft delete)
@@ -217,9 +313,318 @@ impl TransactionService {
         // Check if category exists
         let _ = db.get_category_by_id(category_id)?;
 
+        if db.category_has_children(category_id)? {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "La categoría tiene subcategorías activas".to_string(),
+            ));
+        }
+
         db.delete_category(category_id)
     }
 
+    /// Get deactivated categories, optionally filtered by type
+    pub fn get_inactive_categories(
+        db: &Database,
+        category_type: Option<String>,
+    ) -> Result<Vec<Category>> {
+        if let Some(t) = category_type.as_deref()
+            && t != "income"
+            && t != "expense"
+        {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "El tipo debe ser 'income' o 'expense'".to_string(),
+            ));
+        }
+
+        db.get_inactive_categories(category_type.as_deref())
+    }
+
+    /// Reactivate a soft-deleted category
+    pub fn reactivate_category(db: &Database, category_id: i64) -> Result<Category> {
+        let category = db.get_category_by_id(category_id)?;
+
+        if category.is_active {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "La categoría ya está activa".to_string(),
+            ));
+        }
+
+        Self::ensure_unique_category_name(
+            db,
+            &category.name,
+            &category.category_type,
+            Some(category_id),
+        )?;
+
+        if let Some(parent_id) = category.parent_id
+            && !db.get_category_by_id(parent_id)?.is_active
+        {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "Debe reactivar primero la categoría padre".to_string(),
+            ));
+        }
+
+        db.reactivate_category(category_id)
+    }
+
+    /// Merge category `source_id` into `target_id`: all transactions, recurring
+    /// templates and budgets are reassigned to the target and the source is
+    /// deactivated. Returns the transactions moved.
+    pub fn merge_categories(
+        db: &Database,
+        source_id: i64,
+        target_id: i64,
+        performed_by: Option<String>,
+    ) -> Result<usize> {
+        if source_id == target_id {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "No se puede fusionar una categoría consigo misma".to_string(),
+            ));
+        }
+
+        let source = db.get_category_by_id(source_id)?;
+        let target = db.get_category_by_id(target_id)?;
+
+        if source.category_type != target.category_type {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "Las categorías deben ser del mismo tipo".to_string(),
+            ));
+        }
+
+        if !target.is_active {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "La categoría destino está desactivada".to_string(),
+            ));
+        }
+
+        if db.category_has_children(source_id)? {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "La categoría origen tiene subcategorías activas".to_string(),
+            ));
+        }
+
+        // Transactions may only point at leaf categories
+        if db.category_has_children(target_id)? {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "La categoría destino tiene subcategorías".to_string(),
+            ));
+        }
+
+        // Budgets follow the spending they control; the target can't end up
+        // with two budgets for the same days
+        for budget in db
+            .get_budgets(None, None)?
+            .into_iter()
+            .filter(|budget| budget.category_id == source_id)
+        {
+            if !db
+                .get_overlapping_budgets(target_id, &budget.period_start, &budget.period_end)?
+                .is_empty()
+            {
+                return Err(rusqlite::Error::InvalidParameterName(format!(
+                    "La categoría destino ya tiene un presupuesto entre {} y {}",
+                    budget.period_start, budget.period_end
+                )));
+            }
+        }
+
+        db.merge_categories(
+            source_id,
+            target_id,
+            &format!(
+                "Categoría '{}' (#{}) fusionada en '{}' (#{})",
+                source.name, source_id, target.name, target_id
+            ),
+            performed_by.as_deref(),
+        )
+    }
+
+    fn ensure_unique_category_name(
+        db: &Database,
+        name: &str,
+        category_type: &str,
+        exclude_id: Option<i64>,
+    ) -> Result<()> {
+        match db.find_active_category_by_name(name, category_type)? {
+            Some(existing) if Some(existing.id) != exclude_id => {
+                Err(rusqlite::Error::InvalidParameterName(format!(
+                    "Ya existe una categoría activa llamada '{}'",
+                    existing.name
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Validate the type, amount and concept of a new transaction
+    pub fn validate_transaction_fields(
+        transaction_type: &str,
+        amount: f64,
+        concept: &str,
+    ) -> Result<()> {
+        if concept.trim().is_empty() {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "El concepto es requerido".to_string(),
+            ));
+        }
+
+        if amount <= 0.0 {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "El monto debe ser mayor a cero".to_string(),
+            ));
+        }
+
+        if transaction_type != "income" && transaction_type != "expense" {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "El tipo de transacción debe ser 'income' o 'expense'".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Payment methods are a fixed list so they can be filtered and summed
+    pub fn validate_payment_method(payment_method: &str) -> Result<()> {
+        if !["cash", "card", "transfer", "other"].contains(&payment_method) {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "El medio de pago debe ser 'cash', 'card', 'transfer' u 'other'".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// An expense cannot exceed the cash available in the session
+    pub fn check_balance(current_balance: f64, amount: f64) -> Result<()> {
+        if amount > current_balance {
+            return Err(rusqlite::Error::InvalidParameterName(format!(
+                "Saldo insuficiente. Balance actual: ${:.2}",
+                current_balance
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Transactions may only point at active leaf categories of the same type
+    pub fn validate_transaction_category(
+        db: &Database,
+        category_id: i64,
+        transaction_type: &str,
+    ) -> Result<()> {
+        let category = db.get_category_by_id(category_id)?;
+
+        if category.category_type != transaction_type {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "La categoría no coincide con el tipo de transacción".to_string(),
+            ));
+        }
+
+        if !category.is_active {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "La categoría está desactivada".to_string(),
+            ));
+        }
+
+        if db.category_has_children(category_id)? {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "Seleccione una subcategoría; la categoría tiene subcategorías".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Validate the category lines of a split transaction: each line must use a
+    /// valid category for the transaction type and the lines must add up to the total.
+    fn validate_transaction_lines(
+        db: &Database,
+        lines: &[TransactionLineRequest],
+        category_id: Option<i64>,
+        amount: f64,
+        transaction_type: &str,
+    ) -> Result<()> {
+        if category_id.is_some() {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "Una transacción dividida no puede tener además una categoría única".to_string(),
+            ));
+        }
+
+        for line in lines {
+            if line.amount <= 0.0 {
+                return Err(rusqlite::Error::InvalidParameterName(
+                    "El monto de cada línea debe ser mayor a cero".to_string(),
+                ));
+            }
+
+            Self::validate_transaction_category(db, line.category_id, transaction_type)?;
+        }
+
+        let lines_total: f64 = lines.iter().map(|line| line.amount).sum();
+        if (lines_total - amount).abs() >= 0.005 {
+            return Err(rusqlite::Error::InvalidParameterName(format!(
+                "Las líneas suman ${:.2} y el total de la transacción es ${:.2}",
+                lines_total, amount
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Amount per category: the lines of a split transaction (grouped by
+    /// category) or the whole amount under its single category
+    fn category_allocations(
+        category_id: Option<i64>,
+        amount: f64,
+        lines: &[TransactionLineRequest],
+    ) -> Vec<(i64, f64)> {
+        if lines.is_empty() {
+            return category_id.map(|id| (id, amount)).into_iter().collect();
+        }
+
+        let mut allocations: Vec<(i64, f64)> = Vec::new();
+        for line in lines {
+            match allocations
+                .iter_mut()
+                .find(|(id, _)| *id == line.category_id)
+            {
+                Some((_, total)) => *total += line.amount,
+                None => allocations.push((line.category_id, line.amount)),
+            }
+        }
+
+        allocations
+    }
+
+    fn validate_parent_category(db: &Database, parent_id: i64, category_type: &str) -> Result<()> {
+        let parent = db.get_category_by_id(parent_id)?;
+
+        if parent.category_type != category_type {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "La categoría padre debe ser del mismo tipo".to_string(),
+            ));
+        }
+
+        if !parent.is_active {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "La categoría padre está desactivada".to_string(),
+            ));
+        }
+
+        // Otherwise its transactions would end up on a non-leaf category
+        if db.category_has_transactions(parent_id)? {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "La categoría padre ya tiene transacciones registradas".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn normalize_account_code(account_code: Option<&str>) -> Option<&str> {
+        account_code.map(str::trim).filter(|code| !code.is_empty())
+    }
+
     /// Get today's transactions summary
     pub fn get_today_transactions_summary(db: &Database) -> Result<TransactionsSummary> {
         let today = chrono::Local::now().format("%Y-%m-%d").to_string();
@@ -236,18 +641,26 @@ impl TransactionService {
 
         // Get income stats
         let (total_income, income_count): (f64, i64) = conn.query_row(
-            "SELECT COALESCE(SUM(amount), 0), COUNT(*) 
-             FROM transactions 
-             WHERE type = 'income' AND date(created_at) = date(?)",
+            &format!(
+                "SELECT COALESCE(SUM(amount), 0), COUNT(*)
+                 FROM transactions
+                 WHERE type = 'income' AND {} AND {} AND voided_at IS NULL",
+                Database::local_day_from("created_at", "?1"),
+                Database::local_day_until("created_at", "?1")
+            ),
             [date],
             |row| Ok((row.get(0)?, row.get(1)?)),
         )?;
 
         // Get expense stats
         let (total_expense, expense_count): (f64, i64) = conn.query_row(
-            "SELECT COALESCE(SUM(amount), 0), COUNT(*) 
-             FROM transactions 
-             WHERE type = 'expense' AND date(created_at) = date(?)",
+            &format!(
+                "SELECT COALESCE(SUM(amount), 0), COUNT(*)
+                 FROM transactions
+                 WHERE type = 'expense' AND {} AND {} AND voided_at IS NULL",
+                Database::local_day_from("created_at", "?1"),
+                Database::local_day_until("created_at", "?1")
+            ),
             [date],
             |row| Ok((row.get(0)?, row.get(1)?)),
         )?;
@@ -263,59 +676,112 @@ impl TransactionService {
         })
     }
 
-    /// Search transactions by concept or transaction number
+    /// Full-text search of `query.text` over number, concept and counterparty,
+    /// ranked by relevance, narrowed by the other filters of the query.
+    /// Accents are ignored and every word matches as a prefix.
     pub fn search_transactions(
         db: &Database,
-        query: &str,
-        limit: i64,
-        offset: i64,
-    ) -> Result<(Vec<Transaction>, i64)> {
-        let conn = db.get_connection();
-        let conn = conn.lock().unwrap();
+        query: GetTransactionsRequest,
+    ) -> Result<Page<TransactionSearchResult>> {
+        let page = db.search_transactions(&Self::prepare_query(query)?)?;
+
+        // The raw snippet marks matches with control characters
+        let items = page
+            .items
+            .into_iter()
+            .map(|result| TransactionSearchResult {
+                snippet: Self::highlight_snippet(&result.snippet),
+                ..result
+            })
+            .collect();
+
+        Ok(Page { items, ..page })
+    }
 
-        let search_term = format!("%{}%", query);
-
-        // Get matching transactions
-        let mut stmt = conn.prepare(
-            "SELECT 
-                t.id, t.session_id, t.transaction_number, t.type, t.amount, 
-                t.concept, t.category_id, c.name as category_name, t.created_at, t.created_by
-             FROM transactions t
-             LEFT JOIN categories c ON t.category_id = c.id
-             WHERE t.concept LIKE ?1 OR t.transaction_number LIKE ?1
-             ORDER BY t.created_at DESC
-             LIMIT ?2 OFFSET ?3",
-        )?;
+    /// Escape a raw FTS snippet for HTML and turn its match markers into <mark>
+    fn highlight_snippet(snippet: &str) -> String {
+        let mut html = String::with_capacity(snippet.len());
+
+        for c in snippet.chars() {
+            match c {
+                '\u{2}' => html.push_str("<mark>"),
+                '\u{3}' => html.push_str("</mark>"),
+                '&' => html.push_str("&amp;"),
+                '<' => html.push_str("&lt;"),
+                '>' => html.push_str("&gt;"),
+                '"' => html.push_str("&quot;"),
+                '\'' => html.push_str("&#39;"),
+                _ => html.push(c),
+            }
+        }
 
-        let transactions = stmt.query_map(
-            [&search_term, &limit.to_string(), &offset.to_string()],
-            |row| {
-                Ok(Transaction {
-                    id: row.get(0)?,
-                    session_id: row.get(1)?,
-                    transaction_number: row.get(2)?,
-                    transaction_type: row.get(3)?,
-                    amount: row.get(4)?,
-                    concept: row.get(5)?,
-                    category_id: row.get(6)?,
-                    category_name: row.get(7)?,
-                    created_at: row.get(8)?,
-                    created_by: row.get(9)?,
-                })
-            },
-        )?;
+        html
+    }
+
+    /// Get every tag with the number of transactions carrying it
+    pub fn get_tags(db: &Database) -> Result<Vec<Tag>> {
+        db.get_tags()
+    }
 
-        let transactions: Vec<Transaction> = transactions.collect::<Result<Vec<_>>>()?;
+    /// Tag a transaction; unknown tags are created on the fly
+    pub fn add_transaction_tags(
+        db: &Database,
+        transaction_id: i64,
+        tags: Vec<String>,
+    ) -> Result<Transaction> {
+        let tags = Self::normalize_tags(tags);
+        if tags.is_empty() {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "Indique al menos una etiqueta".to_string(),
+            ));
+        }
 
-        // Get total count
-        let total_count: i64 = conn.query_row(
-            "SELECT COUNT(*) FROM transactions 
-             WHERE concept LIKE ?1 OR transaction_number LIKE ?1",
-            [&search_term],
-            |row| row.get(0),
-        )?;
+        if tags.iter().any(|tag| tag.chars().count() > 50) {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "Las etiquetas no pueden superar los 50 caracteres".to_string(),
+            ));
+        }
+
+        let _ = db.get_transaction_by_id(transaction_id)?;
+        db.add_transaction_tags(transaction_id, &tags)?;
 
-        Ok((transactions, total_count))
+        db.get_transaction_by_id(transaction_id)
+    }
+
+    /// Remove a tag from a transaction
+    pub fn remove_transaction_tag(
+        db: &Database,
+        transaction_id: i64,
+        tag: &str,
+    ) -> Result<Transaction> {
+        let _ = db.get_transaction_by_id(transaction_id)?;
+
+        if db.remove_transaction_tag(transaction_id, tag.trim())? == 0 {
+            return Err(rusqlite::Error::InvalidParameterName(format!(
+                "La transacción no tiene la etiqueta '{}'",
+                tag.trim()
+            )));
+        }
+
+        db.get_transaction_by_id(transaction_id)
+    }
+
+    /// Trim tags and drop empty and repeated (case-insensitive) ones
+    fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+        let mut normalized: Vec<String> = Vec::new();
+
+        for tag in tags {
+            let tag = tag.trim();
+            if !tag.is_empty()
+                && !normalized
+                    .iter()
+                    .any(|t| t.to_lowercase() == tag.to_lowercase())
+            {
+                normalized.push(tag.to_string());
+            }
+        }
+
+        normalized
     }
 
     /// Update a transaction
@@ -325,6 +791,7 @@ impl TransactionService {
         amount: f64,
         concept: &str,
         category_id: Option<i64>,
+        lines: Option<Vec<TransactionLineRequest>>,
     ) -> Result<Transaction> {
         // Validate input
         if concept.trim().is_empty() {
@@ -341,15 +808,38 @@ impl TransactionService {
 
         // Get current transaction to check session and type
         let current = db.get_transaction_by_id(transaction_id)?;
+        if current.voided_at.is_some() {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "La transacción está anulada".to_string(),
+            ));
+        }
 
-        // Validate category if provided
-        if let Some(cat_id) = category_id {
-            let category = db.get_category_by_id(cat_id)?;
-            if category.category_type != current.transaction_type {
-                return Err(rusqlite::Error::InvalidParameterName(
-                    "La categoría no coincide con el tipo de transacción".to_string(),
-                ));
+        // Without new lines, a split transaction keeps its current ones
+        let effective_lines = match &lines {
+            Some(lines) => lines.clone(),
+            None => current
+                .lines
+                .iter()
+                .map(|line| TransactionLineRequest {
+                    category_id: line.category_id,
+                    amount: line.amount,
+                })
+                .collect(),
+        };
+
+        // Validate the category, or every line of a split transaction
+        if effective_lines.is_empty() {
+            if let Some(cat_id) = category_id {
+                Self::validate_transaction_category(db, cat_id, &current.transaction_type)?;
             }
+        } else {
+            Self::validate_transaction_lines(
+                db,
+                &effective_lines,
+                category_id,
+                amount,
+                &current.transaction_type,
+            )?;
         }
 
         // Perform the update
@@ -361,16 +851,67 @@ impl TransactionService {
             )
         })?;
 
-        conn.execute(
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
             "UPDATE transactions SET amount = ?1, concept = ?2, category_id = ?3 WHERE id = ?4",
             rusqlite::params![amount, concept, category_id, transaction_id],
         )?;
+        if let Some(lines) = &lines {
+            Database::replace_transaction_lines(&tx, transaction_id, lines)?;
+        }
+        tx.commit()?;
 
         // Return the updated transaction
         drop(conn);
         db.get_transaction_by_id(transaction_id)
     }
 
+    /// Void a transaction of the active session. It stays in the history but
+    /// no longer counts in balances, reports or budgets. Closed sessions are
+    /// left alone so their closing difference does not change afterwards.
+    pub fn void_transaction(
+        db: &Database,
+        transaction_id: i64,
+        performed_by: Option<String>,
+    ) -> Result<Transaction> {
+        let current = db.get_transaction_by_id(transaction_id)?;
+        if current.voided_at.is_some() {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "La transacción ya está anulada".to_string(),
+            ));
+        }
+
+        if !db.get_session_by_id(current.session_id)?.is_active {
+            return Err(rusqlite::Error::InvalidParameterName(
+                "No se puede anular una transacción de una sesión cerrada".to_string(),
+            ));
+        }
+
+        // Voiding an income takes its cash out of the session
+        if current.transaction_type == "income" {
+            let summary = SessionService::get_session_summary(db, current.session_id)?;
+            Self::check_balance(summary.current_balance, current.amount)?;
+        }
+
+        db.void_transaction(
+            transaction_id,
+            &format!(
+                "Transacción {} anulada ({} de ${:.2}: {})",
+                current.transaction_number,
+                if current.transaction_type == "income" {
+                    "ingreso"
+                } else {
+                    "egreso"
+                },
+                current.amount,
+                current.concept
+            ),
+            performed_by.as_deref(),
+        )?;
+
+        db.get_transaction_by_id(transaction_id)
+    }
+
     /// Delete a transaction
     pub fn delete_transaction(db: &Database, transaction_id: i64) -> Result<()> {
         let conn = db.get_connection();
@@ -381,7 +922,19 @@ impl TransactionService {
             )
         })?;
 
-        conn.execute("DELETE FROM transactions WHERE id = ?1", [transaction_id])?;
+        // Foreign keys are not enforced, so lines and tag links are not
+        // deleted by cascade
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            "DELETE FROM transaction_lines WHERE transaction_id = ?1",
+            [transaction_id],
+        )?;
+        tx.execute(
+            "DELETE FROM transaction_tags WHERE transaction_id = ?1",
+            [transaction_id],
+        )?;
+        tx.execute("DELETE FROM transactions WHERE id = ?1", [transaction_id])?;
+        tx.commit()?;
 
         Ok(())
     }
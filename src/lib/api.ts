@@ -23,6 +23,32 @@ export interface Transaction {
   category_name: string | null;
   created_at: string;
   created_by: string;
+  counterparty: string | null;
+  payment_method: PaymentMethod;
+  voided_at: string | null;
+  lines: TransactionLine[];
+  tags: string[];
+}
+
+export type PaymentMethod = 'cash' | 'card' | 'transfer' | 'other';
+
+export interface TransactionSearchResult extends Transaction {
+  snippet: string; // HTML-escaped, matches wrapped in <mark>
+  rank: number;
+}
+
+export interface Tag {
+  id: number;
+  name: string;
+  transaction_count: number;
+}
+
+export interface TransactionLine {
+  id: number;
+  transaction_id: number;
+  category_id: number;
+  category_name: string | null;
+  amount: number;
 }
 
 export interface Category {
@@ -30,6 +56,12 @@ export interface Category {
   name: string;
   category_type: 'income' | 'expense';
   is_active: boolean;
+  parent_id: number | null;
+  account_code: string | null;
+}
+
+export interface CategoryNode extends Category {
+  children: CategoryNode[];
 }
 
 export interface DailySummary {
@@ -91,6 +123,52 @@ export const sessionApi = {
 };
 
 // Transaction API
+
+// Filters and paging shared by listing and search
+export interface TransactionQuery {
+  sessionId?: number;
+  transactionType?: string;
+  startDate?: string;
+  endDate?: string;
+  categoryIds?: number[];
+  minAmount?: number;
+  maxAmount?: number;
+  createdBy?: string;
+  counterparty?: string;
+  text?: string;
+  tags?: string[];
+  paymentMethod?: PaymentMethod;
+  voided?: boolean;
+  sortBy?: 'date' | 'amount' | 'number' | 'concept' | 'category' | 'created_by';
+  sortDirection?: 'asc' | 'desc';
+  limit?: number;
+  offset?: number;
+  cursor?: string;
+  includeTotal?: boolean;
+}
+
+const transactionRequest = (params: TransactionQuery) => ({
+  session_id: params.sessionId,
+  transaction_type: params.transactionType,
+  start_date: params.startDate,
+  end_date: params.endDate,
+  category_ids: params.categoryIds,
+  min_amount: params.minAmount,
+  max_amount: params.maxAmount,
+  created_by: params.createdBy,
+  counterparty: params.counterparty,
+  text: params.text,
+  tags: params.tags,
+  payment_method: params.paymentMethod,
+  voided: params.voided,
+  sort_by: params.sortBy,
+  sort_direction: params.sortDirection,
+  limit: params.limit || 50,
+  offset: params.offset || 0,
+  cursor: params.cursor,
+  include_total: params.includeTotal
+});
+
 export const transactionApi = {
   async createTransaction(
     sessionId: number,
@@ -98,8 +176,11 @@ export const transactionApi = {
     amount: number,
     concept: string,
     categoryId: number | null,
-    createdBy: string
-  ): Promise<ApiResponse<Transaction>> {
+    createdBy: string,
+    counterparty: string | null = null,
+    lines: { category_id: number; amount: number }[] = [],
+    paymentMethod: PaymentMethod = 'cash'
+  ): Promise<ApiResponse<Transaction> & { warnings: string[] }> {
     return invoke('create_transaction', {
       request: {
         session_id: sessionId,
@@ -107,41 +188,41 @@ export const transactionApi = {
         amount,
         concept,
         category_id: categoryId,
-        created_by: createdBy
+        counterparty,
+        payment_method: paymentMethod,
+        created_by: createdBy,
+        lines
       }
     });
   },
 
-  async getTransactions(params: {
-    sessionId?: number;
-    transactionType?: string;
-    startDate?: string;
-    endDate?: string;
-    limit?: number;
-    offset?: number;
-  }): Promise<{ success: boolean; data: Transaction[]; total_count: number; error: string | null }> {
-    return invoke('get_transactions', {
-      request: {
-        session_id: params.sessionId,
-        transaction_type: params.transactionType,
-        start_date: params.startDate,
-        end_date: params.endDate,
-        limit: params.limit || 50,
-        offset: params.offset || 0
-      }
-    });
+  async getTransactions(params: TransactionQuery): Promise<{ success: boolean; data: Transaction[]; total_count: number | null; next_cursor: string | null; prev_cursor: string | null; error: string | null }> {
+    return invoke('get_transactions', { request: transactionRequest(params) });
   },
 
-  async getRecentTransactions(sessionId?: number, limit: number = 5): Promise<{ success: boolean; data: Transaction[]; total_count: number; error: string | null }> {
-    return invoke('get_recent_transactions', { sessionId, limit });
+  async getRecentTransactions(sessionId?: number, limit: number = 5, cursor?: string): Promise<{ success: boolean; data: Transaction[]; total_count: number | null; next_cursor: string | null; prev_cursor: string | null; error: string | null }> {
+    return invoke('get_recent_transactions', { sessionId, limit, cursor });
   },
 
   async getTodayTransactionsSummary(): Promise<{ success: boolean; data: any; error: string | null }> {
     return invoke('get_today_transactions_summary');
   },
 
-  async searchTransactions(query: string, limit: number = 50, offset: number = 0): Promise<{ success: boolean; data: Transaction[]; total_count: number; error: string | null }> {
-    return invoke('search_transactions', { query, limit, offset });
+  // Full-text search of `text`, narrowed by the other filters
+  async searchTransactions(params: TransactionQuery & { text: string }): Promise<{ success: boolean; data: TransactionSearchResult[]; total_count: number | null; next_cursor: string | null; prev_cursor: string | null; error: string | null }> {
+    return invoke('search_transactions', { request: transactionRequest(params) });
+  },
+
+  async getTags(): Promise<{ success: boolean; data: Tag[]; error: string | null }> {
+    return invoke('get_tags');
+  },
+
+  async addTransactionTags(transactionId: number, tags: string[]): Promise<ApiResponse<Transaction>> {
+    return invoke('add_transaction_tags', { transactionId, tags });
+  },
+
+  async removeTransactionTag(transactionId: number, tag: string): Promise<ApiResponse<Transaction>> {
+    return invoke('remove_transaction_tag', { transactionId, tag });
   },
 
   async updateTransaction(
@@ -150,16 +231,22 @@ export const transactionApi = {
       amount: number;
       concept: string;
       category_id: number | null;
+      lines?: { category_id: number; amount: number }[];
     }
   ): Promise<{ success: boolean; data: Transaction | null; error: string | null }> {
     return invoke('update_transaction', {
       transactionId,
       amount: data.amount,
       concept: data.concept,
-      categoryId: data.category_id
+      categoryId: data.category_id,
+      lines: data.lines
     });
   },
 
+  async voidTransaction(transactionId: number, performedBy?: string): Promise<{ success: boolean; data: Transaction | null; error: string | null }> {
+    return invoke('void_transaction', { transactionId, performedBy });
+  },
+
   async deleteTransaction(transactionId: number): Promise<{ success: boolean; error: string | null }> {
     return invoke('delete_transaction', { transactionId });
   }
@@ -176,25 +263,309 @@ export const categoryApi = {
   }
 };
 
+// Analytics API
+export interface SeriesPoint {
+  period: string; // 'YYYY-MM-DD' or 'YYYY-MM'
+  total_income: number;
+  total_expense: number;
+  income_count: number;
+  expense_count: number;
+}
+
+export interface CategoryStat {
+  category_id: number | null;
+  category_name: string | null;
+  transaction_type: 'income' | 'expense';
+  total: number;
+  transaction_count: number;
+  average_ticket: number;
+}
+
+export interface HeatmapCell {
+  weekday: number; // 0 = Monday ... 6 = Sunday
+  hour: number;
+  transaction_count: number;
+}
+
+export interface DashboardAnalytics {
+  start_date: string;
+  end_date: string;
+  daily: SeriesPoint[];
+  monthly: SeriesPoint[];
+  top_categories: CategoryStat[];
+  heatmap: HeatmapCell[];
+}
+
+export const analyticsApi = {
+  async getDashboardAnalytics(startDate: string, endDate: string, topN?: number): Promise<ApiResponse<DashboardAnalytics>> {
+    return invoke('get_dashboard_analytics', {
+      request: { start_date: startDate, end_date: endDate, top_n: topN }
+    });
+  }
+};
+
+export interface ClosingDifference {
+  session_id: number;
+  opened_at: string;
+  closed_at: string;
+  expected_closing: number;
+  closing_amount: number;
+  difference: number; // Counted minus expected
+}
+
+export interface OperatorPerformance {
+  operator_name: string;
+  session_count: number;
+  average_session_minutes: number | null;
+  transaction_count: number;
+  total_income: number;
+  total_expense: number;
+  total_difference: number;
+  discrepancy_count: number;
+  closings: ClosingDifference[];
+}
+
+export type ReportKind =
+  | 'transactions'
+  | 'balance'
+  | 'ledger'
+  | 'budget'
+  | 'breakdown'
+  | 'comparison'
+  | 'operators';
+
+export interface ReportFilters {
+  transaction_type?: 'income' | 'expense';
+  category_ids?: number[]; // Any of them, including their subcategories
+  tag_id?: number;
+}
+
+export interface ReportOptions {
+  currency?: string;
+  download_path?: string;
+  compare_start?: string; // Comparison: the period compared against
+  compare_end?: string;
+  growth_threshold?: number;
+}
+
+export type ReportFormat = 'pdf' | 'excel' | 'ods' | 'html' | 'markdown';
+
+export interface ReportSpec {
+  kind: ReportKind;
+  start_date: string;
+  end_date: string;
+  filters?: ReportFilters;
+  grouping?: 'category' | 'week';
+  format: ReportFormat;
+  options?: ReportOptions;
+}
+
+// Report content as previewed, and as rendered to PDF and Excel
+export type ReportColumnKind = 'text' | 'integer' | 'money' | 'percent' | 'date';
+
+export type ReportCell = number | string | null;
+
+export interface ReportColumn {
+  label: string;
+  kind: ReportColumnKind;
+  width: number;
+  indent: boolean;
+}
+
+export interface ReportRow {
+  cells: ReportCell[];
+  depth: number;
+  style: 'normal' | 'strong' | 'alert';
+  literal: boolean;
+}
+
+export interface ReportSection {
+  title: string;
+  sheet: string;
+  columns: ReportColumn[];
+  rows: ReportRow[];
+  totals: ReportRow | null;
+}
+
+export interface ReportSummaryItem {
+  label: string;
+  value: ReportCell;
+  kind: ReportColumnKind;
+  strong: boolean;
+}
+
+export interface ReportDocument {
+  title: string;
+  subtitles: string[];
+  generated_at: string;
+  summary: ReportSummaryItem[];
+  sections: ReportSection[];
+  notes: string[];
+}
+
 // Reports API
 export const reportApi = {
-  async generateReport(
-    reportType: string,
-    startDate: string,
-    endDate: string,
-    format: 'pdf' | 'excel',
-    downloadPath?: string
-  ): Promise<{ success: boolean; file_path: string | null; error: string | null }> {
-    return invoke('generate_report', {
-      reportType,
-      startDate,
-      endDate,
-      format,
-      downloadPath
+  async generateReport(spec: ReportSpec): Promise<{ success: boolean; file_path: string | null; error: string | null }> {
+    return invoke('generate_report', { spec });
+  },
+
+  async previewReport(spec: ReportSpec): Promise<{ success: boolean; data: ReportDocument | null; error: string | null }> {
+    return invoke('preview_report', { spec });
+  },
+
+  async getOperatorPerformance(startDate: string, endDate: string): Promise<{ success: boolean; data: OperatorPerformance[]; error: string | null }> {
+    return invoke('get_operator_performance', { startDate, endDate });
+  }
+};
+
+// Report templates API
+export type RelativeDateRange = 'last_month' | 'current_term' | 'year_to_date';
+
+export interface ReportTemplate {
+  id: number;
+  name: string;
+  spec: ReportSpec; // Its format is the default one of the template
+  date_range: RelativeDateRange | null; // Replaces the dates of the spec when the template runs
+  output_folder: string | null;
+  last_run_at: string | null;
+  is_active: boolean;
+}
+
+export interface SaveReportTemplateRequest {
+  name: string;
+  spec: ReportSpec;
+  date_range?: RelativeDateRange;
+  output_folder?: string;
+}
+
+export const reportTemplateApi = {
+  async createTemplate(request: SaveReportTemplateRequest): Promise<{ success: boolean; data: ReportTemplate | null; error: string | null }> {
+    return invoke('create_report_template', { request });
+  },
+
+  async updateTemplate(templateId: number, request: SaveReportTemplateRequest): Promise<{ success: boolean; data: ReportTemplate | null; error: string | null }> {
+    return invoke('update_report_template', { templateId, request });
+  },
+
+  async getTemplates(): Promise<{ success: boolean; data: ReportTemplate[]; error: string | null }> {
+    return invoke('get_report_templates');
+  },
+
+  async deleteTemplate(templateId: number): Promise<{ success: boolean; error: string | null }> {
+    return invoke('delete_report_template', { templateId });
+  },
+
+  async runTemplate(templateId: number, format?: ReportFormat, downloadPath?: string): Promise<{ success: boolean; file_path: string | null; error: string | null }> {
+    return invoke('run_report_template', { templateId, format, downloadPath });
+  }
+};
+
+// Import API
+export interface ImportColumnMapping {
+  date: string;
+  transaction_type?: string;
+  amount: string;
+  concept: string;
+  category?: string;
+  operator?: string;
+}
+
+export interface ImportRowError {
+  row: number;
+  message: string;
+}
+
+export interface ImportResult {
+  dry_run: boolean;
+  committed: boolean;
+  total_rows: number;
+  valid_rows: number;
+  session_count: number;
+  new_categories: string[];
+  errors: ImportRowError[];
+}
+
+export const importApi = {
+  async importTransactions(params: {
+    filePath: string;
+    mapping: ImportColumnMapping;
+    sheet?: string;
+    delimiter?: string;
+    dateFormat?: string;
+    decimalComma?: boolean;
+    openingAmount?: number;
+    defaultOperator?: string;
+    createCategories?: boolean;
+    dryRun: boolean;
+  }): Promise<ApiResponse<ImportResult>> {
+    return invoke('import_transactions', {
+      request: {
+        file_path: params.filePath,
+        mapping: params.mapping,
+        sheet: params.sheet,
+        delimiter: params.delimiter,
+        date_format: params.dateFormat,
+        decimal_comma: params.decimalComma,
+        opening_amount: params.openingAmount,
+        default_operator: params.defaultOperator,
+        create_categories: params.createCategories,
+        dry_run: params.dryRun
+      }
     });
   }
 };
 
+// Export API
+export interface ExportResult {
+  file_path: string;
+  row_count: number;
+}
+
+export const exportApi = {
+  async exportData(params: {
+    entity: 'transactions' | 'sessions' | 'categories' | 'audit_log';
+    format: 'csv' | 'json' | 'jsonl';
+    filter?: {
+      session_id?: number;
+      transaction_type?: string;
+      start_date?: string;
+      end_date?: string;
+      category_ids?: number[];
+      min_amount?: number;
+      max_amount?: number;
+      created_by?: string;
+      counterparty?: string;
+      text?: string;
+      tags?: string[];
+      payment_method?: PaymentMethod;
+      voided?: boolean;
+      sort_by?: string;
+      sort_direction?: 'asc' | 'desc';
+    };
+    delimiter?: string;
+    decimalComma?: boolean;
+    downloadPath?: string;
+  }): Promise<ApiResponse<ExportResult>> {
+    return invoke('export_data', {
+      request: {
+        entity: params.entity,
+        format: params.format,
+        filter: params.filter ?? {},
+        delimiter: params.delimiter,
+        decimal_comma: params.decimalComma,
+        download_path: params.downloadPath
+      }
+    });
+  }
+};
+
+// Formatters API
+export const formatApi = {
+  async amountToWords(amount: number, currency: string): Promise<ApiResponse<string>> {
+    return invoke('amount_to_words', { amount, currency });
+  }
+};
+
 // Backup API
 export interface BackupInfo {
   filename: string;
@@ -1,16 +1,21 @@
 import { Component, createSignal, createEffect, Show, For } from 'solid-js';
 import { AppLayout } from '@/components/layout';
 import { Button } from '@/components/ui';
-import { transactionApi, categoryApi, type Transaction, type Category } from '@/lib/api';
+import { transactionApi, categoryApi, type Transaction, type TransactionSearchResult, type Category } from '@/lib/api';
 import { getCurrencySymbol, config } from '@/stores/configStore';
 import { refreshSessionData } from '@/stores/sessionStore';
 import { Search, Filter, ChevronLeft, ChevronRight, MoreVertical, Edit2, Trash2, X, AlertTriangle } from 'lucide-solid';
 
 const Historial: Component = () => {
-  const [transactions, setTransactions] = createSignal<Transaction[]>([]);
+  const [transactions, setTransactions] = createSignal<(Transaction | TransactionSearchResult)[]>([]);
   const [totalCount, setTotalCount] = createSignal(0);
   const [isLoading, setIsLoading] = createSignal(false);
   const [currentPage, setCurrentPage] = createSignal(1);
+  // Keyset pagination: a page is read from the cursor of the page before or
+  // after it, and the total is only counted on the first page
+  const [pageCursor, setPageCursor] = createSignal<string | undefined>();
+  const [nextCursor, setNextCursor] = createSignal<string | null>(null);
+  const [prevCursor, setPrevCursor] = createSignal<string | null>(null);
   
   // Filters
   const [transactionNumber, setTransactionNumber] = createSignal('');
@@ -35,28 +40,38 @@ const Historial: Component = () => {
   
   const itemsPerPage = 10;
 
-  const loadTransactions = async () => {
+  const loadTransactions = async (cursor?: string, page: number = 1, includeTotal?: boolean) => {
     setIsLoading(true);
     try {
-      const offset = (currentPage() - 1) * itemsPerPage;
-      
       let response;
       
       // If searching by transaction number or concept, use search endpoint
       if (transactionNumber() || concept()) {
         const searchQuery = transactionNumber() || concept();
-        response = await transactionApi.searchTransactions(searchQuery, itemsPerPage, offset);
+        response = await transactionApi.searchTransactions({
+          text: searchQuery,
+          limit: itemsPerPage,
+          cursor,
+          includeTotal
+        });
       } else {
         // Otherwise use regular get transactions
         response = await transactionApi.getTransactions({
           limit: itemsPerPage,
-          offset: offset
+          cursor,
+          includeTotal
         });
       }
       
       if (response.success) {
         setTransactions(response.data);
-        setTotalCount(response.total_count);
+        setPageCursor(cursor);
+        setCurrentPage(page);
+        setNextCursor(response.next_cursor);
+        setPrevCursor(response.prev_cursor);
+        if (response.total_count !== null) {
+          setTotalCount(response.total_count);
+        }
       }
     } catch (err) {
       console.error('Error loading transactions:', err);
@@ -70,7 +85,6 @@ const Historial: Component = () => {
   });
 
   const handleApplyFilters = () => {
-    setCurrentPage(1);
     loadTransactions();
   };
 
@@ -78,10 +92,22 @@ const Historial: Component = () => {
     setTransactionNumber('');
     setConcept('');
     setAmountFilter('');
-    setCurrentPage(1);
     loadTransactions();
   };
 
+  const goToNextPage = () => {
+    const cursor = nextCursor();
+    if (cursor) loadTransactions(cursor, currentPage() + 1);
+  };
+
+  const goToPrevPage = () => {
+    const cursor = prevCursor();
+    if (cursor) loadTransactions(cursor, currentPage() - 1);
+  };
+
+  // Reload the page on screen after a change, counting again
+  const reloadPage = () => loadTransactions(pageCursor(), currentPage(), true);
+
   const totalPages = () => Math.ceil(totalCount() / itemsPerPage);
   const showingFrom = () => (currentPage() - 1) * itemsPerPage + 1;
   const showingTo = () => Math.min(currentPage() * itemsPerPage, totalCount());
@@ -102,26 +128,6 @@ const Historial: Component = () => {
     });
   };
 
-  const getPageNumbers = () => {
-    const total = totalPages();
-    const current = currentPage();
-    const pages: (number | string)[] = [];
-
-    if (total <= 7) {
-      for (let i = 1; i <= total; i++) pages.push(i);
-    } else {
-      if (current <= 3) {
-        pages.push(1, 2, 3, '...', total);
-      } else if (current >= total - 2) {
-        pages.push(1, '...', total - 2, total - 1, total);
-      } else {
-        pages.push(1, '...', current - 1, current, current + 1, '...', total);
-      }
-    }
-
-    return pages;
-  };
-
   const handleEdit = async (transaction: Transaction) => {
     setEditingTransaction(transaction);
     setEditAmount(transaction.amount.toString());
@@ -165,7 +171,7 @@ const Historial: Component = () => {
       
       if (response.success) {
         setShowEditModal(false);
-        await loadTransactions();
+        await reloadPage();
         await refreshSessionData();
         console.log('Transacción actualizada exitosamente');
       } else {
@@ -198,7 +204,7 @@ const Historial: Component = () => {
       
       if (response.success) {
         setShowDeleteConfirm(false);
-        await loadTransactions();
+        await reloadPage();
         await refreshSessionData();
         console.log('Transacción eliminada exitosamente');
       } else {
@@ -344,6 +350,12 @@ const Historial: Component = () => {
                           </td>
                           <td class="px-6 py-4 text-sm text-gray-900">
                             {transaction.concept}
+                            <Show when={'snippet' in transaction && transaction.snippet}>
+                              <p
+                                class="mt-1 text-xs text-gray-500 [&_mark]:bg-yellow-100"
+                                innerHTML={(transaction as TransactionSearchResult).snippet}
+                              />
+                            </Show>
                           </td>
                           <td class={`px-6 py-4 whitespace-nowrap text-sm font-semibold ${
                             transaction.transaction_type === 'income' 
@@ -408,36 +420,20 @@ const Historial: Component = () => {
                 
                 <div class="flex items-center gap-2">
                   <button
-                    onClick={() => setCurrentPage(p => Math.max(1, p - 1))}
-                    disabled={currentPage() === 1}
+                    onClick={goToPrevPage}
+                    disabled={!prevCursor() || isLoading()}
                     class="p-2 hover:bg-gray-100 rounded-lg disabled:opacity-50 disabled:cursor-not-allowed transition-colors"
                   >
                     <ChevronLeft class="w-5 h-5 text-gray-600" />
                   </button>
                   
-                  <For each={getPageNumbers()}>
-                    {(page) => (
-                      <Show 
-                        when={page !== '...'} 
-                        fallback={<span class="px-3 py-2 text-gray-400">...</span>}
-                      >
-                        <button
-                          onClick={() => setCurrentPage(page as number)}
-                          class={`px-3 py-2 rounded-lg text-sm font-medium transition-colors ${
-                            currentPage() === page
-                              ? 'bg-blue-600 text-white'
-                              : 'text-gray-600 hover:bg-gray-100'
-                          }`}
-                        >
-                          {page}
-                        </button>
-                      </Show>
-                    )}
-                  </For>
+                  <span class="px-3 py-2 text-sm font-medium text-gray-600">
+                    Página {currentPage()} de {totalPages()}
+                  </span>
                   
                   <button
-                    onClick={() => setCurrentPage(p => Math.min(totalPages(), p + 1))}
-                    disabled={currentPage() === totalPages()}
+                    onClick={goToNextPage}
+                    disabled={!nextCursor() || isLoading()}
                     class="p-2 hover:bg-gray-100 rounded-lg disabled:opacity-50 disabled:cursor-not-allowed transition-colors"
                   >
                     <ChevronRight class="w-5 h-5 text-gray-600" />
@@ -1,14 +1,17 @@
-import { Component, createSignal, Show, For, createEffect } from 'solid-js';
+import { Component, createSignal, Show, For, createEffect, onMount } from 'solid-js';
 import { AppLayout } from '@/components/layout';
 import { Button } from '@/components/ui';
-import { reportApi } from '@/lib/api';
+import { reportApi, reportTemplateApi } from '@/lib/api';
 import { config } from '@/stores/configStore';
 import { invoke } from '@tauri-apps/api/core';
-import type { Category } from '@/lib/api';
+import type { Category, RelativeDateRange, ReportCell, ReportColumnKind, ReportDocument, ReportFormat, ReportSpec, ReportTemplate } from '@/lib/api';
 import { 
   TrendingUp, 
   TrendingDown, 
   Scale,
+  BookOpen,
+  PieChart,
+  Users,
   Tags,
   ArrowRight,
   FileText,
@@ -16,9 +19,47 @@ import {
   Download,
   X,
   ChevronDown,
-  Filter
+  Filter,
+  Eye,
+  Save,
+  Play,
+  Trash2
 } from 'lucide-solid';
 
+// What a card asks for; dates, format and options come from the modal
+type ReportDefinition = Pick<ReportSpec, 'kind' | 'filters' | 'grouping'>;
+
+const formatOptions: { value: ReportFormat; label: string; activeClass: string }[] = [
+  { value: 'pdf', label: 'PDF', activeClass: 'border-blue-600 bg-blue-50 text-blue-700' },
+  { value: 'excel', label: 'Excel', activeClass: 'border-green-600 bg-green-50 text-green-700' },
+  { value: 'ods', label: 'ODS', activeClass: 'border-teal-600 bg-teal-50 text-teal-700' },
+  { value: 'html', label: 'HTML', activeClass: 'border-orange-600 bg-orange-50 text-orange-700' },
+  { value: 'markdown', label: 'Markdown', activeClass: 'border-gray-600 bg-gray-50 text-gray-700' }
+];
+
+const formatLabel = (format: ReportFormat) =>
+  formatOptions.find(option => option.value === format)?.label ?? format;
+
+const dateRangeLabels: Record<RelativeDateRange, string> = {
+  last_month: 'Mes anterior',
+  current_term: 'Periodo actual',
+  year_to_date: 'Año a la fecha'
+};
+
+// Cells of the preview, formatted like the PDF
+const formatReportCell = (cell: ReportCell, kind: ReportColumnKind): string => {
+  if (cell === null) return '';
+  if (typeof cell === 'string') return cell;
+  switch (kind) {
+    case 'money':
+      return cell.toLocaleString('es', { minimumFractionDigits: 2, maximumFractionDigits: 2 });
+    case 'percent':
+      return `${(cell * 100).toFixed(1)}%`;
+    default:
+      return cell.toFixed(0);
+  }
+};
+
 interface ReportCardProps {
   title: string;
   description: string;
@@ -60,27 +101,35 @@ const Reportes: Component = () => {
   const [selectedReport, setSelectedReport] = createSignal<string | null>(null);
   const [startDate, setStartDate] = createSignal('');
   const [endDate, setEndDate] = createSignal('');
-  const [reportFormat, setReportFormat] = createSignal<'pdf' | 'excel'>('pdf');
+  const [reportFormat, setReportFormat] = createSignal<ReportFormat>('pdf');
   const [isGenerating, setIsGenerating] = createSignal(false);
+  const [preview, setPreview] = createSignal<ReportDocument | null>(null);
+  const [isPreviewing, setIsPreviewing] = createSignal(false);
+
+  // Saved report templates
+  const [templates, setTemplates] = createSignal<ReportTemplate[]>([]);
+  const [templateRange, setTemplateRange] = createSignal<RelativeDateRange | ''>('');
+  const [runningTemplateId, setRunningTemplateId] = createSignal<number | null>(null);
   
   // Category report states
   const [incomeCategories, setIncomeCategories] = createSignal<Category[]>([]);
   const [expenseCategories, setExpenseCategories] = createSignal<Category[]>([]);
   const [selectedCategoryId, setSelectedCategoryId] = createSignal<number | null>(null);
   const [selectedCategoryType, setSelectedCategoryType] = createSignal<'income' | 'expense'>('income');
-  const [categoryReportFormat, setCategoryReportFormat] = createSignal<'pdf' | 'excel'>('pdf');
+  const [categoryReportFormat, setCategoryReportFormat] = createSignal<ReportFormat>('pdf');
   const [categoryStartDate, setCategoryStartDate] = createSignal('');
   const [categoryEndDate, setCategoryEndDate] = createSignal('');
   const [isGeneratingCategory, setIsGeneratingCategory] = createSignal(false);
 
-  const reports = [
+  const reports: (Omit<ReportCardProps, 'onGenerate'> & { spec: ReportDefinition })[] = [
     {
       title: 'Reporte de Ingresos del Dia',
       description: 'Detalle pormenorizado de todas las ventas, abonos y entradas de efectivo registradas durante la jornada actual.',
       icon: TrendingUp,
       iconBgColor: 'bg-green-100',
       iconColor: 'text-green-600',
-      reportType: 'income'
+      reportType: 'income',
+      spec: { kind: 'transactions', filters: { transaction_type: 'income' } }
     },
     {
       title: 'Reporte de Egresos del Dia',
@@ -88,18 +137,69 @@ const Reportes: Component = () => {
       icon: TrendingDown,
       iconBgColor: 'bg-red-100',
       iconColor: 'text-red-600',
-      reportType: 'expense'
+      reportType: 'expense',
+      spec: { kind: 'transactions', filters: { transaction_type: 'expense' } }
     },
     {
       title: 'Balance Diario Consolidado',
-      description: 'Comparativa entre ingresos y egresos para determinar la utilidad neta y el saldo final de caja al cierre.',
+      description: 'Saldos de apertura y cierre de cada sesion del periodo, con el movimiento consolidado de ingresos y egresos.',
       icon: Scale,
       iconBgColor: 'bg-blue-100',
       iconColor: 'text-blue-600',
-      reportType: 'balance'
+      reportType: 'balance',
+      spec: { kind: 'balance' }
+    },
+    {
+      title: 'Resumen Semanal',
+      description: 'Ingresos, egresos y neto agrupados por semana, con el desglose por categoria de cada una.',
+      icon: Calendar,
+      iconBgColor: 'bg-purple-100',
+      iconColor: 'text-purple-600',
+      reportType: 'weekly',
+      spec: { kind: 'transactions', grouping: 'week' }
+    },
+    {
+      title: 'Distribucion por Categoria',
+      description: 'Ingresos y egresos agrupados por categoria, con su participacion en el total, graficos y la variacion frente al periodo anterior.',
+      icon: PieChart,
+      iconBgColor: 'bg-teal-100',
+      iconColor: 'text-teal-600',
+      reportType: 'breakdown',
+      spec: { kind: 'breakdown' }
+    },
+    {
+      title: 'Desempeno por Operador',
+      description: 'Sesiones, duracion promedio, montos manejados e historial de diferencias de cierre de cada operador.',
+      icon: Users,
+      iconBgColor: 'bg-indigo-100',
+      iconColor: 'text-indigo-600',
+      reportType: 'operators',
+      spec: { kind: 'operators' }
+    },
+    {
+      title: 'Libro de Caja',
+      description: 'Movimientos en orden cronologico con el saldo despues de cada uno, partiendo del monto de apertura de cada sesion y con el saldo que pasa al dia siguiente.',
+      icon: BookOpen,
+      iconBgColor: 'bg-amber-100',
+      iconColor: 'text-amber-600',
+      reportType: 'ledger',
+      spec: { kind: 'ledger' }
     }
   ];
 
+  const loadTemplates = async () => {
+    try {
+      const response = await reportTemplateApi.getTemplates();
+      if (response.success) {
+        setTemplates(response.data);
+      }
+    } catch (err) {
+      console.error('Error loading report templates:', err);
+    }
+  };
+
+  onMount(loadTemplates);
+
   // Load categories when category modal opens
   createEffect(() => {
     if (showCategoryModal()) {
@@ -134,23 +234,56 @@ const Reportes: Component = () => {
     setShowModal(true);
   };
 
+  const buildSpec = (): ReportSpec | null => {
+    const report = reports.find(r => r.reportType === selectedReport());
+    if (!report || !startDate() || !endDate()) return null;
+
+    return {
+      ...report.spec,
+      start_date: startDate(),
+      end_date: endDate(),
+      format: reportFormat(),
+      options: {
+        currency: config().currency,
+        download_path: config().downloadPath || undefined
+      }
+    };
+  };
+
+  const handlePreview = async () => {
+    const spec = buildSpec();
+    if (!spec) return;
+
+    setIsPreviewing(true);
+
+    try {
+      const response = await reportApi.previewReport(spec);
+
+      if (response.success && response.data) {
+        setPreview(response.data);
+      } else {
+        alert(`Error al generar vista previa: ${response.error || 'Error desconocido'}`);
+      }
+    } catch (err) {
+      alert(`Error al generar vista previa: ${err}`);
+    } finally {
+      setIsPreviewing(false);
+    }
+  };
+
   const handleConfirmGenerate = async () => {
-    if (!selectedReport() || !startDate() || !endDate()) return;
+    const spec = buildSpec();
+    if (!spec) return;
 
     setIsGenerating(true);
     
     try {
-      const response = await reportApi.generateReport(
-        selectedReport()!,
-        startDate(),
-        endDate(),
-        reportFormat(),
-        config().downloadPath || undefined
-      );
+      const response = await reportApi.generateReport(spec);
       
       if (response.success && response.file_path) {
         alert(`Reporte generado exitosamente!\n\nArchivo guardado en:\n${response.file_path}`);
         setShowModal(false);
+        setPreview(null);
       } else {
         alert(`Error al generar reporte: ${response.error || 'Error desconocido'}`);
       }
@@ -161,6 +294,67 @@ const Reportes: Component = () => {
     }
   };
 
+  const handleSaveTemplate = async () => {
+    const spec = buildSpec();
+    if (!spec) return;
+
+    const name = prompt('Nombre de la plantilla:', getReportTitle());
+    if (!name?.trim()) return;
+
+    try {
+      const response = await reportTemplateApi.createTemplate({
+        name,
+        spec,
+        date_range: templateRange() || undefined,
+        output_folder: config().downloadPath || undefined
+      });
+
+      if (response.success) {
+        await loadTemplates();
+        alert('Plantilla guardada');
+      } else {
+        alert(`Error al guardar plantilla: ${response.error || 'Error desconocido'}`);
+      }
+    } catch (err) {
+      alert(`Error al guardar plantilla: ${err}`);
+    }
+  };
+
+  const handleRunTemplate = async (template: ReportTemplate) => {
+    setRunningTemplateId(template.id);
+
+    try {
+      const response = await reportTemplateApi.runTemplate(template.id, undefined, config().downloadPath || undefined);
+
+      if (response.success && response.file_path) {
+        alert(`Reporte generado exitosamente!\n\nArchivo guardado en:\n${response.file_path}`);
+        await loadTemplates();
+      } else {
+        alert(`Error al generar reporte: ${response.error || 'Error desconocido'}`);
+      }
+    } catch (err) {
+      alert(`Error al generar reporte: ${err}`);
+    } finally {
+      setRunningTemplateId(null);
+    }
+  };
+
+  const handleDeleteTemplate = async (template: ReportTemplate) => {
+    if (!confirm(`¿Eliminar la plantilla "${template.name}"?`)) return;
+
+    try {
+      const response = await reportTemplateApi.deleteTemplate(template.id);
+
+      if (response.success) {
+        await loadTemplates();
+      } else {
+        alert(`Error al eliminar plantilla: ${response.error || 'Error desconocido'}`);
+      }
+    } catch (err) {
+      alert(`Error al eliminar plantilla: ${err}`);
+    }
+  };
+
   const handleCategoryReportClick = () => {
     setShowCategoryModal(true);
   };
@@ -174,16 +368,20 @@ const Reportes: Component = () => {
     setIsGeneratingCategory(true);
     
     try {
-      // Format: category_{id}_{type}
-      const reportType = `category_${selectedCategoryId()}_${selectedCategoryType()}`;
-      
-      const response = await reportApi.generateReport(
-        reportType,
-        categoryStartDate(),
-        categoryEndDate(),
-        categoryReportFormat(),
-        config().downloadPath || undefined
-      );
+      const response = await reportApi.generateReport({
+        kind: 'transactions',
+        start_date: categoryStartDate(),
+        end_date: categoryEndDate(),
+        filters: {
+          category_ids: [selectedCategoryId()!],
+          transaction_type: selectedCategoryType()
+        },
+        format: categoryReportFormat(),
+        options: {
+          currency: config().currency,
+          download_path: config().downloadPath || undefined
+        }
+      });
       
       if (response.success && response.file_path) {
         alert(`Reporte generado exitosamente!\n\nArchivo guardado en:\n${response.file_path}`);
@@ -263,13 +461,57 @@ const Reportes: Component = () => {
           </div>
         </div>
 
+        {/* Saved Templates */}
+        <Show when={templates().length > 0}>
+          <div>
+            <h3 class="text-lg font-semibold text-gray-900 mb-3">Plantillas Guardadas</h3>
+            <div class="bg-white rounded-xl border border-gray-200 shadow-sm divide-y divide-gray-100">
+              <For each={templates()}>
+                {(template) => (
+                  <div class="flex items-center justify-between gap-4 px-6 py-4">
+                    <div>
+                      <p class="font-medium text-gray-900">{template.name}</p>
+                      <p class="text-sm text-gray-500">
+                        {template.date_range
+                          ? dateRangeLabels[template.date_range]
+                          : `${template.spec.start_date} al ${template.spec.end_date}`}
+                        {' · '}
+                        {formatLabel(template.spec.format)}
+                        <Show when={template.last_run_at}>
+                          {' · '}Generado por ultima vez el {template.last_run_at}
+                        </Show>
+                      </p>
+                    </div>
+                    <div class="flex items-center gap-2">
+                      <button
+                        onClick={() => handleRunTemplate(template)}
+                        disabled={runningTemplateId() !== null}
+                        class="text-blue-600 hover:text-blue-700 text-sm font-semibold uppercase tracking-wide flex items-center gap-1 transition-colors disabled:opacity-50"
+                      >
+                        <Play class="w-4 h-4" />
+                        {runningTemplateId() === template.id ? 'Generando...' : 'Generar'}
+                      </button>
+                      <button
+                        onClick={() => handleDeleteTemplate(template)}
+                        class="p-2 hover:bg-gray-100 rounded-lg transition-colors"
+                      >
+                        <Trash2 class="w-4 h-4 text-gray-500" />
+                      </button>
+                    </div>
+                  </div>
+                )}
+              </For>
+            </div>
+          </div>
+        </Show>
+
         {/* Info Alert */}
         <div class="p-4 bg-gray-50 border border-gray-200 rounded-lg flex items-start gap-3">
           <div class="w-8 h-8 bg-gray-200 rounded-full flex items-center justify-center flex-shrink-0">
             <FileText class="w-4 h-4 text-gray-600" />
           </div>
           <p class="text-sm text-gray-600">
-            Todos los reportes se generan en formato PDF y Excel para su descarga. 
+            Todos los reportes se generan en PDF, Excel, ODS, HTML o Markdown para su descarga. 
             Los datos se actualizan en tiempo real conforme se registran transacciones en la seccion de Caja.
           </p>
         </div>
@@ -333,30 +575,41 @@ const Reportes: Component = () => {
                     Formato de Exportacion
                   </span>
                 </label>
-                <div class="flex gap-4">
-                  <button
-                    onClick={() => setReportFormat('pdf')}
-                    class={`flex-1 py-3 px-4 rounded-lg border-2 font-medium transition-all ${
-                      reportFormat() === 'pdf'
-                        ? 'border-blue-600 bg-blue-50 text-blue-700'
-                        : 'border-gray-200 hover:border-gray-300'
-                    }`}
-                  >
-                    PDF
-                  </button>
-                  <button
-                    onClick={() => setReportFormat('excel')}
-                    class={`flex-1 py-3 px-4 rounded-lg border-2 font-medium transition-all ${
-                      reportFormat() === 'excel'
-                        ? 'border-green-600 bg-green-50 text-green-700'
-                        : 'border-gray-200 hover:border-gray-300'
-                    }`}
-                  >
-                    Excel
-                  </button>
+                <div class="grid grid-cols-5 gap-2">
+                  <For each={formatOptions}>
+                    {(option) => (
+                      <button
+                        onClick={() => setReportFormat(option.value)}
+                        class={`py-3 px-2 rounded-lg border-2 text-sm font-medium transition-all ${
+                          reportFormat() === option.value
+                            ? option.activeClass
+                            : 'border-gray-200 hover:border-gray-300'
+                        }`}
+                      >
+                        {option.label}
+                      </button>
+                    )}
+                  </For>
                 </div>
               </div>
 
+              {/* Period used when the report is saved as a template */}
+              <div>
+                <label class="block text-sm font-medium text-gray-700 mb-2">
+                  Periodo de la plantilla
+                </label>
+                <select
+                  value={templateRange()}
+                  onChange={(e) => setTemplateRange(e.currentTarget.value as RelativeDateRange | '')}
+                  class="w-full px-3 py-2 border border-gray-300 rounded-lg focus:ring-2 focus:ring-blue-500 focus:border-blue-500 outline-none bg-white"
+                >
+                  <option value="">Fechas seleccionadas</option>
+                  <For each={Object.entries(dateRangeLabels)}>
+                    {([value, label]) => <option value={value}>{label}</option>}
+                  </For>
+                </select>
+              </div>
+
               {/* Action Buttons */}
               <div class="space-y-3 pt-4">
                 <Button
@@ -376,6 +629,28 @@ const Reportes: Component = () => {
                     </span>
                   )}
                 </Button>
+                <Button
+                  variant="outline"
+                  onClick={handlePreview}
+                  disabled={isPreviewing() || !startDate() || !endDate()}
+                  class="w-full py-3"
+                >
+                  <span class="flex items-center gap-2">
+                    <Eye class="w-4 h-4" />
+                    {isPreviewing() ? 'Cargando...' : 'Vista previa'}
+                  </span>
+                </Button>
+                <Button
+                  variant="outline"
+                  onClick={handleSaveTemplate}
+                  disabled={!startDate() || !endDate()}
+                  class="w-full py-3"
+                >
+                  <span class="flex items-center gap-2">
+                    <Save class="w-4 h-4" />
+                    Guardar como plantilla
+                  </span>
+                </Button>
                 <Button
                   variant="outline"
                   onClick={() => setShowModal(false)}
@@ -390,6 +665,116 @@ const Reportes: Component = () => {
         </div>
       </Show>
 
+      {/* Report Preview */}
+      <Show when={preview()}>
+        {(report) => (
+          <div class="fixed inset-0 bg-black/50 flex items-center justify-center z-50">
+            <div class="bg-white rounded-xl p-8 max-w-5xl w-full mx-4 max-h-[90vh] overflow-y-auto">
+              <div class="flex items-start justify-between mb-6">
+                <div>
+                  <h2 class="text-xl font-bold text-gray-900">{report().title}</h2>
+                  <For each={report().subtitles}>
+                    {(subtitle) => <p class="text-sm text-gray-500">{subtitle}</p>}
+                  </For>
+                </div>
+                <button
+                  onClick={() => setPreview(null)}
+                  class="p-2 hover:bg-gray-100 rounded-lg transition-colors"
+                >
+                  <X class="w-5 h-5 text-gray-500" />
+                </button>
+              </div>
+
+              <Show when={report().summary.length > 0}>
+                <div class="grid grid-cols-2 gap-x-8 gap-y-1 mb-6 text-sm">
+                  <For each={report().summary}>
+                    {(item) => (
+                      <div class={`flex justify-between gap-4 ${item.strong ? 'font-semibold' : ''}`}>
+                        <span class="text-gray-600">{item.label}</span>
+                        <span class="text-gray-900 text-right">{formatReportCell(item.value, item.kind)}</span>
+                      </div>
+                    )}
+                  </For>
+                </div>
+              </Show>
+
+              <For each={report().sections}>
+                {(section) => (
+                  <div class="mb-6">
+                    <h3 class="text-sm font-semibold text-gray-900 uppercase tracking-wide mb-2">{section.title}</h3>
+                    <div class="overflow-x-auto border border-gray-200 rounded-lg">
+                      <table class="w-full text-xs">
+                        <thead class="bg-gray-50">
+                          <tr>
+                            <For each={section.columns}>
+                              {(column) => (
+                                <th class={`px-3 py-2 font-semibold text-gray-700 ${column.kind === 'text' ? 'text-left' : 'text-right'}`}>
+                                  {column.label}
+                                </th>
+                              )}
+                            </For>
+                          </tr>
+                        </thead>
+                        <tbody>
+                          <Show when={section.rows.length > 0} fallback={
+                            <tr>
+                              <td colSpan={section.columns.length} class="px-3 py-4 text-center text-gray-500">Sin movimientos</td>
+                            </tr>
+                          }>
+                            <For each={[...section.rows, ...(section.totals ? [section.totals] : [])]}>
+                              {(row) => (
+                                <tr class={`border-t border-gray-100 ${
+                                  row.style === 'alert' ? 'bg-red-50 text-red-700 font-semibold' : row.style === 'strong' ? 'font-semibold' : ''
+                                } ${row === section.totals ? 'border-gray-300 font-semibold' : ''}`}>
+                                  <For each={section.columns}>
+                                    {(column, index) => (
+                                      <td
+                                        class={`px-3 py-1.5 whitespace-nowrap ${column.kind === 'text' ? 'text-left' : 'text-right'}`}
+                                        style={column.indent ? { 'padding-left': `${0.75 + row.depth}rem` } : undefined}
+                                      >
+                                        {formatReportCell(row.cells[index()], column.kind)}
+                                      </td>
+                                    )}
+                                  </For>
+                                </tr>
+                              )}
+                            </For>
+                          </Show>
+                        </tbody>
+                      </table>
+                    </div>
+                  </div>
+                )}
+              </For>
+
+              <For each={report().notes}>
+                {(note) => <p class="text-xs text-gray-500 mb-1">{note}</p>}
+              </For>
+
+              <div class="flex gap-3 pt-4">
+                <Button
+                  onClick={handleConfirmGenerate}
+                  disabled={isGenerating()}
+                  class="flex-1 py-3"
+                >
+                  <span class="flex items-center gap-2">
+                    <Download class="w-4 h-4" />
+                    {isGenerating() ? 'Generando...' : `Descargar ${formatLabel(reportFormat())}`}
+                  </span>
+                </Button>
+                <Button
+                  variant="outline"
+                  onClick={() => setPreview(null)}
+                  class="flex-1 py-3"
+                >
+                  Cerrar
+                </Button>
+              </div>
+            </div>
+          </div>
+        )}
+      </Show>
+
       {/* Category Report Modal */}
       <Show when={showCategoryModal()}>
         <div class="fixed inset-0 bg-black/50 flex items-center justify-center z-50">
@@ -516,27 +901,21 @@ const Reportes: Component = () => {
                     Formato de Exportacion
                   </span>
                 </label>
-                <div class="flex gap-4">
-                  <button
-                    onClick={() => setCategoryReportFormat('pdf')}
-                    class={`flex-1 py-3 px-4 rounded-lg border-2 font-medium transition-all ${
-                      categoryReportFormat() === 'pdf'
-                        ? 'border-blue-600 bg-blue-50 text-blue-700'
-                        : 'border-gray-200 hover:border-gray-300'
-                    }`}
-                  >
-                    PDF
-                  </button>
-                  <button
-                    onClick={() => setCategoryReportFormat('excel')}
-                    class={`flex-1 py-3 px-4 rounded-lg border-2 font-medium transition-all ${
-                      categoryReportFormat() === 'excel'
-                        ? 'border-green-600 bg-green-50 text-green-700'
-                        : 'border-gray-200 hover:border-gray-300'
-                    }`}
-                  >
-                    Excel
-                  </button>
+                <div class="grid grid-cols-5 gap-2">
+                  <For each={formatOptions}>
+                    {(option) => (
+                      <button
+                        onClick={() => setCategoryReportFormat(option.value)}
+                        class={`py-3 px-2 rounded-lg border-2 text-sm font-medium transition-all ${
+                          categoryReportFormat() === option.value
+                            ? option.activeClass
+                            : 'border-gray-200 hover:border-gray-300'
+                        }`}
+                      >
+                        {option.label}
+                      </button>
+                    )}
+                  </For>
                 </div>
               </div>
 